@@ -0,0 +1,8 @@
+// renders the "materials" preset to out/materials.png at full quality:
+// cargo run --release --example materials
+use ray_tracer::{presets, scene::Scene};
+
+fn main() {
+    let (camera, objects) = presets::materials();
+    Scene::new(camera, objects, "out/materials.png".to_string()).render();
+}
@@ -0,0 +1,8 @@
+// renders the "shapes" preset to out/shapes.png at full quality:
+// cargo run --release --example shapes
+use ray_tracer::{presets, scene::Scene};
+
+fn main() {
+    let (camera, objects) = presets::shapes();
+    Scene::new(camera, objects, "out/shapes.png".to_string()).render();
+}
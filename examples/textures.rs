@@ -0,0 +1,8 @@
+// renders the "textures" preset to out/textures.png at full quality:
+// cargo run --release --example textures
+use ray_tracer::{presets, scene::Scene};
+
+fn main() {
+    let (camera, objects) = presets::textures();
+    Scene::new(camera, objects, "out/textures.png".to_string()).render();
+}
@@ -0,0 +1,125 @@
+use crate::ray::{Ray, RayPacket};
+use crate::vector::Vector;
+
+// axis-aligned bounding box
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Aabb {
+    pub fn new(min: Vector, max: Vector) -> Self {
+        Self { min, max }
+    }
+
+    // an inverted box that any point or box will grow
+    pub fn empty() -> Self {
+        Self {
+            min: Vector(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Vector(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn surrounding(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Vector(
+                self.min.x().min(other.min.x()),
+                self.min.y().min(other.min.y()),
+                self.min.z().min(other.min.z()),
+            ),
+            max: Vector(
+                self.max.x().max(other.max.x()),
+                self.max.y().max(other.max.y()),
+                self.max.z().max(other.max.z()),
+            ),
+        }
+    }
+
+    pub fn grow(&self, p: Vector) -> Aabb {
+        self.surrounding(&Aabb::new(p, p))
+    }
+
+    pub fn centroid(&self) -> Vector {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn extent(&self) -> Vector {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let e = self.extent();
+        2.0 * (e.x() * e.y() + e.y() * e.z() + e.z() * e.x())
+    }
+
+    // index of the longest axis: 0 = x, 1 = y, 2 = z
+    pub fn longest_axis(&self) -> usize {
+        let e = self.extent();
+        if e.x() > e.y() && e.x() > e.z() {
+            0
+        } else if e.y() > e.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    // slab test, returns true when the ray enters the box within [t_min, t_max]
+    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.hit_range(r, t_min, t_max).is_some()
+    }
+
+    // the part of [t_min, t_max] for which the ray is inside the box
+    pub fn hit_range(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let mut t0 = t_min;
+        let mut t1 = t_max;
+
+        for axis in 0..3 {
+            let inv_d = 1.0 / r.direction.axis(axis);
+            let mut near = (self.min.axis(axis) - r.origin.axis(axis)) * inv_d;
+            let mut far = (self.max.axis(axis) - r.origin.axis(axis)) * inv_d;
+
+            if inv_d < 0.0 {
+                std::mem::swap(&mut near, &mut far);
+            }
+
+            t0 = if near > t0 { near } else { t0 };
+            t1 = if far < t1 { far } else { t1 };
+
+            if t1 < t0 {
+                return None;
+            }
+        }
+
+        Some((t0, t1))
+    }
+
+    // slab test for every lane of a packet against its own [t_min, t_max[lane]]
+    pub fn hit_packet(&self, packet: &RayPacket, t_min: f64, t_max: &[f64; 4]) -> [bool; 4] {
+        let mut t0 = [t_min; 4];
+        let mut t1 = *t_max;
+
+        for axis in 0..3 {
+            let (lo, hi) = (self.min.axis(axis), self.max.axis(axis));
+            let origin = &packet.origin[axis];
+            let inv_d = &packet.inv_direction[axis];
+
+            for lane in 0..4 {
+                let a = (lo - origin[lane]) * inv_d[lane];
+                let b = (hi - origin[lane]) * inv_d[lane];
+                let (near, far) = if inv_d[lane] < 0.0 { (b, a) } else { (a, b) };
+
+                t0[lane] = if near > t0[lane] { near } else { t0[lane] };
+                t1[lane] = if far < t1[lane] { far } else { t1[lane] };
+            }
+        }
+
+        [
+            t0[0] <= t1[0],
+            t0[1] <= t1[1],
+            t0[2] <= t1[2],
+            t0[3] <= t1[3],
+        ]
+    }
+}
@@ -0,0 +1,68 @@
+use crate::bvh::{Bvh, BvhBuildOptions};
+use crate::grid::Grid;
+use crate::hittable::{Hit, Hittable};
+use crate::kdtree::KdTree;
+use crate::ray::RayPacket;
+
+// spatial index the scene traces rays against
+pub trait Accelerator: Hittable {
+    // number of primitives indexed
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // closest hit for each ray of a coherent packet (e.g. primary rays of one
+    // pixel), structures that support it share node visits between the rays
+    fn intersect_packet(&self, packet: &RayPacket, t_min: f64, t_max: f64) -> [Option<Hit>; 4] {
+        let [a, b, c, d] = &packet.rays;
+        [
+            self.ray_intersect(a, t_min, t_max),
+            self.ray_intersect(b, t_min, t_max),
+            self.ray_intersect(c, t_min, t_max),
+            self.ray_intersect(d, t_min, t_max),
+        ]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum AcceleratorKind {
+    Bvh(BvhBuildOptions),
+    // a BVH read from the cache file at `path` when it was built from the same
+    // objects, built and saved there otherwise; see `Bvh::load_or_build`
+    CachedBvh {
+        options: BvhBuildOptions,
+        path: String,
+    },
+    KdTree,
+    Grid,
+}
+
+impl AcceleratorKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bvh" => Some(AcceleratorKind::default()),
+            "kdtree" | "kd-tree" => Some(AcceleratorKind::KdTree),
+            "grid" => Some(AcceleratorKind::Grid),
+            _ => None,
+        }
+    }
+}
+
+impl Default for AcceleratorKind {
+    fn default() -> Self {
+        AcceleratorKind::Bvh(BvhBuildOptions::default())
+    }
+}
+
+pub fn build(kind: AcceleratorKind, objects: Vec<Box<dyn Hittable>>) -> Box<dyn Accelerator> {
+    match kind {
+        AcceleratorKind::Bvh(options) => Box::new(Bvh::with_options(objects, options)),
+        AcceleratorKind::CachedBvh { options, path } => {
+            Box::new(Bvh::load_or_build(objects, options, &path))
+        }
+        AcceleratorKind::KdTree => Box::new(KdTree::new(objects)),
+        AcceleratorKind::Grid => Box::new(Grid::new(objects)),
+    }
+}
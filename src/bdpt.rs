@@ -0,0 +1,570 @@
+use crate::hittable::Hit;
+use crate::integrator::Integrator;
+use crate::light::Light;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::utils::random_in_range;
+use crate::vector::Vector;
+
+// longest eye or light subpath, counting the camera or light it starts on.
+// Every eye vertex connects to every light vertex, so the cost grows with the
+// square of this
+const MAX_SUBPATH_VERTICES: usize = 8;
+
+// bidirectional path tracing (Veach): a path from the camera and one from a
+// light, every eye vertex joined to every light vertex by a shadow ray, each
+// way of building the same path weighted by the power heuristic. Light that
+// only reaches the camera through glass or off small bright sources is found
+// from the light's side, where unidirectional path tracing barely ever hits
+// it. Light paths that went through glass or off mirrors are also joined to
+// pinhole cameras and land in whatever pixel they project to (see
+// `Scene::splat`), which is what renders caustics seen directly; elsewhere
+// the camera's stratified samples do better. Directional lights, the
+// environment and emissive objects are only found from the eye side.
+// Materials without a BRDF, such as rough `Metal`, are treated as mirrors, and
+// as they don't reflect the same both ways, come out slightly differently
+// than with `PathTracer`. Ignores light linking and the caustic photon map
+pub struct Bidirectional;
+
+enum Kind {
+    Camera,
+    // `ray` is the one that arrived at `hit`
+    Surface { hit: Hit, ray: Ray },
+    // a point on one of the scene's lights; `radiance` is what it sends back
+    // along an eye path that hit it
+    Light { index: usize, radiance: Vector },
+}
+
+struct Vertex {
+    kind: Kind,
+    p: Vector,
+    // zero for the camera and point lights
+    normal: Vector,
+    // throughput of the subpath up to here, over the densities it was sampled with
+    beta: Vector,
+    // mirror-like surfaces, which no connection can go through
+    delta: bool,
+    // area densities of this vertex being sampled from the vertex before it on
+    // its own subpath, and from the one after it going the other way
+    pdf_fwd: f64,
+    pdf_rev: f64,
+}
+
+impl Vertex {
+    // area density at `next` of this vertex sampling a direction toward it,
+    // having been reached from `prev`
+    fn pdf(&self, scene: &Scene, prev: Option<&Vertex>, next: &Vertex) -> f64 {
+        let direction = (next.p - self.p).to_unit_vector();
+        let pdf = match (&self.kind, prev) {
+            (Kind::Light { index, .. }, _) => {
+                scene.lights()[*index].pdf_emission_direction(self.normal, direction)
+            }
+            (Kind::Surface { hit, .. }, Some(prev)) => {
+                let incoming = Ray::new(prev.p, (self.p - prev.p).to_unit_vector());
+                hit.material.pdf(&incoming, hit, direction).unwrap_or(0.0)
+            }
+            (Kind::Camera, _) => scene.camera_pdf(direction),
+            _ => 0.0,
+        };
+        to_area(pdf, self, next)
+    }
+
+    // point lights can't be hit, so only paths that start on them reach them
+    fn is_delta_light(&self, scene: &Scene) -> bool {
+        match self.kind {
+            Kind::Light { index, .. } => matches!(scene.lights()[index], Light::Point { .. }),
+            _ => false,
+        }
+    }
+}
+
+// a solid angle density at `from` toward `to` as an area density at `to`
+fn to_area(pdf: f64, from: &Vertex, to: &Vertex) -> f64 {
+    let offset = to.p - from.p;
+    let distance_squared = offset.squared_length();
+    if distance_squared == 0.0 {
+        return 0.0;
+    }
+    let cosine = if to.normal.near_zero() {
+        1.0
+    } else {
+        to.normal.dot(offset).abs() / distance_squared.sqrt()
+    };
+    pdf * cosine / distance_squared
+}
+
+// lights a light path can start on: all but the directional ones
+fn emitting(scene: &Scene) -> Vec<usize> {
+    scene
+        .lights()
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !matches!(l, Light::Directional { .. }))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// area density of a light path starting at the light vertex `v`
+fn pdf_light_origin(scene: &Scene, v: &Vertex) -> f64 {
+    match v.kind {
+        Kind::Light { index, .. } => {
+            scene.lights()[index].pdf_emission_position() / emitting(scene).len().max(1) as f64
+        }
+        _ => 0.0,
+    }
+}
+
+// how much more likely light sampling (`Light::sample`, seen from `next`)
+// is to pick the light vertex `end` than a light path is to start there.
+// Light sampling concentrates on what `next` can see of the light, far
+// better for small lights, and sums over every light
+fn light_sampling_ratio(scene: &Scene, end: &Vertex, next: &Vertex) -> f64 {
+    let index = match end.kind {
+        Kind::Light { index, .. } => index,
+        _ => return 1.0,
+    };
+    let offset = end.p - next.p;
+    let distance = offset.length();
+    let pdf = match scene.lights()[index].hit(next.p, offset / distance) {
+        // the far side of a sphere light is never sampled
+        Some((d, _, pdf)) if (d - distance).abs() <= 1e-6 * distance => pdf,
+        Some(_) => return 0.0,
+        // point lights
+        None => return 1.0,
+    };
+    let origin = pdf_light_origin(scene, end);
+    if origin > 0.0 {
+        to_area(pdf, next, end) / origin
+    } else {
+        1.0
+    }
+}
+
+impl Bidirectional {
+    // extends `path` by scattering `ray` until it leaves the scene, is
+    // absorbed or the path is `max` vertices long. `pdf` is the solid angle
+    // density `ray` was picked with. Eye paths also end on lights in front of
+    // the next surface, and return the environment and emissive surfaces they
+    // see, which no other strategy finds
+    #[allow(clippy::too_many_arguments)]
+    fn walk(
+        &self,
+        scene: &Scene,
+        mut ray: Ray,
+        mut hit: Option<Hit>,
+        mut beta: Vector,
+        mut pdf: f64,
+        path: &mut Vec<Vertex>,
+        max: usize,
+        eye: bool,
+    ) -> Vector {
+        let mut emitted = Vector(0.0, 0.0, 0.0);
+
+        while path.len() < max {
+            let direction = ray.direction.to_unit_vector();
+            let prev = path.len() - 1;
+
+            // camera rays never see the lights
+            if eye && prev > 0 {
+                let origin = path[prev].p;
+                let surface_distance = hit
+                    .as_ref()
+                    .map_or(f64::INFINITY, |h| (h.p - origin).length());
+                let light = scene
+                    .lights()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, l)| {
+                        l.hit(origin, direction)
+                            .map(|(d, radiance, _)| (i, d, radiance))
+                    })
+                    .filter(|&(_, d, _)| d < surface_distance)
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+
+                if let Some((index, distance, radiance)) = light {
+                    let p = origin + distance * direction;
+                    let mut vertex = Vertex {
+                        kind: Kind::Light { index, radiance },
+                        p,
+                        normal: scene.lights()[index].normal_at(p),
+                        beta,
+                        delta: false,
+                        pdf_fwd: 0.0,
+                        pdf_rev: 0.0,
+                    };
+                    vertex.pdf_fwd = to_area(pdf, &path[prev], &vertex);
+                    path.push(vertex);
+                    break;
+                }
+            }
+
+            let h = match hit {
+                Some(h) => h,
+                None => {
+                    if eye {
+                        emitted = emitted + beta * scene.environment().radiance(direction);
+                    }
+                    break;
+                }
+            };
+            if eye {
+                emitted = emitted + beta * h.material.emitted(&ray, &h);
+            }
+
+            let time = ray.time;
+            let scattered = if path.len() + 1 < max {
+                h.material.scatter_lobe(&ray, &h)
+            } else {
+                None
+            };
+            let (next_pdf, pdf_rev) = match &scattered {
+                Some((_, _, true)) => (None, 0.0),
+                Some((s, _, false)) => {
+                    let next_direction = s.direction.to_unit_vector();
+                    let reverse = Ray::new(h.p + next_direction, -next_direction);
+                    match h.material.pdf(&ray, &h, next_direction) {
+                        Some(p) => (
+                            Some(p),
+                            h.material.pdf(&reverse, &h, -direction).unwrap_or(0.0),
+                        ),
+                        None => (None, 0.0),
+                    }
+                }
+                None => (Some(0.0), 0.0),
+            };
+
+            let mut vertex = Vertex {
+                p: h.p,
+                normal: h.normal,
+                beta,
+                // no density for the next vertex to be sampled with
+                delta: next_pdf.is_none(),
+                pdf_fwd: 0.0,
+                pdf_rev: 0.0,
+                kind: Kind::Surface { hit: h, ray },
+            };
+            vertex.pdf_fwd = to_area(pdf, &path[prev], &vertex);
+            path[prev].pdf_rev = to_area(pdf_rev, &vertex, &path[prev]);
+            path.push(vertex);
+
+            let (scattered, attenuation, _) = match scattered {
+                Some(s) => s,
+                None => break,
+            };
+            pdf = next_pdf.unwrap_or(0.0);
+            beta = beta * attenuation;
+            ray = scattered.at_time(time);
+            hit = scene.check_hits(&ray);
+        }
+
+        emitted
+    }
+
+    // a path starting on a light picked uniformly among those it can start on
+    fn light_path(&self, scene: &Scene, time: f64, max: usize) -> Vec<Vertex> {
+        let mut path = Vec::new();
+        let candidates = emitting(scene);
+        if candidates.is_empty() {
+            return path;
+        }
+
+        let pick =
+            (random_in_range(0.0, candidates.len() as f64) as usize).min(candidates.len() - 1);
+        let index = candidates[pick];
+        let emission = match scene.lights()[index].sample_emission() {
+            Some(e) => e,
+            None => return path,
+        };
+        let choice = 1.0 / candidates.len() as f64;
+
+        path.push(Vertex {
+            kind: Kind::Light {
+                index,
+                radiance: emission.radiance,
+            },
+            p: emission.origin,
+            normal: emission.normal,
+            beta: emission.radiance / choice,
+            delta: false,
+            pdf_fwd: choice * emission.pdf_position,
+            pdf_rev: 0.0,
+        });
+
+        let cosine = if emission.normal.near_zero() {
+            1.0
+        } else {
+            emission.normal.dot(emission.direction).abs()
+        };
+        // point lights have no area to pick the origin from
+        let pdf_position = if emission.pdf_position > 0.0 {
+            emission.pdf_position
+        } else {
+            1.0
+        };
+        let beta = cosine * emission.radiance / (choice * pdf_position * emission.pdf_direction);
+
+        let ray = Ray::new(emission.origin, emission.direction).at_time(time);
+        let hit = scene.check_hits(&ray);
+        self.walk(
+            scene,
+            ray,
+            hit,
+            beta,
+            emission.pdf_direction,
+            &mut path,
+            max,
+            false,
+        );
+        path
+    }
+
+    // power heuristic weight of the path made of the `eye` vertices joined to
+    // the `light` ones, against every other strategy that builds it: the
+    // densities of those are the ratios of each vertex's reverse and forward
+    // densities, multiplied up along the path from the connection (pbrt's
+    // formulation), corrected for light sampling picking its own light
+    // vertex. Strategies with a single eye vertex only exist for cameras that
+    // trace light, on paths with a mirror-like vertex
+    fn mis_weight(&self, scene: &Scene, eye: &[Vertex], light: &[&Vertex]) -> f64 {
+        let (s, t) = (light.len(), eye.len());
+        let pt = &eye[t - 1];
+        let pt_minus = if t > 1 { Some(&eye[t - 2]) } else { None };
+        let qs = light.last().copied();
+        let qs_minus = if s > 1 { Some(light[s - 2]) } else { None };
+
+        // the path's vertex on a light and the one next to it
+        let (end, next) = match s {
+            0 => (pt, pt_minus),
+            1 => (light[0], Some(pt)),
+            _ => (light[0], Some(light[1])),
+        };
+        let sampled = next.map_or(1.0, |next| light_sampling_ratio(scene, end, next).powi(2));
+
+        // densities of the vertices around the connection going the other way
+        let pt_rev = match qs {
+            Some(qs) => qs.pdf(scene, qs_minus, pt),
+            None => pdf_light_origin(scene, pt),
+        };
+        let pt_minus_rev = pt_minus.map_or(0.0, |pt_minus| pt.pdf(scene, qs, pt_minus));
+        let qs_rev = qs.map_or(0.0, |qs| pt.pdf(scene, pt_minus, qs));
+        let qs_minus_rev = match (qs, qs_minus) {
+            (Some(qs), Some(qs_minus)) => qs.pdf(scene, Some(pt), qs_minus),
+            _ => 0.0,
+        };
+
+        // deltas have no density, they cancel out of the ratios
+        let remap = |p: f64| if p != 0.0 { p } else { 1.0 };
+        let ratio = |rev: f64, fwd: f64| (remap(rev) / remap(fwd)).powi(2);
+        let mut others = 0.0;
+
+        let specular = eye.iter().chain(light.iter().copied()).any(|v| v.delta);
+        let last_eye = if specular && scene.traces_light() {
+            1
+        } else {
+            2
+        };
+        let mut r = 1.0;
+        for i in (last_eye..t).rev() {
+            let rev = match i {
+                _ if i == t - 1 => pt_rev,
+                _ if i + 2 == t => pt_minus_rev,
+                _ => eye[i].pdf_rev,
+            };
+            r *= ratio(rev, eye[i].pdf_fwd);
+            let delta = i != t - 1 && eye[i].delta;
+            if !delta && !eye[i - 1].delta {
+                // the strategy with a single light vertex
+                others += if s == 0 && i == t - 1 { r * sampled } else { r };
+            }
+        }
+
+        let mut r = 1.0;
+        for i in (0..s).rev() {
+            let rev = match i {
+                _ if i == s - 1 => qs_rev,
+                _ if i + 2 == s => qs_minus_rev,
+                _ => light[i].pdf_rev,
+            };
+            r *= ratio(rev, light[i].pdf_fwd);
+            let delta = i != s - 1 && light[i].delta;
+            let delta_before = if i > 0 {
+                light[i - 1].delta
+            } else {
+                light[0].is_delta_light(scene)
+            };
+            if !delta && !delta_before {
+                others += if i == 1 { r * sampled } else { r };
+            }
+        }
+
+        if s == 1 && sampled > 0.0 {
+            1.0 / (1.0 + others / sampled)
+        } else {
+            1.0 / (1.0 + others)
+        }
+    }
+
+    // light the last of the `light` vertices sends straight into the camera,
+    // added to the pixel it lands on
+    fn connect_to_camera(&self, scene: &Scene, camera: &Vertex, light: &[Vertex], time: f64) {
+        let y = &light[light.len() - 1];
+        let Kind::Surface { hit, ray } = &y.kind else {
+            return;
+        };
+        let (x, row, importance) = match scene.camera_importance(y.p) {
+            Some(i) => i,
+            None => return,
+        };
+
+        let offset = camera.p - y.p;
+        let distance = offset.length();
+        let direction = offset / distance;
+        let f = match hit.material.eval(ray, hit, direction) {
+            Some(f) if !f.near_zero() => f,
+            _ => return,
+        };
+        let shadow = Ray::new(y.p, direction).at_time(time);
+        if scene.check_occluded(&shadow, distance * (1.0 - 1e-4)) {
+            return;
+        }
+
+        let light: Vec<&Vertex> = light.iter().collect();
+        let weight = self.mis_weight(scene, std::slice::from_ref(camera), &light);
+        scene.splat(x, row, weight * importance * (y.beta * f));
+    }
+
+    // light the eye vertex `z` (the last of `eye`) receives from a point
+    // sampled on each light, as in `Scene::direct_light`
+    fn connect_to_lights(&self, scene: &Scene, eye: &[Vertex], hit: &Hit, ray: &Ray) -> Vector {
+        let z = &eye[eye.len() - 1];
+        let mut total = Vector(0.0, 0.0, 0.0);
+
+        for (index, light) in scene.lights().iter().enumerate() {
+            let sample = match light.sample(z.p) {
+                Some(s) => s,
+                None => continue,
+            };
+            let f = match hit.material.eval(ray, hit, sample.direction) {
+                Some(f) if !f.near_zero() => f,
+                _ => continue,
+            };
+            let shadow = Ray::new(z.p, sample.direction).at_time(ray.time);
+            if scene.check_occluded(&shadow, sample.distance) {
+                continue;
+            }
+
+            // no other strategy reaches directional lights
+            let weight = if matches!(light, Light::Directional { .. }) {
+                1.0
+            } else {
+                let p = z.p + sample.distance * sample.direction;
+                let mut y = Vertex {
+                    kind: Kind::Light {
+                        index,
+                        radiance: Vector(0.0, 0.0, 0.0),
+                    },
+                    p,
+                    normal: light.normal_at(p),
+                    beta: Vector(0.0, 0.0, 0.0),
+                    delta: false,
+                    pdf_fwd: 0.0,
+                    pdf_rev: 0.0,
+                };
+                y.pdf_fwd = pdf_light_origin(scene, &y);
+                self.mis_weight(scene, eye, &[&y])
+            };
+            total = total + weight * (z.beta * f * sample.irradiance);
+        }
+
+        total
+    }
+
+    // light arriving at the eye vertex `z` (the last of `eye`) along a shadow
+    // ray from the light path vertex `y` (the last of `light`)
+    fn connect(&self, scene: &Scene, eye: &[Vertex], light: &[Vertex], time: f64) -> Vector {
+        let (z, y) = (&eye[eye.len() - 1], &light[light.len() - 1]);
+        let black = Vector(0.0, 0.0, 0.0);
+        let (Kind::Surface { hit: zh, ray: zr }, Kind::Surface { hit: yh, ray: yr }) =
+            (&z.kind, &y.kind)
+        else {
+            return black;
+        };
+
+        let offset = y.p - z.p;
+        let distance = offset.length();
+        if distance <= 0.0 {
+            return black;
+        }
+        let direction = offset / distance;
+
+        // both include the cosine at their own end
+        let (fz, fy) = match (
+            zh.material.eval(zr, zh, direction),
+            yh.material.eval(yr, yh, -direction),
+        ) {
+            (Some(fz), Some(fy)) if !fz.near_zero() && !fy.near_zero() => (fz, fy),
+            _ => return black,
+        };
+
+        let shadow = Ray::new(z.p, direction).at_time(time);
+        // stop short of the surface `y` is on
+        if scene.check_occluded(&shadow, distance * (1.0 - 1e-4)) {
+            return black;
+        }
+
+        let light: Vec<&Vertex> = light.iter().collect();
+        let weight = self.mis_weight(scene, eye, &light);
+        weight * (z.beta * fz * fy * y.beta) / (distance * distance)
+    }
+}
+
+impl Integrator for Bidirectional {
+    fn li(&self, scene: &Scene, ray: Ray, hit: Option<Hit>) -> Vector {
+        let max = (scene.settings.max_depth.max(0) as usize + 2).min(MAX_SUBPATH_VERTICES);
+        let time = ray.time;
+
+        let mut eye = vec![Vertex {
+            kind: Kind::Camera,
+            p: ray.origin,
+            normal: Vector(0.0, 0.0, 0.0),
+            beta: Vector(1.0, 1.0, 1.0),
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+        }];
+        let one = Vector(1.0, 1.0, 1.0);
+        let pdf = scene.camera_pdf(ray.direction);
+        let mut color = self.walk(scene, ray, hit, one, pdf, &mut eye, max, true);
+        let light = self.light_path(scene, time, max);
+
+        if scene.traces_light() {
+            for s in 3..=light.len() {
+                let specular = light[1..s - 1].iter().any(|v| v.delta);
+                if specular && !light[s - 1].delta {
+                    self.connect_to_camera(scene, &eye[0], &light[..s], time);
+                }
+            }
+        }
+
+        for t in 2..=eye.len() {
+            let z = &eye[t - 1];
+            match &z.kind {
+                Kind::Light { radiance, .. } => {
+                    let weight = self.mis_weight(scene, &eye[..t], &[]);
+                    color = color + weight * (z.beta * *radiance);
+                }
+                Kind::Surface { hit, ray } if !z.delta => {
+                    color = color + self.connect_to_lights(scene, &eye[..t], hit, ray);
+                    for s in 2..=light.len() {
+                        if !light[s - 1].delta {
+                            color = color + self.connect(scene, &eye[..t], &light[..s], time);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        color
+    }
+}
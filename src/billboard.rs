@@ -0,0 +1,136 @@
+use std::io;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::material::Scatter;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// alpha below this counts as a hole in the sprite
+const ALPHA_CUTOFF: u8 = 128;
+
+// a flat rectangle turned to face the camera, optionally cut out by the alpha
+// channel of a PNG so cheap sprites (trees, flames, labels) get a silhouette
+pub struct Billboard {
+    center: Vector,
+    // half extents along the sprite's right and up directions
+    right: Vector,
+    up: Vector,
+    normal: Vector,
+    material: Arc<dyn Scatter>,
+    mask: Option<AlphaMask>,
+}
+
+struct AlphaMask {
+    width: usize,
+    height: usize,
+    alpha: Vec<u8>,
+}
+
+impl Billboard {
+    // `eye` is the camera position the sprite turns toward, keeping world +y up
+    pub fn new(center: Vector, width: f64, height: f64, eye: Vector, m: Arc<dyn Scatter>) -> Self {
+        let normal = (eye - center).to_unit_vector();
+        let mut right = Vector(0.0, 1.0, 0.0).cross(normal);
+        if right.near_zero() {
+            // looking straight down or up, any horizontal axis will do
+            right = Vector(1.0, 0.0, 0.0);
+        }
+        let right = right.to_unit_vector();
+        let up = normal.cross(right);
+
+        Self {
+            center,
+            right: 0.5 * width * right,
+            up: 0.5 * height * up,
+            normal,
+            material: m,
+            mask: None,
+        }
+    }
+
+    // cut the sprite out where the image at `path` is transparent; the image is
+    // stretched over the whole billboard
+    pub fn load_alpha_mask(&mut self, path: &str) -> io::Result<()> {
+        let image = lodepng::decode32_file(path).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("could not read \"{}\": {}", path, err),
+            )
+        })?;
+
+        self.mask = Some(AlphaMask {
+            width: image.width,
+            height: image.height,
+            alpha: image.buffer.iter().map(|p| p.a).collect(),
+        });
+
+        Ok(())
+    }
+
+    fn opaque(&self, u: f64, v: f64) -> bool {
+        match &self.mask {
+            None => true,
+            Some(mask) => {
+                let x = ((u * mask.width as f64) as usize).min(mask.width - 1);
+                // image rows run top to bottom
+                let y = (((1.0 - v) * mask.height as f64) as usize).min(mask.height - 1);
+                mask.alpha[y * mask.width + x] >= ALPHA_CUTOFF
+            }
+        }
+    }
+}
+
+impl Hittable for Billboard {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let denom = r.direction.dot(self.normal);
+        if denom.abs() < 1.0e-12 {
+            return None;
+        }
+
+        let t = (self.center - r.origin).dot(self.normal) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let p = r.line_to_p(t);
+        let offset = p - self.center;
+        let u = 0.5 + 0.5 * offset.dot(self.right) / self.right.squared_length();
+        let v = 0.5 + 0.5 * offset.dot(self.up) / self.up.squared_length();
+
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) || !self.opaque(u, v) {
+            return None;
+        }
+
+        let front_face = denom < 0.0;
+
+        Some(Hit {
+            t,
+            p,
+            local: p,
+            normal: if front_face {
+                self.normal
+            } else {
+                -self.normal
+            },
+            front_face,
+            material: self.material.clone(),
+            u,
+            v,
+            dpdu: 2.0 * self.right,
+            dpdv: 2.0 * self.up,
+            object: 0,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let mut b = Aabb::empty();
+        for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            b = b.grow(self.center + su * self.right + sv * self.up);
+        }
+
+        let pad = Vector(1.0e-6, 1.0e-6, 1.0e-6);
+        Aabb::new(b.min - pad, b.max + pad)
+    }
+}
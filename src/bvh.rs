@@ -0,0 +1,533 @@
+use crate::aabb::Aabb;
+use crate::accelerator::Accelerator;
+use crate::hittable::{Hit, Hittable};
+use crate::ray::{Ray, RayPacket};
+use crate::stats;
+use crate::vector::Vector;
+use std::fs;
+use std::io;
+
+// number of candidate split planes evaluated per axis by the SAH builder
+const SAH_BUCKETS: usize = 12;
+// cost of visiting an interior node relative to one primitive test
+const SAH_TRAVERSAL_COST: f64 = 0.125;
+
+// identifies BVH cache files, bumped whenever the layout changes
+const CACHE_MAGIC: &[u8; 4] = b"BVH1";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitMethod {
+    // split at the median centroid along the widest axis, cheap to build
+    Median,
+    // choose the split minimizing the surface area heuristic, faster to trace
+    Sah,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BvhBuildOptions {
+    pub split: SplitMethod,
+    // primitives per leaf before the builder stops splitting
+    pub max_leaf_size: usize,
+}
+
+impl Default for BvhBuildOptions {
+    fn default() -> Self {
+        Self {
+            split: SplitMethod::Sah,
+            max_leaf_size: 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NodeKind {
+    // range into the primitive list
+    Leaf { start: usize, count: usize },
+    // the left child always directly follows its parent in the node list
+    Interior { right: usize, axis: usize },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    bbox: Aabb,
+    kind: NodeKind,
+}
+
+// primitive bounds, position in the caller's object list, and the primitive itself
+type Item = (Aabb, usize, Box<dyn Hittable>);
+
+// bounding volume hierarchy stored as a flat, depth-first node list
+pub struct Bvh {
+    nodes: Vec<Node>,
+    primitives: Vec<Box<dyn Hittable>>,
+    // index in the original object list of each entry in `primitives`
+    order: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Self {
+        Self::with_options(objects, BvhBuildOptions::default())
+    }
+
+    pub fn with_options(objects: Vec<Box<dyn Hittable>>, options: BvhBuildOptions) -> Self {
+        let mut items: Vec<Item> = objects
+            .into_iter()
+            .enumerate()
+            .map(|(i, o)| (o.bounding_box(), i, o))
+            .collect();
+
+        let mut nodes = Vec::new();
+        if !items.is_empty() {
+            build(&mut nodes, &mut items, 0, &options);
+        }
+
+        let order = items.iter().map(|(_, i, _)| *i).collect();
+
+        Self {
+            nodes,
+            primitives: items.into_iter().map(|(_, _, o)| o).collect(),
+            order,
+        }
+    }
+
+    // loads the hierarchy cached at `path` if it was built from the same objects
+    // and options, otherwise builds it and writes the cache for the next run
+    pub fn load_or_build(
+        objects: Vec<Box<dyn Hittable>>,
+        options: BvhBuildOptions,
+        path: &str,
+    ) -> Self {
+        let key = fingerprint(&objects, &options);
+
+        if let Ok((nodes, order)) = read_cache(path, key, objects.len()) {
+            let mut slots: Vec<Option<Box<dyn Hittable>>> = objects.into_iter().map(Some).collect();
+            let primitives = order.iter().map(|&i| slots[i].take().unwrap()).collect();
+
+            return Self {
+                nodes,
+                primitives,
+                order,
+            };
+        }
+
+        let bvh = Self::with_options(objects, options);
+        if let Err(err) = bvh.save(path, key) {
+            println!("Error writing BVH cache \"{}\": {}", path, err);
+        }
+
+        bvh
+    }
+
+    fn save(&self, path: &str, key: u64) -> io::Result<()> {
+        let mut out = Vec::new();
+        out.extend_from_slice(CACHE_MAGIC);
+        out.extend_from_slice(&key.to_le_bytes());
+        out.extend_from_slice(&(self.order.len() as u64).to_le_bytes());
+        out.extend_from_slice(&(self.nodes.len() as u64).to_le_bytes());
+
+        for node in &self.nodes {
+            for v in [node.bbox.min, node.bbox.max] {
+                for axis in 0..3 {
+                    out.extend_from_slice(&v.axis(axis).to_le_bytes());
+                }
+            }
+
+            let (tag, a, b) = match node.kind {
+                NodeKind::Leaf { start, count } => (0u8, start, count),
+                NodeKind::Interior { right, axis } => (1u8, right, axis),
+            };
+            out.push(tag);
+            out.extend_from_slice(&(a as u64).to_le_bytes());
+            out.extend_from_slice(&(b as u64).to_le_bytes());
+        }
+
+        for &i in &self.order {
+            out.extend_from_slice(&(i as u64).to_le_bytes());
+        }
+
+        fs::write(path, out)
+    }
+}
+
+// FNV-1a over everything the built hierarchy depends on, so edited objects or
+// options invalidate the cache; a cache file starts with its magic and this
+pub fn fingerprint(objects: &[Box<dyn Hittable>], options: &BvhBuildOptions) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |bits: u64| {
+        for byte in bits.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+
+    feed(options.split as u64);
+    feed(options.max_leaf_size as u64);
+    for object in objects {
+        let b = object.bounding_box();
+        for v in [b.min, b.max] {
+            for axis in 0..3 {
+                feed(v.axis(axis).to_bits());
+            }
+        }
+    }
+
+    hash
+}
+
+fn read_cache(path: &str, key: u64, count: usize) -> io::Result<(Vec<Node>, Vec<usize>)> {
+    let data = fs::read(path)?;
+    let mut reader = CacheReader {
+        data: &data,
+        pos: 0,
+    };
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "stale or corrupt BVH cache");
+
+    if reader.take(4)? != CACHE_MAGIC || reader.u64()? != key || reader.u64()? != count as u64 {
+        return Err(invalid());
+    }
+
+    // a node is six bounds, a tag and two indices
+    let node_count = reader.u64()? as usize;
+    if node_count > (data.len() - reader.pos) / 65 {
+        return Err(invalid());
+    }
+    let mut nodes = Vec::with_capacity(node_count);
+    for i in 0..node_count {
+        let mut v = [0.0; 6];
+        for value in v.iter_mut() {
+            *value = f64::from_bits(reader.u64()?);
+        }
+        let bbox = Aabb::new(Vector(v[0], v[1], v[2]), Vector(v[3], v[4], v[5]));
+
+        // children come after their parent, the left one right after it, so
+        // traversal can't run off the end or loop
+        let tag = reader.take(1)?[0];
+        let (a, b) = (reader.u64()? as usize, reader.u64()? as usize);
+        let kind = match tag {
+            0 if a.checked_add(b).is_some_and(|end| end <= count) => {
+                NodeKind::Leaf { start: a, count: b }
+            }
+            1 if i + 1 < node_count && a > i + 1 && a < node_count && b < 3 => {
+                NodeKind::Interior { right: a, axis: b }
+            }
+            _ => return Err(invalid()),
+        };
+
+        nodes.push(Node { bbox, kind });
+    }
+
+    let mut order = Vec::with_capacity(count);
+    let mut seen = vec![false; count];
+    for _ in 0..count {
+        let i = reader.u64()? as usize;
+        if i >= count || seen[i] {
+            return Err(invalid());
+        }
+        seen[i] = true;
+        order.push(i);
+    }
+
+    Ok((nodes, order))
+}
+
+struct CacheReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl CacheReader<'_> {
+    fn take(&mut self, n: usize) -> io::Result<&[u8]> {
+        if self.pos + n > self.data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated BVH cache",
+            ));
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+// recursively partitions items, appending nodes depth-first and returning the
+// index of the created node
+fn build(
+    nodes: &mut Vec<Node>,
+    items: &mut [Item],
+    offset: usize,
+    options: &BvhBuildOptions,
+) -> usize {
+    let bbox = items
+        .iter()
+        .fold(Aabb::empty(), |acc, (b, _, _)| acc.surrounding(b));
+    let index = nodes.len();
+
+    if items.len() <= options.max_leaf_size.max(1) {
+        nodes.push(Node {
+            bbox,
+            kind: NodeKind::Leaf {
+                start: offset,
+                count: items.len(),
+            },
+        });
+        return index;
+    }
+
+    let centroid_bounds = items
+        .iter()
+        .fold(Aabb::empty(), |acc, (b, _, _)| acc.grow(b.centroid()));
+    let axis = centroid_bounds.longest_axis();
+
+    items.sort_by(|(a, _, _), (b, _, _)| {
+        a.centroid()
+            .axis(axis)
+            .partial_cmp(&b.centroid().axis(axis))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mid = match options.split {
+        SplitMethod::Median => items.len() / 2,
+        SplitMethod::Sah => sah_split(items, &bbox, &centroid_bounds, axis),
+    };
+
+    // placeholder, patched once the right child's index is known
+    nodes.push(Node {
+        bbox,
+        kind: NodeKind::Leaf { start: 0, count: 0 },
+    });
+
+    let (left, right) = items.split_at_mut(mid);
+    build(nodes, left, offset, options);
+    let right_index = build(nodes, right, offset + mid, options);
+
+    nodes[index].kind = NodeKind::Interior {
+        right: right_index,
+        axis,
+    };
+
+    index
+}
+
+// bins centroids along `axis` and returns the number of (sorted) items that go
+// to the left child under the cheapest bucket boundary
+fn sah_split(items: &[Item], bbox: &Aabb, centroid_bounds: &Aabb, axis: usize) -> usize {
+    let lo = centroid_bounds.min.axis(axis);
+    let width = centroid_bounds.max.axis(axis) - lo;
+
+    // all centroids coincide, nothing to gain from a smarter split
+    if width <= 0.0 {
+        return items.len() / 2;
+    }
+
+    let bucket_of = |b: &Aabb| {
+        let i = ((b.centroid().axis(axis) - lo) / width * SAH_BUCKETS as f64) as usize;
+        i.min(SAH_BUCKETS - 1)
+    };
+
+    let mut counts = [0usize; SAH_BUCKETS];
+    let mut bounds = [Aabb::empty(); SAH_BUCKETS];
+    for (b, _, _) in items {
+        let i = bucket_of(b);
+        counts[i] += 1;
+        bounds[i] = bounds[i].surrounding(b);
+    }
+
+    let area = bbox.surface_area();
+    let mut best = (f64::INFINITY, items.len() / 2);
+
+    for split in 1..SAH_BUCKETS {
+        let (mut left_box, mut left_count) = (Aabb::empty(), 0);
+        let (mut right_box, mut right_count) = (Aabb::empty(), 0);
+
+        for i in 0..SAH_BUCKETS {
+            if counts[i] == 0 {
+                continue;
+            }
+            if i < split {
+                left_box = left_box.surrounding(&bounds[i]);
+                left_count += counts[i];
+            } else {
+                right_box = right_box.surrounding(&bounds[i]);
+                right_count += counts[i];
+            }
+        }
+
+        if left_count == 0 || right_count == 0 {
+            continue;
+        }
+
+        let cost = SAH_TRAVERSAL_COST
+            + (left_count as f64 * left_box.surface_area()
+                + right_count as f64 * right_box.surface_area())
+                / area;
+
+        if cost < best.0 {
+            best = (cost, left_count);
+        }
+    }
+
+    best.1
+}
+
+impl Bvh {
+    // visits every leaf whose box the ray enters before the limit returned by
+    // the previous call to `visit_leaf`, nearest child first
+    fn traverse(
+        &self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut visit_leaf: impl FnMut(&[Box<dyn Hittable>], &[usize]) -> f64,
+    ) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let counting = stats::counting();
+        let mut limit = t_max;
+        let mut stack = vec![0];
+
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if counting {
+                stats::count_node();
+            }
+            if !node.bbox.hit(r, t_min, limit) {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    if counting {
+                        stats::count_primitives(count);
+                    }
+                    let range = start..start + count;
+                    limit = visit_leaf(&self.primitives[range.clone()], &self.order[range]);
+                }
+                NodeKind::Interior { right, axis } => {
+                    // push the far child first so the near one is visited first
+                    if r.direction.axis(axis) < 0.0 {
+                        stack.push(i + 1);
+                        stack.push(right);
+                    } else {
+                        stack.push(right);
+                        stack.push(i + 1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Hittable for Bvh {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let mut closest: Option<Hit> = None;
+        let mut t_closest = t_max;
+
+        self.traverse(r, t_min, t_max, |objects, order| {
+            for (object, &index) in objects.iter().zip(order) {
+                if let Some(mut hit) = object.ray_intersect(r, t_min, t_closest) {
+                    t_closest = hit.t;
+                    hit.object = index;
+                    closest = Some(hit);
+                }
+            }
+            t_closest
+        });
+
+        closest
+    }
+
+    fn occluded(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        let mut found = false;
+
+        self.traverse(r, t_min, t_max, |objects, _| {
+            found = objects.iter().any(|o| o.occluded(r, t_min, t_max));
+            if found {
+                f64::NEG_INFINITY
+            } else {
+                t_max
+            }
+        });
+
+        found
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self.nodes.first() {
+            Some(root) => root.bbox,
+            None => Aabb::empty(),
+        }
+    }
+}
+
+impl Accelerator for Bvh {
+    fn len(&self) -> usize {
+        self.primitives.len()
+    }
+
+    // visits each node once for the whole packet, descending while any lane
+    // still enters the node's box before its own closest hit
+    fn intersect_packet(&self, packet: &RayPacket, t_min: f64, t_max: f64) -> [Option<Hit>; 4] {
+        let mut closest: [Option<Hit>; 4] = [None, None, None, None];
+        let mut t_closest = [t_max; 4];
+
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let counting = stats::counting();
+        let mut stack = vec![0];
+
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if counting {
+                stats::count_node();
+            }
+            let active = node.bbox.hit_packet(packet, t_min, &t_closest);
+            if !active.contains(&true) {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    if counting {
+                        stats::count_primitives(count * active.iter().filter(|&&a| a).count());
+                    }
+                    for p in start..start + count {
+                        let object = &self.primitives[p];
+                        for lane in 0..4 {
+                            if !active[lane] {
+                                continue;
+                            }
+                            let r = &packet.rays[lane];
+                            if let Some(mut hit) = object.ray_intersect(r, t_min, t_closest[lane]) {
+                                t_closest[lane] = hit.t;
+                                hit.object = self.order[p];
+                                closest[lane] = Some(hit);
+                            }
+                        }
+                    }
+                }
+                NodeKind::Interior { right, axis } => {
+                    // order by the first ray, the packet is assumed to be coherent
+                    if packet.rays[0].direction.axis(axis) < 0.0 {
+                        stack.push(i + 1);
+                        stack.push(right);
+                    } else {
+                        stack.push(right);
+                        stack.push(i + 1);
+                    }
+                }
+            }
+        }
+
+        closest
+    }
+}
@@ -1,17 +1,91 @@
+use crate::environment::direction_of;
+use crate::ray::Ray;
 use crate::utils;
 use crate::vector::Vector;
 
-pub struct Camera {
+// vertical field of view of `PerspectiveCamera::look_at`, in degrees
+const DEFAULT_VFOV: f64 = 40.0;
+
+// turns a point on the image into a primary ray, so scenes can pick any
+// projection. (s, t) runs from (0, 0) at the bottom left to (1, 1) at the top
+// right; random lens samples come from the thread's generator in `utils`
+pub trait Camera: Send + Sync {
+    // unit length direction
+    fn generate_ray(&self, s: f64, t: f64) -> Ray;
+
+    // the ray through (s, t) without random lens effects such as defocus, for
+    // auxiliary buffers and picking
+    fn center_ray(&self, s: f64, t: f64) -> Ray {
+        self.generate_ray(s, t)
+    }
+
+    fn origin(&self) -> Vector;
+
+    // right, up and backward unit vectors of the camera frame, the camera looks along -w
+    fn basis(&self) -> (Vector, Vector, Vector);
+
+    // width over height of the image the camera covers; renders of another
+    // shape see more or less to the sides
+    fn aspect_ratio(&self) -> f64;
+
+    // vertical field of view in degrees of pinhole-like projections, None for
+    // projections without one
+    fn vfov(&self) -> Option<f64> {
+        None
+    }
+
+    // false where (s, t) falls outside what the lens projects, such as the
+    // corners around a fisheye's image circle; those samples stay black
+    fn covers(&self, _s: f64, _t: f64) -> bool {
+        true
+    }
+
+    // where light leaving `p` toward the camera lands, as the (s, t) of
+    // `generate_ray`, and the importance of that ray: 1 / (A cos^4) for the
+    // area A the unit square of (s, t) covers one unit in front of the camera
+    // and the angle to the view axis. That over the cosine is also the
+    // density of ray directions over the square. None behind the camera, and
+    // for cameras light can't be traced into, anything with a lens or a
+    // nonlinear projection
+    fn project(&self, _p: Vector) -> Option<(f64, f64, f64)> {
+        None
+    }
+
+    // the same camera, lens and shutter included, moved by `offset`, e.g. one
+    // eye of a stereo pair; None for cameras that can't be copied this way
+    fn translated(&self, _offset: Vector) -> Option<Box<dyn Camera>> {
+        None
+    }
+}
+
+// right, up and backward unit vectors of a camera at lookfrom facing lookat
+fn view_basis(lookfrom: Vector, lookat: Vector, vup: Vector) -> (Vector, Vector, Vector) {
+    let cw = (lookfrom - lookat).to_unit_vector();
+    let cu = vup.cross(cw).to_unit_vector();
+    let cv = cw.cross(cu);
+    (cu, cv, cw)
+}
+
+// rays fan out from a thin lens, distant things look smaller
+pub struct PerspectiveCamera {
     pub origin: Vector,
     lower_left_corner: Vector,
     horizontal: Vector,
     vertical: Vector,
     cu: Vector,
     cv: Vector,
+    cw: Vector,
+    // vertical field of view in degrees
+    pub vfov: f64,
+    // width over height of the image plane
+    pub aspect_ratio: f64,
     lens_radius: f64,
+    // part of the frame the shutter is open for, see `set_shutter`
+    shutter_open: f64,
+    shutter_close: f64,
 }
 
-impl Camera {
+impl PerspectiveCamera {
     pub fn new(
         lookfrom: Vector,
         lookat: Vector,
@@ -20,40 +94,325 @@ impl Camera {
         aspect_ratio: f64,
         aperture: f64,
         focus_dist: f64,
-    ) -> Camera {
+    ) -> Self {
+        let (cu, cv, cw) = view_basis(lookfrom, lookat, vup);
+
+        let mut camera = Self {
+            origin: lookfrom,
+            horizontal: Vector(0.0, 0.0, 0.0),
+            vertical: Vector(0.0, 0.0, 0.0),
+            lower_left_corner: Vector(0.0, 0.0, 0.0),
+            cu,
+            cv,
+            cw,
+            vfov,
+            aspect_ratio,
+            lens_radius: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        };
+        camera.set_depth_of_field(aperture, focus_dist);
+        camera
+    }
+
+    // thin lens: rays start anywhere on a disc `aperture` wide and meet again
+    // on the plane `focus_dist` in front of the camera, which is the only one
+    // in perfect focus. An aperture of zero is a pinhole, sharp everywhere
+    pub fn set_depth_of_field(&mut self, aperture: f64, focus_dist: f64) {
         // Vertical field-of-view in degrees
-        let theta = std::f64::consts::PI / 180.0 * vfov;
+        let theta = std::f64::consts::PI / 180.0 * self.vfov;
         let viewport_height = 2.0 * (theta / 2.0).tan();
-        let viewport_width = aspect_ratio * viewport_height;
+        let viewport_width = self.aspect_ratio * viewport_height;
 
-        let cw = (lookfrom - lookat).to_unit_vector();
-        let cu = vup.cross(cw).to_unit_vector();
-        let cv = cw.cross(cu);
+        // the image plane sits on the focus plane
+        self.horizontal = focus_dist * viewport_width * self.cu;
+        self.vertical = focus_dist * viewport_height * self.cv;
+        self.lower_left_corner =
+            self.origin - self.horizontal / 2.0 - self.vertical / 2.0 - focus_dist * self.cw;
+        self.lens_radius = aperture / 2.0;
+    }
 
-        let h = focus_dist * viewport_width * cu;
-        let v = focus_dist * viewport_height * cv;
+    // exposes each image from `open` to `close`, as fractions of the frame
+    // (0 its start, 1 its end), so objects moving during the frame blur. The
+    // default keeps the shutter open for an instant at the start
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        self.shutter_open = open;
+        self.shutter_close = close.max(open);
+    }
+
+    // pinhole camera at `eye` facing `target`, with `up` (roughly) up in the
+    // image and the default aspect ratio. Change the field of view and focus
+    // with `new` if these defaults don't suit
+    pub fn look_at(eye: Vector, target: Vector, up: Vector) -> Self {
+        let forward = (target - eye).to_unit_vector();
+        // looking straight along `up` leaves the roll undefined, pick any
+        // perpendicular instead
+        let up = if up.cross(forward).near_zero() {
+            let (t, _) = utils::orthonormal_basis(forward);
+            t
+        } else {
+            up
+        };
+
+        Self::new(
+            eye,
+            target,
+            up,
+            DEFAULT_VFOV,
+            crate::ASPECT_RATIO,
+            0.0,
+            (target - eye).length(),
+        )
+    }
+
+    fn on_plane(&self, s: f64, t: f64) -> Vector {
+        self.lower_left_corner + s * self.horizontal + t * self.vertical
+    }
+}
 
-        let llc = lookfrom - h / 2.0 - v / 2.0 - focus_dist * cw;
+impl Camera for PerspectiveCamera {
+    fn generate_ray(&self, s: f64, t: f64) -> Ray {
+        let rd = self.lens_radius * utils::random_in_unit_disk();
+        let origin = self.origin + rd.x() * self.cu + rd.y() * self.cv;
+        let time = if self.shutter_close > self.shutter_open {
+            utils::random_in_range(self.shutter_open, self.shutter_close)
+        } else {
+            self.shutter_open
+        };
+
+        Ray::new(origin, (self.on_plane(s, t) - origin).to_unit_vector()).at_time(time)
+    }
+
+    fn center_ray(&self, s: f64, t: f64) -> Ray {
+        Ray::new(
+            self.origin,
+            (self.on_plane(s, t) - self.origin).to_unit_vector(),
+        )
+    }
+
+    fn origin(&self) -> Vector {
+        self.origin
+    }
+
+    fn basis(&self) -> (Vector, Vector, Vector) {
+        (self.cu, self.cv, self.cw)
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+
+    fn vfov(&self) -> Option<f64> {
+        Some(self.vfov)
+    }
+
+    // pinholes only, a lens would need a point on it picked too
+    fn project(&self, p: Vector) -> Option<(f64, f64, f64)> {
+        if self.lens_radius > 0.0 {
+            return None;
+        }
+        let d = p - self.origin;
+        let ahead = -d.dot(self.cw);
+        if ahead <= 0.0 {
+            return None;
+        }
+
+        let focus_dist = -(self.lower_left_corner - self.origin).dot(self.cw);
+        let q = self.origin + (focus_dist / ahead) * d - self.lower_left_corner;
+        let s = q.dot(self.horizontal) / self.horizontal.squared_length();
+        let t = q.dot(self.vertical) / self.vertical.squared_length();
+
+        let area = self.horizontal.length() * self.vertical.length() / (focus_dist * focus_dist);
+        let cosine = ahead / d.length();
+        Some((s, t, 1.0 / (area * cosine.powi(4))))
+    }
+
+    fn translated(&self, offset: Vector) -> Option<Box<dyn Camera>> {
+        Some(Box::new(PerspectiveCamera {
+            origin: self.origin + offset,
+            lower_left_corner: self.lower_left_corner + offset,
+            ..*self
+        }))
+    }
+}
+
+// parallel rays along the view direction from a rectangle through the camera
+// position, sizes don't change with distance: technical drawings, isometric
+// views. Nothing behind the camera position is visible
+pub struct OrthographicCamera {
+    pub origin: Vector,
+    lower_left_corner: Vector,
+    horizontal: Vector,
+    vertical: Vector,
+    cu: Vector,
+    cv: Vector,
+    cw: Vector,
+    pub aspect_ratio: f64,
+}
 
-        Camera {
+impl OrthographicCamera {
+    // sees a `view_height` tall slab of the scene, its width follows the aspect ratio
+    pub fn new(
+        lookfrom: Vector,
+        lookat: Vector,
+        vup: Vector,
+        view_height: f64,
+        aspect_ratio: f64,
+    ) -> Self {
+        let (cu, cv, cw) = view_basis(lookfrom, lookat, vup);
+        let horizontal = view_height * aspect_ratio * cu;
+        let vertical = view_height * cv;
+
+        Self {
+            origin: lookfrom,
+            lower_left_corner: lookfrom - horizontal / 2.0 - vertical / 2.0,
+            horizontal,
+            vertical,
+            cu,
+            cv,
+            cw,
+            aspect_ratio,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn generate_ray(&self, s: f64, t: f64) -> Ray {
+        Ray::new(
+            self.lower_left_corner + s * self.horizontal + t * self.vertical,
+            -self.cw,
+        )
+    }
+
+    fn origin(&self) -> Vector {
+        self.origin
+    }
+
+    fn basis(&self) -> (Vector, Vector, Vector) {
+        (self.cu, self.cv, self.cw)
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+}
+
+// full 360 by 180 degree equirectangular view from one point, laid out like
+// the environment maps `Environment` reads so a render can light another
+// scene, and viewable in VR panorama players. Render it at 2:1
+pub struct PanoramaCamera {
+    pub origin: Vector,
+}
+
+impl PanoramaCamera {
+    pub fn new(origin: Vector) -> Self {
+        Self { origin }
+    }
+}
+
+impl Camera for PanoramaCamera {
+    fn generate_ray(&self, s: f64, t: f64) -> Ray {
+        Ray::new(self.origin, direction_of(s, t))
+    }
+
+    fn origin(&self) -> Vector {
+        self.origin
+    }
+
+    // the middle of the image looks along +x with +y up
+    fn basis(&self) -> (Vector, Vector, Vector) {
+        (
+            Vector(0.0, 0.0, -1.0),
+            Vector(0.0, 1.0, 0.0),
+            Vector(-1.0, 0.0, 0.0),
+        )
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        2.0
+    }
+}
+
+// how a fisheye spreads angles from the view direction over its image circle
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FisheyeMapping {
+    // distance from the center proportional to the angle, the usual choice
+    // for dome projection
+    Equidistant,
+    // equal areas of the image see equal solid angles, like most real fisheye lenses
+    Equisolid,
+}
+
+// circular fisheye: the image circle fills the height of the frame and spans
+// `fov` degrees across, up to 360 to see all around. Frames wider than they
+// are tall show black bars beside the circle
+pub struct FisheyeCamera {
+    pub origin: Vector,
+    cu: Vector,
+    cv: Vector,
+    cw: Vector,
+    // full field of view across the image circle, in radians
+    fov: f64,
+    mapping: FisheyeMapping,
+}
+
+impl FisheyeCamera {
+    pub fn new(
+        lookfrom: Vector,
+        lookat: Vector,
+        vup: Vector,
+        fov: f64,
+        mapping: FisheyeMapping,
+    ) -> Self {
+        let (cu, cv, cw) = view_basis(lookfrom, lookat, vup);
+        Self {
             origin: lookfrom,
-            horizontal: h,
-            vertical: v,
-            lower_left_corner: llc,
-            cu: cu,
-            cv: cv,
-            lens_radius: aperture / 2.0,
+            cu,
+            cv,
+            cw,
+            fov: fov.clamp(1.0, 360.0).to_radians(),
+            mapping,
         }
     }
 
-    pub fn get_pixel_direction(&self, x: f64, y: f64) -> (Vector, Vector) {
-        let rd = self.lens_radius * utils::random_vector_in_unit_sphere();
-        let offset = rd.x() * self.cu + rd.y() * self.cv;
+    // (s, t) relative to the center of the image circle, in radii
+    fn from_center(s: f64, t: f64) -> (f64, f64) {
+        (2.0 * s - 1.0, 2.0 * t - 1.0)
+    }
+}
 
+impl Camera for FisheyeCamera {
+    fn generate_ray(&self, s: f64, t: f64) -> Ray {
+        let (x, y) = Self::from_center(s, t);
+        let r = (x * x + y * y).sqrt().min(1.0);
+
+        // angle away from the view direction
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => r * self.fov / 2.0,
+            FisheyeMapping::Equisolid => 2.0 * (r * (self.fov / 4.0).sin()).asin(),
+        };
+
+        let (cos_phi, sin_phi) = if r > 0.0 { (x / r, y / r) } else { (1.0, 0.0) };
         let direction =
-            self.lower_left_corner + x * self.horizontal + y * self.vertical - self.origin - offset;
-        let origin = self.origin + offset;
+            theta.sin() * (cos_phi * self.cu + sin_phi * self.cv) - theta.cos() * self.cw;
+
+        Ray::new(self.origin, direction.to_unit_vector())
+    }
+
+    fn origin(&self) -> Vector {
+        self.origin
+    }
+
+    fn basis(&self) -> (Vector, Vector, Vector) {
+        (self.cu, self.cv, self.cw)
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        1.0
+    }
 
-        (origin, direction)
+    fn covers(&self, s: f64, t: f64) -> bool {
+        let (x, y) = Self::from_center(s, t);
+        x * x + y * y <= 1.0
     }
 }
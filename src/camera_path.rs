@@ -0,0 +1,103 @@
+use crate::camera::PerspectiveCamera;
+use crate::vector::Vector;
+
+// camera pose at one moment of a fly-through
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    // seconds from the start of the animation
+    pub time: f64,
+    pub position: Vector,
+    pub target: Vector,
+    // vertical field of view in degrees
+    pub vfov: f64,
+}
+
+// keyframed camera animation: position and target follow Catmull-Rom curves
+// through the keyframes so the camera doesn't jerk at them, the field of view
+// changes linearly. Render frame N with `Scene::render_frame`
+pub struct CameraPath {
+    // sorted by time
+    keyframes: Vec<Keyframe>,
+    up: Vector,
+    aspect_ratio: f64,
+}
+
+impl CameraPath {
+    pub fn new(up: Vector, aspect_ratio: f64) -> Self {
+        Self {
+            keyframes: Vec::new(),
+            up,
+            aspect_ratio,
+        }
+    }
+
+    pub fn add_keyframe(&mut self, time: f64, position: Vector, target: Vector, vfov: f64) {
+        let i = self.keyframes.partition_point(|k| k.time <= time);
+        self.keyframes.insert(
+            i,
+            Keyframe {
+                time,
+                position,
+                target,
+                vfov,
+            },
+        );
+    }
+
+    // time of the last keyframe
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    // frames needed to play the whole path at `fps`, first and last included
+    pub fn frame_count(&self, fps: f64) -> u32 {
+        (self.duration() * fps).floor() as u32 + 1
+    }
+
+    // pinhole camera at `time` seconds, holding the first and last poses
+    // outside the keyframes. None without keyframes
+    pub fn camera_at(&self, time: f64) -> Option<PerspectiveCamera> {
+        let keys = &self.keyframes;
+        let last = keys.len().checked_sub(1)?;
+
+        // the segment from keys[i] to keys[i + 1] containing `time`
+        let i = keys
+            .partition_point(|k| k.time <= time)
+            .saturating_sub(1)
+            .min(last.saturating_sub(1));
+        let (a, b) = (keys[i], keys[(i + 1).min(last)]);
+        let span = b.time - a.time;
+        let u = if span > 0.0 {
+            ((time - a.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        // neighbors shape the tangents, repeating the ends
+        let before = keys[i.saturating_sub(1)];
+        let after = keys[(i + 2).min(last)];
+        let position = catmull_rom(before.position, a.position, b.position, after.position, u);
+        let target = catmull_rom(before.target, a.target, b.target, after.target, u);
+        let vfov = a.vfov + u * (b.vfov - a.vfov);
+
+        Some(PerspectiveCamera::new(
+            position,
+            target,
+            self.up,
+            vfov,
+            self.aspect_ratio,
+            0.0,
+            (target - position).length(),
+        ))
+    }
+}
+
+// point `u` of the way from p1 to p2 on the uniform Catmull-Rom spline through p0..p3
+fn catmull_rom(p0: Vector, p1: Vector, p2: Vector, p3: Vector, u: f64) -> Vector {
+    let u2 = u * u;
+    let u3 = u2 * u;
+    0.5 * ((2.0 * p1)
+        + u * (p2 - p0)
+        + u2 * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3)
+        + u3 * (3.0 * p1 - p0 - 3.0 * p2 + p3))
+}
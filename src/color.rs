@@ -0,0 +1,152 @@
+use crate::vector::Vector;
+
+// Minimal color management. Rendering always happens in linear light with
+// Rec. 709 / sRGB primaries (the working space); color spaces here only differ in
+// their transfer function, which is applied when reading texture files and when
+// writing the final image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    // values are used as they are
+    Linear,
+    // IEC 61966-2-1, what PNGs and most monitors assume
+    Srgb,
+    // ITU-R BT.709 camera curve, for video pipelines
+    Rec709,
+}
+
+impl ColorSpace {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "linear" => Some(ColorSpace::Linear),
+            "srgb" => Some(ColorSpace::Srgb),
+            "rec709" | "rec.709" | "bt709" => Some(ColorSpace::Rec709),
+            _ => None,
+        }
+    }
+
+    // encoded value in [0, 1] to linear light
+    pub fn decode(self, c: Vector) -> Vector {
+        let f: fn(f64) -> f64 = match self {
+            ColorSpace::Linear => return c,
+            ColorSpace::Srgb => |v| {
+                if v <= 0.04045 {
+                    v / 12.92
+                } else {
+                    ((v + 0.055) / 1.055).powf(2.4)
+                }
+            },
+            ColorSpace::Rec709 => |v| {
+                if v < 0.081 {
+                    v / 4.5
+                } else {
+                    ((v + 0.099) / 1.099).powf(1.0 / 0.45)
+                }
+            },
+        };
+        Vector(f(c.x()), f(c.y()), f(c.z()))
+    }
+
+    // linear light to the encoded value, negative input is treated as black
+    pub fn encode(self, c: Vector) -> Vector {
+        let f: fn(f64) -> f64 = match self {
+            ColorSpace::Linear => return c,
+            ColorSpace::Srgb => |v| {
+                if v <= 0.0031308 {
+                    12.92 * v.max(0.0)
+                } else {
+                    1.055 * v.powf(1.0 / 2.4) - 0.055
+                }
+            },
+            ColorSpace::Rec709 => |v| {
+                if v < 0.018 {
+                    4.5 * v.max(0.0)
+                } else {
+                    1.099 * v.powf(0.45) - 0.099
+                }
+            },
+        };
+        Vector(f(c.x()), f(c.y()), f(c.z()))
+    }
+}
+
+// how linear radiance, which has no upper bound, is brought into the 0 to 1
+// range of the output before its transfer function
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMap {
+    // leaves everything up to 1 exactly as rendered and blends brighter colors
+    // toward white, see `Vector::desaturate_highlights`
+    Clip,
+    // Reinhard's L / (1 + L) on luminance: compresses highlights smoothly and
+    // never reaches white, but darkens the midtones too
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve per channel: a slight toe,
+    // punchy midtones and highlights that roll off into white
+    Aces,
+}
+
+impl ToneMap {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "clip" | "none" => Some(ToneMap::Clip),
+            "reinhard" => Some(ToneMap::Reinhard),
+            "aces" | "filmic" => Some(ToneMap::Aces),
+            _ => None,
+        }
+    }
+
+    // linear radiance to linear values between 0 and 1
+    pub fn apply(self, c: Vector) -> Vector {
+        let c = Vector(c.x().max(0.0), c.y().max(0.0), c.z().max(0.0));
+        match self {
+            ToneMap::Clip => c.desaturate_highlights(),
+            ToneMap::Reinhard => {
+                let lum = c.luminance();
+                if lum <= 0.0 {
+                    return c;
+                }
+                // scaling the luminance keeps the hue, saturated colors may
+                // still go past 1 in one channel
+                (1.0 / (1.0 + lum) * c).desaturate_highlights()
+            }
+            ToneMap::Aces => {
+                // the fit expects the curve's own exposure, which is 0.6 of ours
+                let f = |v: f64| {
+                    let v = 0.6 * v;
+                    ((v * (2.51 * v + 0.03)) / (v * (2.43 * v + 0.59) + 0.14)).clamp(0.0, 1.0)
+                };
+                Vector(f(c.x()), f(c.y()), f(c.z()))
+            }
+        }
+    }
+}
+
+pub struct ColorConfig {
+    // encoding of 8-bit texture files, given to texture loaders
+    pub input: ColorSpace,
+    // transfer function applied to the linear image before quantizing to 8 bits
+    pub output: ColorSpace,
+    // brings the image into range before `output` is applied
+    pub tone_map: ToneMap,
+    // stops the image is brightened (or darkened, below zero) by before tone
+    // mapping
+    pub exposure: f64,
+}
+
+impl ColorConfig {
+    // linear radiance to the encoded output value, between 0 and 1
+    pub fn display(&self, c: Vector) -> Vector {
+        self.output
+            .encode(self.tone_map.apply(2f64.powf(self.exposure) * c))
+    }
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        Self {
+            input: ColorSpace::Srgb,
+            output: ColorSpace::Srgb,
+            tone_map: ToneMap::Clip,
+            exposure: 0.0,
+        }
+    }
+}
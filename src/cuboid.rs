@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::material::Scatter;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// solid axis-aligned box, rotate it by wrapping it in an Instance
+pub struct Cuboid {
+    pub bounds: Aabb,
+    pub material: Arc<dyn Scatter>,
+}
+
+impl Cuboid {
+    pub fn new(min: Vector, max: Vector, m: Arc<dyn Scatter>) -> Self {
+        Self {
+            bounds: Aabb::new(min, max),
+            material: m,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        // slab test keeping track of which axis the ray enters and leaves through
+        let (mut t_near, mut t_far) = (f64::NEG_INFINITY, f64::INFINITY);
+        let (mut near_axis, mut far_axis) = (0, 0);
+
+        for axis in 0..3 {
+            let inv_d = 1.0 / r.direction.axis(axis);
+            let mut t0 = (self.bounds.min.axis(axis) - r.origin.axis(axis)) * inv_d;
+            let mut t1 = (self.bounds.max.axis(axis) - r.origin.axis(axis)) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            if t0 > t_near {
+                t_near = t0;
+                near_axis = axis;
+            }
+            if t1 < t_far {
+                t_far = t1;
+                far_axis = axis;
+            }
+        }
+
+        if t_near > t_far {
+            return None;
+        }
+
+        // entering from outside, or leaving when the ray starts inside
+        let (t, axis, front_face) = if t_near > t_min && t_near < t_max {
+            (t_near, near_axis, true)
+        } else if t_far > t_min && t_far < t_max {
+            (t_far, far_axis, false)
+        } else {
+            return None;
+        };
+
+        // the face normal opposes the ray on entry and follows it on exit, the
+        // stored normal always opposes the ray
+        let sign = if r.direction.axis(axis) > 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let normal = match axis {
+            0 => Vector(sign, 0.0, 0.0),
+            1 => Vector(0.0, sign, 0.0),
+            _ => Vector(0.0, 0.0, sign),
+        };
+
+        // planar mapping of each face over its two other axes
+        let p = r.line_to_p(t);
+        let (a, b) = match axis {
+            0 => (2, 1),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+        let extent = |i: usize| {
+            let mut d = [0.0; 3];
+            d[i] = self.bounds.max.axis(i) - self.bounds.min.axis(i);
+            Vector(d[0], d[1], d[2])
+        };
+        let local = |i: usize| {
+            (p.axis(i) - self.bounds.min.axis(i))
+                / (self.bounds.max.axis(i) - self.bounds.min.axis(i))
+        };
+
+        Some(Hit {
+            t,
+            p,
+            local: p,
+            normal,
+            front_face,
+            material: self.material.clone(),
+            u: local(a),
+            v: local(b),
+            dpdu: extent(a),
+            dpdv: extent(b),
+            object: 0,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
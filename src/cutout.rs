@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::ray::Ray;
+use crate::texture::Texture;
+
+// opacity below this counts as a hole
+const OPACITY_CUTOFF: f64 = 0.5;
+
+// punches holes in another object where an opacity texture (read from the x
+// channel, e.g. `ImageTexture::load_alpha`) is below one half, for leaf and
+// fence cards. Rays, shadow rays included, pass straight through the holes
+pub struct Cutout {
+    object: Box<dyn Hittable>,
+    opacity: Arc<dyn Texture>,
+}
+
+impl Cutout {
+    pub fn new(object: Box<dyn Hittable>, opacity: Arc<dyn Texture>) -> Self {
+        Self { object, opacity }
+    }
+}
+
+impl Hittable for Cutout {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        // keep looking past transparent hits until an opaque one or none is left
+        let mut t_min = t_min;
+        loop {
+            let hit = self.object.ray_intersect(r, t_min, t_max)?;
+            if self.opacity.value_at(&hit).x() >= OPACITY_CUTOFF {
+                return Some(hit);
+            }
+            t_min = hit.t;
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box()
+    }
+}
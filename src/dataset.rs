@@ -0,0 +1,183 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::camera::Camera;
+use crate::vector::Vector;
+
+// Per-frame outputs for synthetic training data, written under one directory:
+//
+//   rgb/000000.png       shaded image, 8-bit RGB
+//   depth/000000.pfm     camera-space z distance per pixel as 32-bit float PFM, 0 where nothing was hit
+//   normal/000000.png    world-space normal of the visible surface mapped to 0.5 * (n + 1), black on miss
+//   instance/000000.png  16-bit grayscale, index of the hit object in the scene's list + 1, 0 on miss
+//   class/000000.png     16-bit grayscale semantic class id, 0 on miss or unlabeled objects
+//   classes.json         legend mapping class ids to the labels given with `Scene::set_label`
+//   camera/000000.json   intrinsics in pixels and the 4x4 camera-to-world matrix
+//
+// The camera uses the OpenGL convention: x right, y up, looking down -z.
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    // all buffers are row-major, top row first
+    pub rgb: Vec<lodepng::RGB<u8>>,
+    pub depth: Vec<f32>,
+    pub normal: Vec<Vector>,
+    pub instance: Vec<u16>,
+    pub class: Vec<u16>,
+    // class names, id i + 1 is legend[i]
+    pub legend: Vec<String>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        let n = width * height;
+        Self {
+            width,
+            height,
+            rgb: Vec::with_capacity(n),
+            depth: Vec::with_capacity(n),
+            normal: Vec::with_capacity(n),
+            instance: Vec::with_capacity(n),
+            class: Vec::with_capacity(n),
+            legend: Vec::new(),
+        }
+    }
+
+    pub fn write(&self, dir: &Path, index: u32, camera: &dyn Camera) -> io::Result<()> {
+        let name = format!("{:06}", index);
+        for sub in ["rgb", "depth", "normal", "instance", "class", "camera"] {
+            fs::create_dir_all(dir.join(sub))?;
+        }
+
+        let (w, h) = (self.width, self.height);
+        lodepng::encode24_file(
+            dir.join("rgb").join(format!("{}.png", name)),
+            &self.rgb,
+            w,
+            h,
+        )
+        .map_err(to_io_error)?;
+
+        let normals: Vec<lodepng::RGB<u8>> = self
+            .normal
+            .iter()
+            .map(|&n| {
+                if n.near_zero() {
+                    Vector(0.0, 0.0, 0.0).to_rgb()
+                } else {
+                    (0.5 * (n + Vector(1.0, 1.0, 1.0))).to_rgb()
+                }
+            })
+            .collect();
+        lodepng::encode24_file(
+            dir.join("normal").join(format!("{}.png", name)),
+            &normals,
+            w,
+            h,
+        )
+        .map_err(to_io_error)?;
+
+        write_grey16(
+            &dir.join("instance").join(format!("{}.png", name)),
+            &self.instance,
+            w,
+            h,
+        )?;
+        write_grey16(
+            &dir.join("class").join(format!("{}.png", name)),
+            &self.class,
+            w,
+            h,
+        )?;
+        self.write_legend(&dir.join("classes.json"))?;
+
+        self.write_pfm(&dir.join("depth").join(format!("{}.pfm", name)))?;
+        self.write_camera(&dir.join("camera").join(format!("{}.json", name)), camera)
+    }
+
+    fn write_pfm(&self, path: &Path) -> io::Result<()> {
+        let mut out = Vec::with_capacity(self.depth.len() * 4 + 32);
+        // negative scale marks little-endian data, rows run bottom to top
+        write!(out, "Pf\n{} {}\n-1.0\n", self.width, self.height)?;
+        for row in self.depth.chunks(self.width).rev() {
+            for d in row {
+                out.extend_from_slice(&d.to_le_bytes());
+            }
+        }
+
+        fs::write(path, out)
+    }
+
+    fn write_legend(&self, path: &Path) -> io::Result<()> {
+        let mut entries = vec!["  \"0\": \"background\"".to_string()];
+        for (i, name) in self.legend.iter().enumerate() {
+            entries.push(format!("  \"{}\": {}", i + 1, json_string(name)));
+        }
+
+        fs::write(path, format!("{{\n{}\n}}\n", entries.join(",\n")))
+    }
+
+    fn write_camera(&self, path: &Path, camera: &dyn Camera) -> io::Result<()> {
+        let (u, v, w) = camera.basis();
+        let o = camera.origin();
+
+        // pinhole intrinsics, other projections have no focal length
+        let f = camera.vfov().map_or("null".to_string(), |vfov| {
+            (0.5 * self.height as f64 / (vfov.to_radians() / 2.0).tan()).to_string()
+        });
+        let rows = [
+            [u.x(), v.x(), w.x(), o.x()],
+            [u.y(), v.y(), w.y(), o.y()],
+            [u.z(), v.z(), w.z(), o.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let matrix: Vec<String> = rows
+            .iter()
+            .map(|r| format!("[{}, {}, {}, {}]", r[0], r[1], r[2], r[3]))
+            .collect();
+
+        let json = format!(
+            "{{\n  \"width\": {},\n  \"height\": {},\n  \"fx\": {},\n  \"fy\": {},\n  \"cx\": {},\n  \"cy\": {},\n  \"cam_to_world\": [{}]\n}}\n",
+            self.width,
+            self.height,
+            f,
+            f,
+            0.5 * self.width as f64,
+            0.5 * self.height as f64,
+            matrix.join(", ")
+        );
+
+        fs::write(path, json)
+    }
+}
+
+fn write_grey16(path: &Path, values: &[u16], w: usize, h: usize) -> io::Result<()> {
+    // 16-bit PNG samples are big-endian
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+    lodepng::encode_file(path, &bytes, w, h, lodepng::ColorType::GREY, 16).map_err(to_io_error)
+}
+
+fn to_io_error(err: lodepng::Error) -> io::Error {
+    io::Error::other(err.to_string())
+}
+
+// `text` as a quoted JSON string. Debug formatting is not JSON: it writes
+// escapes like `\u{1b}` for control and some non-ASCII characters
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,177 @@
+use std::f64::consts::PI;
+use std::io;
+use std::sync::Arc;
+
+use crate::hdr;
+use crate::sky::Sky;
+use crate::sphere::sphere_uv;
+use crate::texture::Texture;
+use crate::utils::random_in_range;
+use crate::vector::Vector;
+
+// light arriving from directions where rays escape the scene, the only
+// ambient illumination a path traced scene gets
+pub enum Environment {
+    // white at the horizon fading to the given color overhead, the original sky
+    Gradient(Vector),
+    // hemispherical model: `sky` above the horizon and `ground` below
+    SkyGround {
+        sky: Vector,
+        ground: Vector,
+    },
+    // equirectangular image (or any texture) mapped by direction, scaled by strength
+    Map {
+        texture: Arc<dyn Texture>,
+        strength: f64,
+    },
+    // equirectangular HDR image, also sampled directly toward its bright spots
+    // (sun, windows) so they light the scene without fireflies
+    Hdr(HdrMap),
+    // analytic daylight sky, see `Sky`
+    Sky(Sky),
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::Gradient(Vector(0.5, 0.7, 1.0))
+    }
+}
+
+// pixels of an HDR environment with the distribution used to sample them
+pub struct HdrMap {
+    width: usize,
+    height: usize,
+    // linear radiance, rows top to bottom
+    texels: Vec<Vector>,
+    strength: f64,
+    // running sum of the texel weights, normalized to end at 1
+    cdf: Vec<f64>,
+}
+
+// unit direction of equirectangular coordinates, the inverse of `sphere_uv`
+pub fn direction_of(u: f64, v: f64) -> Vector {
+    let (theta, phi) = (v * PI, u * 2.0 * PI);
+    Vector(
+        -theta.sin() * phi.cos(),
+        -theta.cos(),
+        theta.sin() * phi.sin(),
+    )
+}
+
+impl HdrMap {
+    // index of the texel a direction falls in, nearest lookup so the radiance
+    // is constant over the texel just like the sampling density
+    fn texel_index(&self, direction: Vector) -> usize {
+        let (u, v) = sphere_uv(direction.to_unit_vector());
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = (((1.0 - v) * self.height as f64) as usize).min(self.height - 1);
+        y * self.width + x
+    }
+
+    // texels in row `y` cover solid angle in proportion to sin(theta)
+    fn sin_theta(&self, y: usize) -> f64 {
+        let v = 1.0 - (y as f64 + 0.5) / self.height as f64;
+        (v * PI).sin()
+    }
+
+    // solid angle pdf of picking `direction`
+    fn pdf(&self, direction: Vector) -> f64 {
+        let i = self.texel_index(direction);
+        let previous = if i == 0 { 0.0 } else { self.cdf[i - 1] };
+        let p_texel = self.cdf[i] - previous;
+
+        // a texel spans 2 pi / width by pi / height of the (phi, theta) domain,
+        // and d(solid angle) = sin(theta) d(phi) d(theta)
+        let texel_area = 2.0 * PI * PI / (self.width * self.height) as f64;
+        let d = direction.to_unit_vector();
+        let sin_theta = (1.0 - d.y() * d.y()).max(0.0).sqrt().max(1.0e-6);
+        p_texel / (texel_area * sin_theta)
+    }
+
+    fn sample(&self) -> (Vector, f64) {
+        let target = random_in_range(0.0, 1.0);
+        let i = self
+            .cdf
+            .partition_point(|&c| c < target)
+            .min(self.cdf.len() - 1);
+        let (x, y) = (i % self.width, i / self.width);
+
+        // uniform point inside the texel
+        let u = (x as f64 + random_in_range(0.0, 1.0)) / self.width as f64;
+        let v = 1.0 - (y as f64 + random_in_range(0.0, 1.0)) / self.height as f64;
+        let direction = direction_of(u, v);
+        (direction, self.pdf(direction))
+    }
+}
+
+impl Environment {
+    // loads an equirectangular Radiance .hdr image, `strength` scales its radiance
+    pub fn load_hdr(path: &str, strength: f64) -> io::Result<Self> {
+        let (width, height, texels) = hdr::read(path)?;
+
+        let mut map = HdrMap {
+            width,
+            height,
+            texels,
+            strength,
+            cdf: Vec::with_capacity(width * height),
+        };
+
+        let mut total = 0.0;
+        for (i, texel) in map.texels.iter().enumerate() {
+            total += texel.luminance().max(0.0) * map.sin_theta(i / width);
+            map.cdf.push(total);
+        }
+        if total > 0.0 {
+            map.cdf.iter_mut().for_each(|c| *c /= total);
+        } else {
+            // a black map: sample uniformly, it contributes nothing anyway
+            let n = map.cdf.len() as f64;
+            map.cdf
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, c)| *c = (i + 1) as f64 / n);
+        }
+
+        Ok(Environment::Hdr(map))
+    }
+
+    pub fn radiance(&self, direction: Vector) -> Vector {
+        let d = direction.to_unit_vector();
+
+        match self {
+            Environment::Gradient(zenith) => {
+                let t = 0.5 * (d.y() + 1.0);
+                (1.0 - t) * Vector(1.0, 1.0, 1.0) + t * *zenith
+            }
+            Environment::SkyGround { sky, ground } => {
+                // blend over a narrow band so the horizon isn't a hard seam
+                let t = (0.5 + 10.0 * d.y()).clamp(0.0, 1.0);
+                (1.0 - t) * *ground + t * *sky
+            }
+            Environment::Map { texture, strength } => {
+                let (u, v) = sphere_uv(d);
+                *strength * texture.value(u, v, d)
+            }
+            Environment::Hdr(map) => map.strength * map.texels[map.texel_index(d)],
+            Environment::Sky(sky) => sky.radiance(d),
+        }
+    }
+
+    // a direction drawn in proportion to brightness with its solid angle pdf,
+    // None for environments that are only found by rays escaping the scene
+    pub fn sample(&self) -> Option<(Vector, f64)> {
+        match self {
+            Environment::Hdr(map) => Some(map.sample()),
+            _ => None,
+        }
+    }
+
+    // pdf `sample` would pick `direction` with
+    pub fn pdf(&self, direction: Vector) -> Option<f64> {
+        match self {
+            Environment::Hdr(map) => Some(map.pdf(direction)),
+            _ => None,
+        }
+    }
+}
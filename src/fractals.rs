@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use crate::bvh::Bvh;
+use crate::cuboid::Cuboid;
+use crate::hittable::Hittable;
+use crate::instance::Instance;
+use crate::material::Scatter;
+use crate::transform::Transform;
+use crate::triangle::Triangle;
+use crate::vector::Vector;
+
+// Menger sponge of edge length `size` around `center`: every level keeps the 20
+// sub-cubes that are not on a face or body center line, 20^depth cubes in total;
+// the cubes are instances of one shared unit cube under a top-level BVH
+pub fn menger_sponge(center: Vector, size: f64, depth: u32, material: Arc<dyn Scatter>) -> Bvh {
+    let unit: Arc<dyn Hittable> = Arc::new(Cuboid::new(
+        Vector(-0.5, -0.5, -0.5),
+        Vector(0.5, 0.5, 0.5),
+        material,
+    ));
+
+    let mut cubes: Vec<Box<dyn Hittable>> = Vec::new();
+    menger_level(&unit, center, size, depth, &mut cubes);
+
+    Bvh::new(cubes)
+}
+
+fn menger_level(
+    unit: &Arc<dyn Hittable>,
+    center: Vector,
+    size: f64,
+    depth: u32,
+    out: &mut Vec<Box<dyn Hittable>>,
+) {
+    if depth == 0 {
+        out.push(Box::new(placed(unit, center, size)));
+        return;
+    }
+
+    let step = size / 3.0;
+    for x in -1..=1i32 {
+        for y in -1..=1i32 {
+            for z in -1..=1i32 {
+                // drop the cubes with two or more centered coordinates
+                if (x == 0) as u32 + (y == 0) as u32 + (z == 0) as u32 >= 2 {
+                    continue;
+                }
+
+                let offset = Vector(x as f64, y as f64, z as f64);
+                menger_level(unit, center + step * offset, step, depth - 1, out);
+            }
+        }
+    }
+}
+
+// Sierpinski tetrahedron with edge length `size` and its centroid at `center`:
+// each level replaces a tetrahedron by four half-size copies at its corners,
+// 4^depth instances of one shared regular tetrahedron
+pub fn sierpinski_tetrahedron(
+    center: Vector,
+    size: f64,
+    depth: u32,
+    material: Arc<dyn Scatter>,
+) -> Bvh {
+    let corners = unit_tetrahedron();
+    let [a, b, c, d] = corners;
+    let faces: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Triangle::new(a, c, b, material.clone())),
+        Box::new(Triangle::new(a, b, d, material.clone())),
+        Box::new(Triangle::new(b, c, d, material.clone())),
+        Box::new(Triangle::new(c, a, d, material)),
+    ];
+    let unit: Arc<dyn Hittable> = Arc::new(Bvh::new(faces));
+
+    let mut tetrahedra: Vec<Box<dyn Hittable>> = Vec::new();
+    sierpinski_level(&unit, &corners, center, size, depth, &mut tetrahedra);
+
+    Bvh::new(tetrahedra)
+}
+
+fn sierpinski_level(
+    unit: &Arc<dyn Hittable>,
+    corners: &[Vector; 4],
+    center: Vector,
+    size: f64,
+    depth: u32,
+    out: &mut Vec<Box<dyn Hittable>>,
+) {
+    if depth == 0 {
+        out.push(Box::new(placed(unit, center, size)));
+        return;
+    }
+
+    // a half-size copy touching each corner has its center halfway to that corner
+    for corner in corners {
+        let child = center + 0.5 * size * *corner;
+        sierpinski_level(unit, corners, child, 0.5 * size, depth - 1, out);
+    }
+}
+
+// corners of a regular tetrahedron with unit edge length, centered on its centroid
+fn unit_tetrahedron() -> [Vector; 4] {
+    let h = (2.0f64 / 3.0).sqrt();
+    let r = 1.0 / 3.0f64.sqrt();
+    let base = -0.25 * h;
+
+    [
+        Vector(r, base, 0.0),
+        Vector(-0.5 * r, base, 0.5),
+        Vector(-0.5 * r, base, -0.5),
+        Vector(0.0, 0.75 * h, 0.0),
+    ]
+}
+
+fn placed(unit: &Arc<dyn Hittable>, center: Vector, size: f64) -> Instance {
+    let mut instance = Instance::new(unit.clone());
+    instance.set_transform(
+        Transform::scale(Vector(size, size, size)).then(&Transform::translate(center)),
+    );
+    instance
+}
@@ -0,0 +1,40 @@
+use crate::vector::Vector;
+
+// running sum of every pass's linear color per pixel, rows top first, so a
+// render can be refined pass after pass and its current estimate read at any
+// point, see `Scene::render_pass`
+pub struct Framebuffer {
+    pub width: i32,
+    pub height: i32,
+    sums: Vec<Vector>,
+    passes: u32,
+}
+
+impl Framebuffer {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            sums: vec![Vector(0.0, 0.0, 0.0); (width * height).max(0) as usize],
+            passes: 0,
+        }
+    }
+
+    // one full image of per-pixel averages, rows top first
+    pub fn add_pass(&mut self, colors: &[Vector]) {
+        for (sum, &c) in self.sums.iter_mut().zip(colors) {
+            *sum = *sum + c;
+        }
+        self.passes += 1;
+    }
+
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    // average of the passes so far, black before the first
+    pub fn estimate(&self) -> Vec<Vector> {
+        let scale = 1.0 / self.passes.max(1) as f64;
+        self.sums.iter().map(|&s| scale * s).collect()
+    }
+}
@@ -0,0 +1,54 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// columns of the contact sheet
+const COLUMNS: usize = 2;
+
+// writes `index.html` and `contact.png` into `dir`, showing the rendered
+// previews `(name, file name in dir)` side by side
+pub fn write(dir: &Path, previews: &[(&str, String)]) -> io::Result<()> {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head><title>Gallery</title>\n\
+         <style>body { font-family: sans-serif; display: flex; flex-wrap: wrap; }\n\
+         figure { margin: 8px; }</style>\n</head>\n<body>\n",
+    );
+    for (name, file) in previews {
+        html.push_str(&format!(
+            "<figure><img src=\"{}\"><figcaption>{}</figcaption></figure>\n",
+            file, name
+        ));
+    }
+    html.push_str("</body>\n</html>\n");
+    fs::write(dir.join("index.html"), html)?;
+
+    let mut images = Vec::new();
+    for (_, file) in previews {
+        let image = lodepng::decode24_file(dir.join(file)).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("could not read \"{}\": {}", file, err),
+            )
+        })?;
+        images.push(image);
+    }
+
+    // tiles are sized for the largest preview, smaller ones sit in the corner
+    let tile_w = images.iter().map(|i| i.width).max().unwrap_or(0);
+    let tile_h = images.iter().map(|i| i.height).max().unwrap_or(0);
+    let rows = images.len().div_ceil(COLUMNS);
+    let (width, height) = (tile_w * COLUMNS.min(images.len()), tile_h * rows);
+
+    let mut sheet = vec![lodepng::RGB { r: 0u8, g: 0, b: 0 }; width * height];
+    for (n, image) in images.iter().enumerate() {
+        let (x0, y0) = ((n % COLUMNS) * tile_w, (n / COLUMNS) * tile_h);
+        for y in 0..image.height {
+            let src = &image.buffer[y * image.width..(y + 1) * image.width];
+            let start = (y0 + y) * width + x0;
+            sheet[start..start + image.width].copy_from_slice(src);
+        }
+    }
+
+    lodepng::encode24_file(dir.join("contact.png"), &sheet, width, height)
+        .map_err(|err| io::Error::other(err.to_string()))
+}
@@ -0,0 +1,212 @@
+use crate::aabb::Aabb;
+use crate::accelerator::Accelerator;
+use crate::hittable::{Hit, Hittable};
+use crate::ray::Ray;
+use crate::stats;
+
+// upper bound on cells per axis so huge scenes don't explode memory
+const MAX_RESOLUTION: usize = 64;
+
+// uniform grid over the scene bounds, cheap to build so it suits geometry that
+// changes every frame; traversed cell by cell with a 3D-DDA
+pub struct Grid {
+    cells: Vec<Vec<usize>>,
+    resolution: [usize; 3],
+    cell_size: [f64; 3],
+    primitives: Vec<Box<dyn Hittable>>,
+    bounds: Aabb,
+}
+
+impl Grid {
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Self {
+        let boxes: Vec<Aabb> = objects.iter().map(|o| o.bounding_box()).collect();
+        let bounds = boxes
+            .iter()
+            .fold(Aabb::empty(), |acc, b| acc.surrounding(b));
+        let extent = bounds.extent();
+
+        // roughly 3 cells per primitive, distributed proportionally to the extent
+        let max_extent = extent.x().max(extent.y()).max(extent.z());
+        let cells_per_unit = if max_extent > 0.0 {
+            (3.0 * objects.len() as f64).cbrt() / max_extent
+        } else {
+            0.0
+        };
+
+        let mut resolution = [1; 3];
+        let mut cell_size = [0.0; 3];
+        for axis in 0..3 {
+            let n = (extent.axis(axis) * cells_per_unit).round() as usize;
+            resolution[axis] = n.clamp(1, MAX_RESOLUTION);
+            cell_size[axis] = extent.axis(axis) / resolution[axis] as f64;
+        }
+
+        let mut grid = Self {
+            cells: vec![Vec::new(); resolution[0] * resolution[1] * resolution[2]],
+            resolution,
+            cell_size,
+            primitives: Vec::new(),
+            bounds,
+        };
+
+        for (i, b) in boxes.iter().enumerate() {
+            let lo = grid.cell_of(b.min.axis(0), b.min.axis(1), b.min.axis(2));
+            let hi = grid.cell_of(b.max.axis(0), b.max.axis(1), b.max.axis(2));
+
+            for z in lo[2]..=hi[2] {
+                for y in lo[1]..=hi[1] {
+                    for x in lo[0]..=hi[0] {
+                        let c = grid.index([x, y, z]);
+                        grid.cells[c].push(i);
+                    }
+                }
+            }
+        }
+
+        grid.primitives = objects;
+        grid
+    }
+
+    fn cell_of(&self, x: f64, y: f64, z: f64) -> [usize; 3] {
+        let p = [x, y, z];
+        let mut cell = [0; 3];
+        for axis in 0..3 {
+            let offset = p[axis] - self.bounds.min.axis(axis);
+            let c = if self.cell_size[axis] > 0.0 {
+                (offset / self.cell_size[axis]).floor().max(0.0) as usize
+            } else {
+                0
+            };
+            cell[axis] = c.min(self.resolution[axis] - 1);
+        }
+        cell
+    }
+
+    fn index(&self, cell: [usize; 3]) -> usize {
+        (cell[2] * self.resolution[1] + cell[1]) * self.resolution[0] + cell[0]
+    }
+}
+
+impl Grid {
+    // walks the cells pierced by the ray front to back, stopping once the limit
+    // returned by `visit_cell` lies inside the current cell
+    fn traverse(
+        &self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut visit_cell: impl FnMut(&[usize]) -> f64,
+    ) {
+        if self.primitives.is_empty() {
+            return;
+        }
+
+        let (t_enter, _) = match self.bounds.hit_range(r, t_min, t_max) {
+            Some(range) => range,
+            None => return,
+        };
+        let entry = r.line_to_p(t_enter);
+        let start = self.cell_of(entry.x(), entry.y(), entry.z());
+
+        let mut cell = [start[0] as i64, start[1] as i64, start[2] as i64];
+        let mut next_t = [f64::INFINITY; 3];
+        let mut delta_t = [f64::INFINITY; 3];
+        let mut step = [0i64; 3];
+        let mut out = [0i64; 3];
+
+        for axis in 0..3 {
+            let d = r.direction.axis(axis);
+            let size = self.cell_size[axis];
+            let cell_min = self.bounds.min.axis(axis) + cell[axis] as f64 * size;
+
+            if d > 0.0 {
+                next_t[axis] = t_enter + (cell_min + size - entry.axis(axis)) / d;
+                delta_t[axis] = size / d;
+                step[axis] = 1;
+                out[axis] = self.resolution[axis] as i64;
+            } else if d < 0.0 {
+                next_t[axis] = t_enter + (cell_min - entry.axis(axis)) / d;
+                delta_t[axis] = -size / d;
+                step[axis] = -1;
+                out[axis] = -1;
+            }
+        }
+
+        let counting = stats::counting();
+        loop {
+            let c = self.index([cell[0] as usize, cell[1] as usize, cell[2] as usize]);
+            if counting {
+                stats::count_node();
+                stats::count_primitives(self.cells[c].len());
+            }
+            let limit = visit_cell(&self.cells[c]);
+
+            // step across the nearest cell boundary
+            let axis = if next_t[0] < next_t[1] && next_t[0] < next_t[2] {
+                0
+            } else if next_t[1] < next_t[2] {
+                1
+            } else {
+                2
+            };
+
+            // the limit lies inside the current cell
+            if limit < next_t[axis] || next_t[axis] >= t_max {
+                break;
+            }
+
+            cell[axis] += step[axis];
+            if cell[axis] == out[axis] {
+                break;
+            }
+            next_t[axis] += delta_t[axis];
+        }
+    }
+}
+
+impl Hittable for Grid {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let mut closest: Option<Hit> = None;
+        let mut t_closest = t_max;
+
+        self.traverse(r, t_min, t_max, |items| {
+            for &p in items {
+                if let Some(mut hit) = self.primitives[p].ray_intersect(r, t_min, t_closest) {
+                    t_closest = hit.t;
+                    hit.object = p;
+                    closest = Some(hit);
+                }
+            }
+            t_closest
+        });
+
+        closest
+    }
+
+    fn occluded(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        let mut found = false;
+
+        self.traverse(r, t_min, t_max, |items| {
+            found = items
+                .iter()
+                .any(|&p| self.primitives[p].occluded(r, t_min, t_max));
+            if found {
+                f64::NEG_INFINITY
+            } else {
+                t_max
+            }
+        });
+
+        found
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+impl Accelerator for Grid {
+    fn len(&self) -> usize {
+        self.primitives.len()
+    }
+}
@@ -0,0 +1,125 @@
+use std::fs;
+use std::io;
+
+use crate::vector::Vector;
+
+// reader for Radiance RGBE (.hdr) images, the usual format of HDRI environment
+// maps. Returns width, height and linear pixels, rows top to bottom
+pub fn read(path: &str) -> io::Result<(usize, usize, Vec<Vector>)> {
+    let data = fs::read(path)?;
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("could not read \"{}\": {}", path, what),
+        )
+    };
+
+    if !data.starts_with(b"#?") {
+        return Err(invalid("not a Radiance HDR file"));
+    }
+
+    // text header up to an empty line, then the resolution line
+    let mut pos = 0;
+    let mut line = || -> Option<String> {
+        let end = data[pos..].iter().position(|&b| b == b'\n')? + pos;
+        let text = String::from_utf8_lossy(&data[pos..end]).trim().to_string();
+        pos = end + 1;
+        Some(text)
+    };
+
+    loop {
+        let l = line().ok_or_else(|| invalid("truncated header"))?;
+        if l.is_empty() {
+            break;
+        }
+        if let Some(format) = l.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid("only RGBE pixels are supported"));
+            }
+        }
+    }
+
+    let resolution = line().ok_or_else(|| invalid("missing resolution"))?;
+    let fields: Vec<&str> = resolution.split_whitespace().collect();
+    let (height, width) = match fields.as_slice() {
+        ["-Y", h, "+X", w] => (h.parse::<usize>(), w.parse::<usize>()),
+        _ => return Err(invalid("only -Y +X images are supported")),
+    };
+    let (height, width) = match (height, width) {
+        (Ok(h), Ok(w)) if h > 0 && w > 0 => (h, w),
+        _ => return Err(invalid("bad resolution")),
+    };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut rest = &data[pos..];
+    let mut scanline = vec![[0u8; 4]; width];
+
+    for _ in 0..height {
+        rest = read_scanline(rest, &mut scanline).ok_or_else(|| invalid("truncated pixels"))?;
+        pixels.extend(scanline.iter().map(|&rgbe| rgbe_to_vector(rgbe)));
+    }
+
+    Ok((width, height, pixels))
+}
+
+// decodes one row into `out`, returning the remaining bytes
+fn read_scanline<'a>(data: &'a [u8], out: &mut [[u8; 4]]) -> Option<&'a [u8]> {
+    let width = out.len();
+
+    // new-style run length encoding: 2, 2, width, then each channel separately
+    let rle = (8..0x8000).contains(&width)
+        && data.len() >= 4
+        && data[0] == 2
+        && data[1] == 2
+        && ((data[2] as usize) << 8 | data[3] as usize) == width;
+
+    if !rle {
+        let bytes = data.get(..width * 4)?;
+        for (pixel, chunk) in out.iter_mut().zip(bytes.chunks_exact(4)) {
+            pixel.copy_from_slice(chunk);
+        }
+        return Some(&data[width * 4..]);
+    }
+
+    let mut data = &data[4..];
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *data.first()? as usize;
+            if count > 128 {
+                // a run of one repeated value
+                let count = count - 128;
+                let value = *data.get(1)?;
+                if x + count > width {
+                    return None;
+                }
+                for pixel in &mut out[x..x + count] {
+                    pixel[channel] = value;
+                }
+                data = &data[2..];
+                x += count;
+            } else {
+                if count == 0 || x + count > width {
+                    return None;
+                }
+                let values = data.get(1..1 + count)?;
+                for (pixel, &value) in out[x..x + count].iter_mut().zip(values) {
+                    pixel[channel] = value;
+                }
+                data = &data[1 + count..];
+                x += count;
+            }
+        }
+    }
+
+    Some(data)
+}
+
+fn rgbe_to_vector([r, g, b, e]: [u8; 4]) -> Vector {
+    if e == 0 {
+        return Vector(0.0, 0.0, 0.0);
+    }
+    // mantissas are in [0, 256) scaled by 2^(e - 128)
+    let scale = 2f64.powi(e as i32 - 136);
+    Vector(r as f64 * scale, g as f64 * scale, b as f64 * scale)
+}
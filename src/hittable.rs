@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::material::Scatter;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+pub struct Hit {
+    pub t: f64,
+    pub p: Vector,
+    // the hit point in the primitive's own space, before any `Instance`
+    // transforms; the same as `p` for objects placed directly in the scene
+    pub local: Vector,
+    // always points against the incoming ray
+    pub normal: Vector,
+    // true when the ray hit the outside of the surface
+    pub front_face: bool,
+    pub material: Arc<dyn Scatter>,
+    // surface coordinates for texture lookups, usually in [0, 1]
+    pub u: f64,
+    pub v: f64,
+    // how the hit point moves with u and v, for bump mapping; zero when unknown
+    pub dpdu: Vector,
+    pub dpdv: Vector,
+    // position of the hit object in the list the scene was built from, set by
+    // the accelerators; primitives leave it at 0
+    pub object: usize,
+}
+
+// anything a ray can be intersected with: primitives, lists and acceleration structures
+pub trait Hittable: Send + Sync {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit>;
+
+    // any-hit query for shadow rays: true if anything lies within [t_min, t_max],
+    // implementations may stop at the first hit instead of searching for the closest
+    fn occluded(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.ray_intersect(r, t_min, t_max).is_some()
+    }
+
+    fn bounding_box(&self) -> Aabb;
+}
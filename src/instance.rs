@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::material::Scatter;
+use crate::ray::Ray;
+use crate::transform::Transform;
+
+// a placement of shared geometry that can swap out materials without copying the geometry;
+// wrapping a Bvh and collecting instances into another Bvh gives a two-level hierarchy
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    // object to world space
+    transform: Transform,
+    // object to world space at the end of the frame, for motion blur
+    end_transform: Option<Transform>,
+    // (original, replacement) pairs, matched by pointer at hit time
+    materials: Vec<(Arc<dyn Scatter>, Arc<dyn Scatter>)>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>) -> Self {
+        Self {
+            object,
+            transform: Transform::identity(),
+            end_transform: None,
+            materials: Vec::new(),
+        }
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    // moves the instance from its transform at the start of the frame to `end`
+    // at its end, blurred over the camera's shutter interval
+    pub fn set_motion(&mut self, end: Transform) {
+        self.end_transform = Some(end);
+    }
+
+    fn transform_at(&self, time: f64) -> Transform {
+        match &self.end_transform {
+            Some(end) => self.transform.lerp(end, time),
+            None => self.transform,
+        }
+    }
+
+    // render every surface of the shared object using `original` with `replacement` instead
+    pub fn override_material(&mut self, original: Arc<dyn Scatter>, replacement: Arc<dyn Scatter>) {
+        self.materials.retain(|(m, _)| !Arc::ptr_eq(m, &original));
+        self.materials.push((original, replacement));
+    }
+
+    // the direction is not renormalized so t is the same in both spaces
+    fn to_local(&self, r: &Ray, transform: &Transform) -> Ray {
+        Ray::new(
+            transform.inverse_point(r.origin),
+            transform.inverse_vector(r.direction),
+        )
+        .at_time(r.time)
+    }
+
+    fn resolve_material(&self, material: Arc<dyn Scatter>) -> Arc<dyn Scatter> {
+        for (original, replacement) in &self.materials {
+            if Arc::ptr_eq(original, &material) {
+                return replacement.clone();
+            }
+        }
+
+        material
+    }
+}
+
+impl Hittable for Instance {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let transform = self.transform_at(r.time);
+        let local = self.to_local(r, &transform);
+
+        let mut hit = self.object.ray_intersect(&local, t_min, t_max)?;
+        hit.p = r.line_to_p(hit.t);
+        hit.normal = transform.normal(hit.normal);
+        hit.dpdu = transform.vector(hit.dpdu);
+        hit.dpdv = transform.vector(hit.dpdv);
+        hit.material = self.resolve_material(hit.material);
+
+        Some(hit)
+    }
+
+    fn occluded(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        let transform = self.transform_at(r.time);
+        self.object
+            .occluded(&self.to_local(r, &transform), t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // points move in straight lines between the two ends, so both boxes cover the path
+        let b = self.object.bounding_box();
+        match &self.end_transform {
+            Some(end) => self
+                .transform
+                .bounding_box(&b)
+                .surrounding(&end.bounding_box(&b)),
+            None => self.transform.bounding_box(&b),
+        }
+    }
+}
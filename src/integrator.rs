@@ -0,0 +1,125 @@
+use crate::hittable::Hit;
+use crate::materials::dielectric::Dielectric;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::utils;
+use crate::vector::Vector;
+
+// how the light arriving along a camera ray is estimated, chosen per render
+// with `Scene::set_integrator`. `hit` is the ray's closest hit, traced by the
+// caller so camera rays can go through the scene in packets; random numbers
+// come from the thread's generator in `utils`
+pub trait Integrator: Send + Sync {
+    fn li(&self, scene: &Scene, ray: Ray, hit: Option<Hit>) -> Vector;
+}
+
+// full global illumination: lights, environment and every bounce between
+// surfaces, see `Scene::shade`. The default
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn li(&self, scene: &Scene, ray: Ray, hit: Option<Hit>) -> Vector {
+        scene.shade(ray, hit, 0)
+    }
+}
+
+// classic recursive ray tracing: surfaces see the lights directly and follow
+// only mirror and glass bounces, so there is no color bleeding or soft sky
+// light but far less noise. Good for checking light placement. The scene's
+// ambient fill, with baked occlusion, stands in for the missing bounces.
+// Diffuse surfaces get a thin glossy layer like plastic: a mirror reflection
+// weighted by Schlick's Fresnel term, faint head-on and strong at grazing angles
+pub struct Whitted {
+    // index of refraction of that layer, 1.5 for most plastics and paints
+    pub ior: f64,
+}
+
+impl Whitted {
+    pub fn new(ior: f64) -> Self {
+        Self { ior }
+    }
+
+    fn trace(&self, scene: &Scene, ray: Ray, hit: Option<Hit>, depth: i32) -> Vector {
+        let h = match hit {
+            Some(h) => h,
+            None => return scene.environment().radiance(ray.direction),
+        };
+
+        let mut color = scene.direct_light(&ray, &h) + scene.caustic_light(&ray, &h);
+        let can_bounce = depth < scene.settings.max_depth;
+
+        // materials without a BRDF to evaluate are the mirror-like ones
+        let specular = h.material.eval(&ray, &h, h.normal).is_none();
+        if !specular {
+            color = color + scene.ambient_light(&ray, &h);
+        }
+        if specular && can_bounce {
+            if let Some((scattered, attenuation)) = h.material.scatter(&ray, &h) {
+                let scattered = scattered.at_time(ray.time);
+                let next = scene.check_hits(&scattered);
+                color = color + attenuation * self.trace(scene, scattered, next, depth + 1);
+            }
+        } else if h.material.diffuse(&h).is_some() {
+            let unit_direction = ray.direction.to_unit_vector();
+            let cosine = (-unit_direction).dot(h.normal).clamp(0.0, 1.0);
+            let reflectance = Dielectric::reflectance(cosine, 1.0 / self.ior);
+            // what the coating reflects never reaches the diffuse base
+            color = (1.0 - reflectance) * color;
+            if can_bounce {
+                let reflected = Ray::new(h.p, unit_direction.reflect(h.normal)).at_time(ray.time);
+                let next = scene.check_hits(&reflected);
+                color = color + reflectance * self.trace(scene, reflected, next, depth + 1);
+            }
+        } else if can_bounce {
+            // the mirror-like part of a blend, as for `Mix` over metal
+            if let Some((scattered, attenuation, true)) = h.material.scatter_lobe(&ray, &h) {
+                let scattered = scattered.at_time(ray.time);
+                let next = scene.check_hits(&scattered);
+                color = color + attenuation * self.trace(scene, scattered, next, depth + 1);
+            }
+        }
+
+        h.material.emitted(&ray, &h) + color
+    }
+}
+
+impl Integrator for Whitted {
+    fn li(&self, scene: &Scene, ray: Ray, hit: Option<Hit>) -> Vector {
+        self.trace(scene, ray, hit, 0)
+    }
+}
+
+// white where the open sky is visible, darker in creases and contact spots:
+// the fraction of cosine-weighted directions that stay clear for `radius`.
+// A fast clay render that shows the geometry without materials or lights;
+// the radius sets how far apart surfaces still darken each other
+pub struct AmbientOcclusion {
+    pub radius: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn li(&self, scene: &Scene, _ray: Ray, hit: Option<Hit>) -> Vector {
+        let h = match hit {
+            Some(h) => h,
+            None => return Vector(1.0, 1.0, 1.0),
+        };
+
+        let mut direction = h.normal + utils::random_unit_vector();
+        if direction.near_zero() {
+            direction = h.normal;
+        }
+        let probe = Ray::new(h.p, direction.to_unit_vector());
+
+        if scene.check_occluded(&probe, self.radius) {
+            Vector(0.0, 0.0, 0.0)
+        } else {
+            Vector(1.0, 1.0, 1.0)
+        }
+    }
+}
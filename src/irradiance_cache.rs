@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::vector::Vector;
+
+// indirect light arriving at a point on a diffuse surface, gathered over the
+// hemisphere around its normal
+pub struct IrradianceRecord {
+    pub position: Vector,
+    pub normal: Vector,
+    pub irradiance: Vector,
+    // harmonic mean distance to the surfaces seen from the point, the closer
+    // they are the faster the irradiance changes around it
+    pub radius: f64,
+}
+
+// irradiance records interpolated with Ward's weights: a record is used at
+// points within `accuracy` times its radius that face about the same way.
+// They're filed in hashed grids of power of two cells, each record in the
+// grid whose cells are just larger than the distance it reaches
+pub struct IrradianceCache {
+    records: Vec<IrradianceRecord>,
+    accuracy: f64,
+    cells: HashMap<(i32, i64, i64, i64), Vec<usize>>,
+    // log2 of the cell sizes in use
+    levels: Vec<i32>,
+}
+
+impl IrradianceCache {
+    pub fn new(accuracy: f64) -> Self {
+        Self {
+            records: Vec::new(),
+            accuracy,
+            cells: HashMap::new(),
+            levels: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn insert(&mut self, record: IrradianceRecord) {
+        let reach = self.accuracy * record.radius;
+        let level = reach.log2().ceil().max(-60.0) as i32;
+        if let Err(i) = self.levels.binary_search(&level) {
+            self.levels.insert(i, level);
+        }
+
+        // the record's reach is at most one cell, so it overlaps two per axis
+        let low = cell(record.position - Vector(reach, reach, reach), level);
+        let high = cell(record.position + Vector(reach, reach, reach), level);
+        let index = self.records.len();
+        for x in low.0..=high.0 {
+            for y in low.1..=high.1 {
+                for z in low.2..=high.2 {
+                    self.cells.entry((level, x, y, z)).or_default().push(index);
+                }
+            }
+        }
+        self.records.push(record);
+    }
+
+    // irradiance at `p` on a surface facing `normal`, None where no record
+    // is close enough to stand in for it
+    pub fn irradiance(&self, p: Vector, normal: Vector) -> Option<Vector> {
+        let mut total = Vector(0.0, 0.0, 0.0);
+        let mut total_weight = 0.0;
+        for &level in &self.levels {
+            let (x, y, z) = cell(p, level);
+            let indices = match self.cells.get(&(level, x, y, z)) {
+                Some(indices) => indices,
+                None => continue,
+            };
+            for &i in indices {
+                let record = &self.records[i];
+                let offset = p - record.position;
+                // records in front of `p` see surfaces it doesn't
+                if offset.dot(normal + record.normal) < -0.02 * record.radius {
+                    continue;
+                }
+
+                let error = offset.length() / record.radius
+                    + (1.0 - normal.dot(record.normal)).max(0.0).sqrt();
+                if error >= self.accuracy {
+                    continue;
+                }
+                // falls to 0 at the edge of the record's reach, so the
+                // interpolation doesn't jump where a record drops out
+                let weight = 1.0 / error.max(1e-9) - 1.0 / self.accuracy;
+                total = total + weight * record.irradiance;
+                total_weight += weight;
+            }
+        }
+
+        if total_weight > 0.0 {
+            Some(total / total_weight)
+        } else {
+            None
+        }
+    }
+}
+
+// grid cell containing `p` at a cell size of 2^level
+fn cell(p: Vector, level: i32) -> (i64, i64, i64) {
+    let size = 2f64.powi(level);
+    (
+        (p.x() / size).floor() as i64,
+        (p.y() / size).floor() as i64,
+        (p.z() / size).floor() as i64,
+    )
+}
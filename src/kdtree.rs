@@ -0,0 +1,331 @@
+use crate::aabb::Aabb;
+use crate::accelerator::Accelerator;
+use crate::hittable::{Hit, Hittable};
+use crate::ray::Ray;
+use crate::stats;
+use crate::vector::Vector;
+
+// relative costs used by the SAH split search
+const TRAVERSAL_COST: f64 = 1.0;
+const INTERSECT_COST: f64 = 80.0;
+// discount for splits that leave one side empty
+const EMPTY_BONUS: f64 = 0.5;
+// splits allowed to be worse than a leaf before giving up on a subtree
+const MAX_BAD_REFINES: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+enum KdNode {
+    // range into the index list
+    Leaf {
+        start: usize,
+        count: usize,
+    },
+    // the below child always directly follows its parent in the node list
+    Interior {
+        axis: usize,
+        split: f64,
+        above: usize,
+    },
+}
+
+// kd-tree over primitive bounding boxes; primitives straddling a split plane
+// are referenced from both sides
+pub struct KdTree {
+    nodes: Vec<KdNode>,
+    indices: Vec<usize>,
+    primitives: Vec<Box<dyn Hittable>>,
+    bounds: Aabb,
+}
+
+struct Builder<'a> {
+    boxes: &'a [Aabb],
+    nodes: Vec<KdNode>,
+    indices: Vec<usize>,
+}
+
+impl KdTree {
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Self {
+        let boxes: Vec<Aabb> = objects.iter().map(|o| o.bounding_box()).collect();
+        let bounds = boxes
+            .iter()
+            .fold(Aabb::empty(), |acc, b| acc.surrounding(b));
+
+        let mut builder = Builder {
+            boxes: &boxes,
+            nodes: Vec::new(),
+            indices: Vec::new(),
+        };
+
+        if !objects.is_empty() {
+            let max_depth = (8.0 + 1.3 * (objects.len() as f64).log2()).round() as usize;
+            builder.build(bounds, (0..objects.len()).collect(), max_depth, 0);
+        }
+
+        Self {
+            nodes: builder.nodes,
+            indices: builder.indices,
+            primitives: objects,
+            bounds,
+        }
+    }
+}
+
+impl Builder<'_> {
+    fn build(&mut self, bounds: Aabb, items: Vec<usize>, depth: usize, bad_refines: usize) {
+        let index = self.nodes.len();
+
+        if items.len() <= 1 || depth == 0 {
+            self.push_leaf(&items);
+            return;
+        }
+
+        let leaf_cost = INTERSECT_COST * items.len() as f64;
+        let (cost, axis, split) = match self.find_split(&bounds, &items) {
+            Some(best) => best,
+            None => {
+                self.push_leaf(&items);
+                return;
+            }
+        };
+
+        let bad_refines = if cost > leaf_cost {
+            bad_refines + 1
+        } else {
+            bad_refines
+        };
+
+        if (cost > 4.0 * leaf_cost && items.len() < 16) || bad_refines == MAX_BAD_REFINES {
+            self.push_leaf(&items);
+            return;
+        }
+
+        let below: Vec<usize> = items
+            .iter()
+            .copied()
+            .filter(|&i| self.boxes[i].min.axis(axis) <= split)
+            .collect();
+        let above: Vec<usize> = items
+            .iter()
+            .copied()
+            .filter(|&i| self.boxes[i].max.axis(axis) >= split)
+            .collect();
+
+        let (mut below_bounds, mut above_bounds) = (bounds, bounds);
+        below_bounds.max = with_axis(below_bounds.max, axis, split);
+        above_bounds.min = with_axis(above_bounds.min, axis, split);
+
+        // placeholder, patched once the above child's index is known
+        self.nodes.push(KdNode::Leaf { start: 0, count: 0 });
+        self.build(below_bounds, below, depth - 1, bad_refines);
+        let above_index = self.nodes.len();
+        self.build(above_bounds, above, depth - 1, bad_refines);
+
+        self.nodes[index] = KdNode::Interior {
+            axis,
+            split,
+            above: above_index,
+        };
+    }
+
+    fn push_leaf(&mut self, items: &[usize]) {
+        self.nodes.push(KdNode::Leaf {
+            start: self.indices.len(),
+            count: items.len(),
+        });
+        self.indices.extend_from_slice(items);
+    }
+
+    // sweeps the bounding box edges of every item along each axis and returns
+    // the cheapest (cost, axis, position) split strictly inside `bounds`
+    fn find_split(&self, bounds: &Aabb, items: &[usize]) -> Option<(f64, usize, f64)> {
+        let extent = bounds.extent();
+        let area = bounds.surface_area();
+        let mut best: Option<(f64, usize, f64)> = None;
+
+        for axis in 0..3 {
+            // (position, is_end) pairs; starts sort before ends at the same position
+            let mut edges: Vec<(f64, bool)> = items
+                .iter()
+                .flat_map(|&i| {
+                    [
+                        (self.boxes[i].min.axis(axis), false),
+                        (self.boxes[i].max.axis(axis), true),
+                    ]
+                })
+                .collect();
+            edges.sort_by(|a, b| {
+                a.0.partial_cmp(&b.0)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.1.cmp(&b.1))
+            });
+
+            let (other0, other1) = ((axis + 1) % 3, (axis + 2) % 3);
+            let cap_area = 2.0 * extent.axis(other0) * extent.axis(other1);
+            let side = extent.axis(other0) + extent.axis(other1);
+
+            let mut below = 0;
+            let mut above = items.len();
+
+            for &(position, is_end) in &edges {
+                if is_end {
+                    above -= 1;
+                }
+
+                if position > bounds.min.axis(axis) && position < bounds.max.axis(axis) {
+                    let below_area = cap_area + 2.0 * (position - bounds.min.axis(axis)) * side;
+                    let above_area = cap_area + 2.0 * (bounds.max.axis(axis) - position) * side;
+                    let bonus = if below == 0 || above == 0 {
+                        EMPTY_BONUS
+                    } else {
+                        0.0
+                    };
+                    let cost = TRAVERSAL_COST
+                        + INTERSECT_COST
+                            * (1.0 - bonus)
+                            * (below_area / area * below as f64 + above_area / area * above as f64);
+
+                    if best.is_none_or(|(c, _, _)| cost < c) {
+                        best = Some((cost, axis, position));
+                    }
+                }
+
+                if !is_end {
+                    below += 1;
+                }
+            }
+        }
+
+        best
+    }
+}
+
+fn with_axis(v: Vector, axis: usize, value: f64) -> Vector {
+    match axis {
+        0 => Vector(value, v.y(), v.z()),
+        1 => Vector(v.x(), value, v.z()),
+        _ => Vector(v.x(), v.y(), value),
+    }
+}
+
+impl KdTree {
+    // walks the leaves pierced by the ray front to back, stopping once the limit
+    // returned by `visit_leaf` lies before the next leaf
+    fn traverse(
+        &self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut visit_leaf: impl FnMut(&[usize]) -> f64,
+    ) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let (mut node_min, mut node_max) = match self.bounds.hit_range(r, t_min, t_max) {
+            Some(range) => range,
+            None => return,
+        };
+        let counting = stats::counting();
+        let mut limit = t_max;
+        let mut stack: Vec<(usize, f64, f64)> = Vec::new();
+        let mut i = 0;
+
+        loop {
+            // everything left is further away than the limit
+            if limit < node_min {
+                break;
+            }
+
+            if counting {
+                stats::count_node();
+            }
+            match self.nodes[i] {
+                KdNode::Interior { axis, split, above } => {
+                    let origin = r.origin.axis(axis);
+                    let direction = r.direction.axis(axis);
+                    let t_plane = (split - origin) / direction;
+
+                    let below_first = origin < split || (origin == split && direction <= 0.0);
+                    let (first, second) = if below_first {
+                        (i + 1, above)
+                    } else {
+                        (above, i + 1)
+                    };
+
+                    if t_plane > node_max || t_plane <= 0.0 {
+                        i = first;
+                    } else if t_plane < node_min {
+                        i = second;
+                    } else {
+                        stack.push((second, t_plane, node_max));
+                        i = first;
+                        node_max = t_plane;
+                    }
+                    continue;
+                }
+                KdNode::Leaf { start, count } => {
+                    if counting {
+                        stats::count_primitives(count);
+                    }
+                    limit = visit_leaf(&self.indices[start..start + count]);
+                }
+            }
+
+            match stack.pop() {
+                Some((next, next_min, next_max)) => {
+                    i = next;
+                    node_min = next_min;
+                    node_max = next_max;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl Hittable for KdTree {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let mut closest: Option<Hit> = None;
+        let mut t_closest = t_max;
+
+        self.traverse(r, t_min, t_max, |items| {
+            for &p in items {
+                if let Some(mut hit) = self.primitives[p].ray_intersect(r, t_min, t_closest) {
+                    t_closest = hit.t;
+                    hit.object = p;
+                    closest = Some(hit);
+                }
+            }
+            t_closest
+        });
+
+        closest
+    }
+
+    fn occluded(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        let mut found = false;
+
+        self.traverse(r, t_min, t_max, |items| {
+            found = items
+                .iter()
+                .any(|&p| self.primitives[p].occluded(r, t_min, t_max));
+            if found {
+                f64::NEG_INFINITY
+            } else {
+                t_max
+            }
+        });
+
+        found
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+impl Accelerator for KdTree {
+    fn len(&self) -> usize {
+        self.primitives.len()
+    }
+}
@@ -0,0 +1,58 @@
+extern crate indicatif;
+extern crate lodepng;
+extern crate rand;
+extern crate ttf_parser;
+
+pub mod aabb;
+pub mod accelerator;
+pub mod bdpt;
+pub mod billboard;
+pub mod bvh;
+pub mod camera;
+pub mod camera_path;
+pub mod color;
+pub mod cuboid;
+pub mod cutout;
+pub mod dataset;
+pub mod environment;
+pub mod fractals;
+pub mod framebuffer;
+pub mod gallery;
+pub mod grid;
+pub mod hdr;
+pub mod hittable;
+pub mod instance;
+pub mod integrator;
+pub mod irradiance_cache;
+pub mod kdtree;
+pub mod light;
+pub mod material;
+pub mod material_library;
+pub mod materials;
+pub mod metadata;
+#[cfg(feature = "debug-nan")]
+pub mod nan;
+pub mod partial;
+pub mod perlin;
+pub mod photon_map;
+pub mod physics;
+pub mod plane;
+pub mod presets;
+pub mod procedural;
+pub mod ray;
+pub mod sampler;
+pub mod scatter;
+pub mod scene;
+pub mod settings;
+pub mod sky;
+pub mod sphere;
+pub mod stats;
+pub mod text;
+pub mod texture;
+pub mod transform;
+pub mod triangle;
+pub mod utils;
+pub mod vector;
+
+pub static ASPECT_RATIO: f64 = 16.0 / 9.0;
+pub static VIEWPORT_WIDTH: i32 = 1600;
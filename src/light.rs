@@ -0,0 +1,572 @@
+use crate::ray::Ray;
+use crate::utils::{orthonormal_basis, random_in_range, random_in_unit_disk, random_unit_vector};
+use crate::vector::Vector;
+
+// light source that isn't part of the geometry: camera rays never see it, but
+// `Scene` samples it directly at every diffuse or glossy hit, so even tiny
+// lights converge quickly. Rect and sphere lights are also found by scattered
+// rays, which shows them in mirrors and keeps glossy highlights clean.
+// Emissive objects are still the way to get lamps the camera sees
+pub enum Light {
+    // lamp radiating `intensity` (W/sr) of `color` in every direction; a
+    // radius above zero softens its shadows
+    Point {
+        position: Vector,
+        color: Vector,
+        intensity: f64,
+        radius: f64,
+        falloff: Falloff,
+    },
+    // infinitely far light such as the sun, shining along `direction` with
+    // `intensity` (W/m^2) of irradiance; the angular radius in degrees, about
+    // 0.27 for the real sun, softens its shadows
+    Directional {
+        direction: Vector,
+        color: Vector,
+        intensity: f64,
+        angular_radius: f64,
+    },
+    // one-sided rectangular panel with corner `corner` and sides `edge_u` and
+    // `edge_v`, emitting `radiance` (W/m^2/sr) of `color` from the side
+    // edge_u x edge_v points to; sampled by area for soft shadows that match its size
+    Rect {
+        corner: Vector,
+        edge_u: Vector,
+        edge_v: Vector,
+        color: Vector,
+        radiance: f64,
+    },
+    // glowing ball emitting `radiance` (W/m^2/sr) of `color`, sampled over the
+    // cone it subtends so its soft shadows stay low-noise at any size
+    Sphere {
+        center: Vector,
+        radius: f64,
+        color: Vector,
+        radiance: f64,
+    },
+}
+
+// how a point light dims with distance: 1 / max(distance, min_distance)^exponent.
+// Physical lights fall off with the inverse square; a lower exponent reaches
+// further for stylized looks, and the minimum distance keeps surfaces right
+// next to the lamp from blowing out
+#[derive(Clone, Copy)]
+pub struct Falloff {
+    pub exponent: f64,
+    pub min_distance: f64,
+}
+
+impl Default for Falloff {
+    fn default() -> Self {
+        Self::inverse_square()
+    }
+}
+
+impl Falloff {
+    pub fn inverse_square() -> Self {
+        Self {
+            exponent: 2.0,
+            min_distance: 0.0,
+        }
+    }
+
+    pub fn linear() -> Self {
+        Self {
+            exponent: 1.0,
+            min_distance: 0.0,
+        }
+    }
+
+    pub fn attenuation(&self, distance: f64) -> f64 {
+        1.0 / distance.max(self.min_distance).powf(self.exponent)
+    }
+}
+
+// which `Light`s shine on an object, by the index `Scene::add_light` returned.
+// Art direction, not physics: a fill light can skip the backdrop, a rim light
+// can touch only the hero
+#[derive(Clone, Default)]
+pub enum LightLinks {
+    #[default]
+    All,
+    Only(Vec<usize>),
+    Except(Vec<usize>),
+}
+
+impl LightLinks {
+    pub fn includes(&self, light: usize) -> bool {
+        match self {
+            LightLinks::All => true,
+            LightLinks::Only(lights) => lights.contains(&light),
+            LightLinks::Except(lights) => !lights.contains(&light),
+        }
+    }
+}
+
+// one light sample as seen from a shaded point
+pub struct LightSample {
+    // unit vector toward the sampled point on the light
+    pub direction: Vector,
+    // how far along `direction` the shadow ray must stay clear
+    pub distance: f64,
+    // irradiance arriving perpendicular to `direction`
+    pub irradiance: Vector,
+    // solid angle density `direction` was picked with, None for point-like
+    // lights that scattered rays can never hit
+    pub pdf: Option<f64>,
+}
+
+// start of a light path, see `Light::sample_emission`
+pub struct Emission {
+    pub origin: Vector,
+    // of the light's surface at the origin, zero for point lights
+    pub normal: Vector,
+    // unit vector the light leaves along
+    pub direction: Vector,
+    // radiance along `direction`, radiant intensity for point lights
+    pub radiance: Vector,
+    // area density of the origin, zero for point lights
+    pub pdf_position: f64,
+    // solid angle density of the direction
+    pub pdf_direction: f64,
+}
+
+impl Light {
+    pub fn point(position: Vector, color: Vector, intensity: f64, radius: f64) -> Self {
+        Light::Point {
+            position,
+            color,
+            intensity,
+            radius,
+            falloff: Falloff::default(),
+        }
+    }
+
+    // replaces a point light's inverse-square falloff, other lights keep theirs
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        if let Light::Point { falloff: f, .. } = &mut self {
+            *f = falloff;
+        }
+        self
+    }
+
+    pub fn directional(
+        direction: Vector,
+        color: Vector,
+        intensity: f64,
+        angular_radius: f64,
+    ) -> Self {
+        Light::Directional {
+            direction,
+            color,
+            intensity,
+            angular_radius,
+        }
+    }
+
+    pub fn rect(
+        corner: Vector,
+        edge_u: Vector,
+        edge_v: Vector,
+        color: Vector,
+        radiance: f64,
+    ) -> Self {
+        Light::Rect {
+            corner,
+            edge_u,
+            edge_v,
+            color,
+            radiance,
+        }
+    }
+
+    pub fn sphere(center: Vector, radius: f64, color: Vector, radiance: f64) -> Self {
+        Light::Sphere {
+            center,
+            radius,
+            color,
+            radiance,
+        }
+    }
+
+    pub fn sample(&self, p: Vector) -> Option<LightSample> {
+        match self {
+            Light::Point {
+                position,
+                color,
+                intensity,
+                radius,
+                falloff,
+            } => {
+                let target = *position + *radius * random_unit_vector();
+                let offset = target - p;
+                let distance = offset.length();
+                if distance <= 0.0 {
+                    return None;
+                }
+
+                Some(LightSample {
+                    direction: offset / distance,
+                    distance,
+                    irradiance: (*intensity * falloff.attenuation(distance)) * *color,
+                    pdf: None,
+                })
+            }
+            Light::Directional {
+                direction,
+                color,
+                intensity,
+                angular_radius,
+            } => {
+                // uniform direction inside the cone the light disc subtends
+                let toward = -direction.to_unit_vector();
+                let cos_max = angular_radius.to_radians().cos();
+                let cos_theta = 1.0 - random_in_range(0.0, 1.0) * (1.0 - cos_max);
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * std::f64::consts::PI * random_in_range(0.0, 1.0);
+                let (t, b) = orthonormal_basis(toward);
+
+                Some(LightSample {
+                    direction: sin_theta * phi.cos() * t
+                        + sin_theta * phi.sin() * b
+                        + cos_theta * toward,
+                    distance: f64::INFINITY,
+                    irradiance: *intensity * *color,
+                    pdf: None,
+                })
+            }
+            Light::Rect {
+                corner,
+                edge_u,
+                edge_v,
+                color,
+                radiance,
+            } => {
+                let target = *corner
+                    + random_in_range(0.0, 1.0) * *edge_u
+                    + random_in_range(0.0, 1.0) * *edge_v;
+                let offset = target - p;
+                let distance = offset.length();
+                let normal = edge_u.cross(*edge_v);
+                let area = normal.length();
+                if distance <= 0.0 || area <= 0.0 {
+                    return None;
+                }
+
+                let direction = offset / distance;
+                let cos_light = -direction.dot(normal / area);
+                if cos_light <= 0.0 {
+                    return None;
+                }
+
+                // radiance over the area pdf 1 / area, converted to solid angle
+                let pdf = distance * distance / (cos_light * area);
+                Some(LightSample {
+                    direction,
+                    distance,
+                    irradiance: (*radiance / pdf) * *color,
+                    pdf: Some(pdf),
+                })
+            }
+            Light::Sphere {
+                center,
+                radius,
+                color,
+                radiance,
+            } => {
+                let offset = *center - p;
+                let d = offset.length();
+                if d <= *radius {
+                    return None;
+                }
+
+                // uniform direction in the cone, pdf 1 / (2 pi (1 - cos_max))
+                let sin2_max = radius * radius / (d * d);
+                let cos_max = (1.0 - sin2_max).max(0.0).sqrt();
+                let cos_theta = 1.0 - random_in_range(0.0, 1.0) * (1.0 - cos_max);
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * std::f64::consts::PI * random_in_range(0.0, 1.0);
+                let axis = offset / d;
+                let (t, b) = orthonormal_basis(axis);
+                let direction =
+                    sin_theta * phi.cos() * t + sin_theta * phi.sin() * b + cos_theta * axis;
+
+                // nearer intersection of the sampled direction with the sphere
+                let distance = d * cos_theta
+                    - (radius * radius - d * d * sin_theta * sin_theta)
+                        .max(0.0)
+                        .sqrt();
+
+                let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_max);
+                Some(LightSample {
+                    direction,
+                    distance,
+                    irradiance: (*radiance * solid_angle) * *color,
+                    pdf: Some(1.0 / solid_angle),
+                })
+            }
+        }
+    }
+
+    // a random point on the light and direction out of it, for light paths
+    // that start anywhere around it. None for directional lights, which have
+    // no position to start from
+    pub fn sample_emission(&self) -> Option<Emission> {
+        let (origin, normal, radiance) = match self {
+            Light::Point {
+                position,
+                color,
+                intensity,
+                ..
+            } => {
+                let direction = random_unit_vector();
+                return Some(Emission {
+                    origin: *position,
+                    normal: Vector(0.0, 0.0, 0.0),
+                    direction,
+                    radiance: *intensity * *color,
+                    pdf_position: 0.0,
+                    pdf_direction: 1.0 / (4.0 * std::f64::consts::PI),
+                });
+            }
+            Light::Directional { .. } => return None,
+            Light::Rect {
+                corner,
+                edge_u,
+                edge_v,
+                color,
+                radiance,
+            } => {
+                let origin = *corner
+                    + random_in_range(0.0, 1.0) * *edge_u
+                    + random_in_range(0.0, 1.0) * *edge_v;
+                (
+                    origin,
+                    edge_u.cross(*edge_v).to_unit_vector(),
+                    *radiance * *color,
+                )
+            }
+            Light::Sphere {
+                center,
+                radius,
+                color,
+                radiance,
+            } => {
+                let normal = random_unit_vector();
+                (*center + *radius * normal, normal, *radiance * *color)
+            }
+        };
+
+        // cosine-weighted out of the emitting side
+        let mut direction = normal + random_unit_vector();
+        if direction.near_zero() {
+            direction = normal;
+        }
+        let direction = direction.to_unit_vector();
+
+        Some(Emission {
+            origin,
+            normal,
+            direction,
+            radiance,
+            pdf_position: self.pdf_emission_position(),
+            pdf_direction: self.pdf_emission_direction(normal, direction),
+        })
+    }
+
+    // area density `sample_emission` picks its origin with, zero for point
+    // and directional lights
+    pub fn pdf_emission_position(&self) -> f64 {
+        match self {
+            Light::Point { .. } | Light::Directional { .. } => 0.0,
+            Light::Rect { edge_u, edge_v, .. } => 1.0 / edge_u.cross(*edge_v).length(),
+            Light::Sphere { radius, .. } => 1.0 / (4.0 * std::f64::consts::PI * radius * radius),
+        }
+    }
+
+    // solid angle density `sample_emission` picks `direction` with from a
+    // point with `normal`
+    pub fn pdf_emission_direction(&self, normal: Vector, direction: Vector) -> f64 {
+        match self {
+            Light::Point { .. } => 1.0 / (4.0 * std::f64::consts::PI),
+            Light::Directional { .. } => 0.0,
+            Light::Rect { .. } | Light::Sphere { .. } => {
+                normal.dot(direction).max(0.0) / std::f64::consts::PI
+            }
+        }
+    }
+
+    // emitting side's normal at `p` on the light, zero for point-like lights
+    pub fn normal_at(&self, p: Vector) -> Vector {
+        match self {
+            Light::Point { .. } | Light::Directional { .. } => Vector(0.0, 0.0, 0.0),
+            Light::Rect { edge_u, edge_v, .. } => edge_u.cross(*edge_v).to_unit_vector(),
+            Light::Sphere { center, .. } => (p - *center).to_unit_vector(),
+        }
+    }
+
+    // one photon leaving the light toward the sphere at `target` with
+    // `radius`: the ray, and the flux it carries if it were the only one shot.
+    // Directional light starts `far` away. Falloff is ignored, photons spread
+    // out physically. None when the light faces away
+    pub fn emit(&self, target: Vector, radius: f64, far: f64) -> Option<(Ray, Vector)> {
+        match self {
+            Light::Point {
+                position,
+                color,
+                intensity,
+                radius: size,
+                ..
+            } => {
+                let origin = *position + *size * random_unit_vector();
+                let (direction, solid_angle) = toward_sphere(origin, target, radius);
+                Some((
+                    Ray::new(origin, direction),
+                    (*intensity * solid_angle) * *color,
+                ))
+            }
+            Light::Directional {
+                color, intensity, ..
+            } => {
+                // `sample` jitters the direction over the light's disc
+                let toward = self.sample(target)?.direction;
+                let (t, b) = orthonormal_basis(toward);
+                let d = random_in_unit_disk();
+                let origin = target + radius * (d.x() * t + d.y() * b) + far * toward;
+
+                let area = std::f64::consts::PI * radius * radius;
+                Some((Ray::new(origin, -toward), (*intensity * area) * *color))
+            }
+            Light::Rect {
+                corner,
+                edge_u,
+                edge_v,
+                color,
+                radiance,
+            } => {
+                let origin = *corner
+                    + random_in_range(0.0, 1.0) * *edge_u
+                    + random_in_range(0.0, 1.0) * *edge_v;
+                let normal = edge_u.cross(*edge_v);
+                let area = normal.length();
+                Self::emit_from_surface(origin, normal / area, area, target, radius)
+                    .map(|(ray, scale)| (ray, (*radiance * scale) * *color))
+            }
+            Light::Sphere {
+                center,
+                radius: size,
+                color,
+                radiance,
+            } => {
+                let normal = random_unit_vector();
+                let area = 4.0 * std::f64::consts::PI * size * size;
+                Self::emit_from_surface(*center + *size * normal, normal, area, target, radius)
+                    .map(|(ray, scale)| (ray, (*radiance * scale) * *color))
+            }
+        }
+    }
+
+    // photon from a point on an emitting surface of `area`, and what its
+    // radiance is multiplied by to give the photon's flux
+    fn emit_from_surface(
+        origin: Vector,
+        normal: Vector,
+        area: f64,
+        target: Vector,
+        radius: f64,
+    ) -> Option<(Ray, f64)> {
+        let (direction, solid_angle) = toward_sphere(origin, target, radius);
+        let cosine = normal.dot(direction);
+        if cosine <= 0.0 {
+            return None;
+        }
+        Some((Ray::new(origin, direction), area * cosine * solid_angle))
+    }
+
+    // where a ray from `origin` along the unit vector `direction` meets the
+    // light: distance, radiance, and the density `sample` would have picked the
+    // direction with. None for point-like lights and misses
+    pub fn hit(&self, origin: Vector, direction: Vector) -> Option<(f64, Vector, f64)> {
+        match self {
+            Light::Point { .. } | Light::Directional { .. } => None,
+            Light::Rect {
+                corner,
+                edge_u,
+                edge_v,
+                color,
+                radiance,
+            } => {
+                let normal = edge_u.cross(*edge_v);
+                let area = normal.length();
+                let denom = direction.dot(normal);
+                // only the front emits
+                if area <= 0.0 || denom >= 0.0 {
+                    return None;
+                }
+
+                let distance = (*corner - origin).dot(normal) / denom;
+                if distance <= 0.0 {
+                    return None;
+                }
+
+                // parallelogram coordinates of the crossing point
+                let w = origin + distance * direction - *corner;
+                let nn = normal.dot(normal);
+                let a = normal.dot(w.cross(*edge_v)) / nn;
+                let b = normal.dot(edge_u.cross(w)) / nn;
+                if !(0.0..=1.0).contains(&a) || !(0.0..=1.0).contains(&b) {
+                    return None;
+                }
+
+                let cos_light = -denom / area;
+                let pdf = distance * distance / (cos_light * area);
+                Some((distance, *radiance * *color, pdf))
+            }
+            Light::Sphere {
+                center,
+                radius,
+                color,
+                radiance,
+            } => {
+                let oc = origin - *center;
+                let half_b = oc.dot(direction);
+                let c = oc.squared_length() - radius * radius;
+                let discriminant = half_b * half_b - c;
+                if c <= 0.0 || discriminant < 0.0 {
+                    return None;
+                }
+
+                let distance = -half_b - discriminant.sqrt();
+                if distance <= 0.0 {
+                    return None;
+                }
+
+                let cos_max = (1.0 - radius * radius / oc.squared_length())
+                    .max(0.0)
+                    .sqrt();
+                let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_max);
+                Some((distance, *radiance * *color, 1.0 / solid_angle))
+            }
+        }
+    }
+}
+
+// uniform unit vector from `from` into the sphere at `center` with `radius`,
+// and the solid angle it was picked from
+fn toward_sphere(from: Vector, center: Vector, radius: f64) -> (Vector, f64) {
+    let offset = center - from;
+    let d = offset.length();
+    if d <= radius {
+        return (random_unit_vector(), 4.0 * std::f64::consts::PI);
+    }
+
+    let cos_max = (1.0 - radius * radius / (d * d)).max(0.0).sqrt();
+    let cos_theta = 1.0 - random_in_range(0.0, 1.0) * (1.0 - cos_max);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * std::f64::consts::PI * random_in_range(0.0, 1.0);
+    let axis = offset / d;
+    let (t, b) = orthonormal_basis(axis);
+    let direction = sin_theta * phi.cos() * t + sin_theta * phi.sin() * b + cos_theta * axis;
+
+    (direction, 2.0 * std::f64::consts::PI * (1.0 - cos_max))
+}
@@ -1,81 +1,488 @@
-use camera::Camera;
-use materials::{lambertian::Lambertian, metal::Metal};
-use sphere::Sphere;
-use std::{env, sync::Arc};
-use vector::Vector;
-
-extern crate indicatif;
-extern crate lodepng;
-extern crate rand;
-
-mod camera;
-mod material;
-mod materials;
-mod ray;
-mod scene;
-mod sphere;
-mod utils;
-mod vector;
-
-static ASPECT_RATIO: f64 = 16.0 / 9.0;
-static VIEWPORT_WIDTH: i32 = 1600;
-static ANTIALIAS_SAMPLES: i32 = 300;
-static REFLECTION_DEPTH: i32 = 100;
-static BACKGROUND_COLOR: Vector = Vector(0.5, 0.7, 1.0);
+use ray_tracer::accelerator::AcceleratorKind;
+use ray_tracer::bdpt::Bidirectional;
+use ray_tracer::camera::{Camera, FisheyeCamera, FisheyeMapping, PanoramaCamera};
+use ray_tracer::color::{ColorConfig, ColorSpace, ToneMap};
+use ray_tracer::environment::Environment;
+use ray_tracer::gallery;
+use ray_tracer::integrator::{AmbientOcclusion, PathTracer, Whitted};
+use ray_tracer::materials::lambertian::Lambertian;
+use ray_tracer::metadata;
+use ray_tracer::partial::Partial;
+use ray_tracer::presets;
+use ray_tracer::sampler::{BlueNoiseSampler, RandomSampler, SobolSampler, StratifiedSampler};
+use ray_tracer::scene;
+use ray_tracer::texture::{Addressing, ImageTexture};
+use ray_tracer::vector::Vector;
+use std::sync::Arc;
+use std::{env, fs, path::Path};
+
+// preview quality for the gallery
+const GALLERY_WIDTH: i32 = 320;
+const GALLERY_SAMPLES: i32 = 32;
+
+// `--name=value` flags that change the rendered image, embedded in its PNG
+const RENDER_FLAGS: [&str; 16] = [
+    "scene",
+    "camera",
+    "input-space",
+    "output-space",
+    "tone-map",
+    "exposure",
+    "environment",
+    "ground",
+    "ambient",
+    "integrator",
+    "sampler",
+    "ao-radius",
+    "caustics",
+    "caustic-radius",
+    "irradiance-cache",
+    "irradiance-accuracy",
+];
+
+// `--name=value` flags for `RenderSettings`, which the scene embeds itself
+const SETTINGS_FLAGS: [&str; 6] = [
+    "samples",
+    "max-depth",
+    "clamp-indirect",
+    "noise-threshold",
+    "max-samples",
+    "passes",
+];
+
+// the color encoding flags, shared by renders and `merge`
+fn color_config(args: &[String]) -> ColorConfig {
+    // --input-space=srgb|rec709|linear is how 8-bit texture files are encoded
+    let mut color = ColorConfig::default();
+    if let Some(name) = args.iter().find_map(|a| a.strip_prefix("--input-space=")) {
+        match ColorSpace::from_name(name) {
+            Some(input) => color.input = input,
+            None => println!("Unknown input space \"{}\", reading sRGB.", name),
+        }
+    }
+    // --output-space=srgb|rec709|linear picks the transfer function of the image
+    if let Some(name) = args.iter().find_map(|a| a.strip_prefix("--output-space=")) {
+        match ColorSpace::from_name(name) {
+            Some(output) => color.output = output,
+            None => println!("Unknown color space \"{}\", writing sRGB output.", name),
+        }
+    }
+    // --tone-map=clip|reinhard|aces brings highlights into range after
+    // --exposure=<stops> scales the image
+    if let Some(name) = args.iter().find_map(|a| a.strip_prefix("--tone-map=")) {
+        match ToneMap::from_name(name) {
+            Some(tone_map) => color.tone_map = tone_map,
+            None => println!("Unknown tone map \"{}\", clipping highlights.", name),
+        }
+    }
+    if let Some(value) = args.iter().find_map(|a| a.strip_prefix("--exposure=")) {
+        match value.parse::<f64>() {
+            Ok(stops) => color.exposure = stops,
+            Err(_) => println!("Exposure \"{}\" is not a number of stops, using 0.", value),
+        }
+    }
+    color
+}
+
+// `merge <output.png> <part>...` averages partial renders made with --seed,
+// encoded by the same --output-space, --tone-map and --exposure flags
+fn merge(args: &[String]) {
+    let paths: Vec<&String> = args.iter().filter(|a| !a.starts_with("--")).collect();
+    if paths.len() < 2 {
+        println!("Usage: merge [--output-space=..] [--tone-map=..] [--exposure=..] <output.png> <part.partial>...");
+        return;
+    }
+
+    let mut parts = Vec::new();
+    for path in &paths[1..] {
+        match Partial::read(path) {
+            Ok(part) => parts.push(part),
+            Err(err) => {
+                println!("Error reading \"{}\": {}", path, err);
+                return;
+            }
+        }
+    }
+
+    match Partial::merge(&parts) {
+        Some(merged) => {
+            if merged.make_png(paths[0], &color_config(args)) {
+                println!("Merged {} samples per pixel.", merged.samples);
+            }
+        }
+        None => println!("Partial renders have different sizes."),
+    }
+}
+
+// `settings-from-image <render.png>` prints the settings embedded in a render,
+// saves them next to it as <render>.settings and rebuilds the command line
+fn settings_from_image(args: &[String]) {
+    let path = match args.first() {
+        Some(p) => p,
+        None => {
+            println!("Usage: settings-from-image <render.png>");
+            return;
+        }
+    };
+
+    let settings = match metadata::read(path) {
+        Ok(s) => s,
+        Err(err) => {
+            println!("Error reading \"{}\": {}", path, err);
+            return;
+        }
+    };
+    if settings.is_empty() {
+        println!("No settings embedded in \"{}\".", path);
+        return;
+    }
+
+    let mut saved = String::new();
+    let mut command = String::from("cargo run --release --");
+    for (key, value) in &settings {
+        println!("{}: {}", key, value);
+        saved.push_str(&format!("{}={}\n", key, value));
+
+        if key == "name" {
+            command.push_str(&format!(" {}", value));
+        } else if RENDER_FLAGS.contains(&key.as_str()) || SETTINGS_FLAGS.contains(&key.as_str()) {
+            command.push_str(&format!(" --{}={}", key, value));
+        }
+    }
+
+    let out = format!("{}.settings", path.trim_end_matches(".png"));
+    match fs::write(&out, saved) {
+        Ok(()) => println!("Settings written to {}.", out),
+        Err(err) => println!("Error writing \"{}\": {}", out, err),
+    }
+    println!("To render it again: {}", command);
+}
+
+// `gallery` renders every preset at preview quality into out/gallery/ with an
+// HTML page and a contact sheet
+fn gallery() {
+    let dir = Path::new("out/gallery");
+    if let Err(err) = fs::create_dir_all(dir) {
+        println!("Error creating \"{}\": {}", dir.display(), err);
+        return;
+    }
+
+    let mut previews = Vec::new();
+    for preset in presets::all() {
+        println!("Rendering {}...", preset.name);
+        let (cam, objects) = (preset.build)();
+        let file = format!("{}.png", preset.name);
+
+        let mut scene = scene::Scene::new(cam, objects, dir.join(&file).display().to_string());
+        scene.set_preview(GALLERY_WIDTH, GALLERY_SAMPLES);
+        scene.render();
+        previews.push((preset.name, file));
+    }
+
+    match gallery::write(dir, &previews) {
+        Ok(()) => println!("Gallery written to {}.", dir.join("index.html").display()),
+        Err(err) => println!("Error writing gallery: {}", err),
+    }
+}
 
 fn main() {
-    // camera
-    let lookfrom = Vector(16.0, 1.6, 3.0);
-    let lookat = Vector(0.0, 0.0, 0.0);
-    let vup = Vector(0.0, 1.0, 0.0);
-    let dist_to_focus = 15.0;
-    let aperture = 0.08;
-
-    let cam = Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        20.0,
-        ASPECT_RATIO,
-        aperture,
-        dist_to_focus,
-    );
-
-    let mut objects: Vec<Sphere> = Vec::new();
-
-    // basic materials
-    let ground_material = Arc::new(Lambertian::new(Vector(0.7, 0.72, 0.62)));
-
-    let red_lambertian: Arc<Lambertian> = Arc::new(Lambertian::new(Vector(0.6, 0.2, 0.2)));
-    let blue_lambertian: Arc<Lambertian> = Arc::new(Lambertian::new(Vector(0.2, 0.6, 0.2)));
-    let green_lambertian: Arc<Lambertian> = Arc::new(Lambertian::new(Vector(0.2, 0.2, 0.6)));
-
-    let metal: Arc<Metal> = Arc::new(Metal::new(Vector(0.6, 0.6, 0.65)));
-
-    // setup scene objects
-    // diffuse material spheres
-    objects.push(Sphere::new(&Vector(0.0, -0.7, 0.4), 0.3, red_lambertian));
-    objects.push(Sphere::new(&Vector(0.7, -0.7, 0.0), 0.3, blue_lambertian));
-    objects.push(Sphere::new(&Vector(-0.7, -0.7, 0.8), 0.3, green_lambertian));
-
-    // metal sphere
-    objects.push(Sphere::new(&Vector(-3.0, 0.0, 0.0), 1.0, metal));
-
-    // ground
-    objects.push(Sphere::new(
-        &Vector(0.0, -1001.0, 0.0),
-        1000.0,
-        ground_material,
-    ));
-
-    // get filename if present
-    let mut filename = "out/out.png".to_string();
-    if let Some(arg1) = env::args().nth(1) {
-        let f = format!("out/{}.png", arg1);
-        filename = f;
-    }
-
-    let scene: scene::Scene = scene::Scene::new(cam, objects, filename);
-
-    scene.render();
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|a| a.as_str()) {
+        Some("merge") => return merge(&args[1..]),
+        Some("gallery") => return gallery(),
+        Some("settings-from-image") => return settings_from_image(&args[1..]),
+        _ => {}
+    }
+
+    // --scene=<preset> picks one of the built-in scenes
+    let preset_name = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--scene="))
+        .unwrap_or("spheres");
+    let preset = match presets::find(preset_name) {
+        Some(p) => p,
+        None => {
+            println!("Unknown scene \"{}\".", preset_name);
+            return;
+        }
+    };
+    let (cam, objects) = (preset.build)();
+
+    // --camera=panorama|fisheye swaps the scene's camera for a 360 degree one, or
+    // a 180 degree fisheye looking the same way, in the same spot
+    let camera_kind = args.iter().find_map(|a| a.strip_prefix("--camera="));
+    let cam: Box<dyn Camera> = match camera_kind {
+        Some("panorama") => Box::new(PanoramaCamera::new(cam.origin())),
+        Some("fisheye") => {
+            let (_, up, back) = cam.basis();
+            Box::new(FisheyeCamera::new(
+                cam.origin(),
+                cam.origin() - back,
+                up,
+                180.0,
+                FisheyeMapping::Equisolid,
+            ))
+        }
+        Some(kind) => {
+            println!("Unknown camera \"{}\", using the scene's own.", kind);
+            cam
+        }
+        None => cam,
+    };
+
+    // get filename if present, flags start with --
+    let name = args
+        .iter()
+        .find(|a| !a.starts_with("--"))
+        .map_or("out", |a| a.as_str());
+    let filename = format!("out/{}.png", name);
+
+    // --accelerator=bvh|kdtree|grid picks the spatial index rays are traced against
+    let mut accelerator = AcceleratorKind::default();
+    if let Some(name) = args.iter().find_map(|a| a.strip_prefix("--accelerator=")) {
+        match AcceleratorKind::from_name(name) {
+            Some(kind) => accelerator = kind,
+            None => println!("Unknown accelerator \"{}\", using a BVH.", name),
+        }
+    }
+    // --bvh-cache=<path> keeps the built BVH in a file and reuses it while the
+    // scene's objects stay the same, for big meshes that are slow to build
+    if let Some(path) = args.iter().find_map(|a| a.strip_prefix("--bvh-cache=")) {
+        match accelerator {
+            AcceleratorKind::Bvh(options) => {
+                accelerator = AcceleratorKind::CachedBvh {
+                    options,
+                    path: path.to_string(),
+                }
+            }
+            _ => println!("Only a BVH can be cached, ignoring --bvh-cache."),
+        }
+    }
+
+    let mut scene = scene::Scene::with_accelerator(cam, objects, filename, accelerator);
+    match camera_kind {
+        Some("panorama") => scene.set_resolution(scene.width, scene.width / 2),
+        Some("fisheye") => scene.set_resolution(scene.height, scene.height),
+        _ => {}
+    }
+
+    let color = color_config(&args);
+    let input_space = color.input;
+    scene.set_color_config(color);
+
+    // --environment=<image.png|image.hdr> lights the scene with an equirectangular map
+    if let Some(path) = args.iter().find_map(|a| a.strip_prefix("--environment=")) {
+        let environment = if path.ends_with(".hdr") {
+            Environment::load_hdr(path, 1.0)
+        } else {
+            ImageTexture::load(path, input_space, Addressing::Wrap).map(|texture| {
+                Environment::Map {
+                    texture: Arc::new(texture),
+                    strength: 1.0,
+                }
+            })
+        };
+
+        match environment {
+            Ok(environment) => scene.set_environment(environment),
+            Err(err) => println!("Error loading environment \"{}\": {}", path, err),
+        }
+    }
+
+    // --ground=diffuse|shadow-catcher puts a plane under the scene
+    if let Some(kind) = args.iter().find_map(|a| a.strip_prefix("--ground=")) {
+        match kind {
+            "diffuse" => scene.set_ground(scene::Ground::Material(Arc::new(Lambertian::new(
+                Vector(0.5, 0.5, 0.5),
+            )))),
+            "shadow-catcher" => scene.set_ground(scene::Ground::ShadowCatcher),
+            _ => println!("Unknown ground \"{}\", rendering without one.", kind),
+        }
+    }
+
+    // --ambient=<gray>|environment adds an unshadowed fill light
+    if let Some(value) = args.iter().find_map(|a| a.strip_prefix("--ambient=")) {
+        match (value, value.parse::<f64>()) {
+            ("environment", _) => scene.set_ambient(scene::Ambient::Environment(1.0)),
+            (_, Ok(gray)) => scene.set_ambient(scene::Ambient::Color(Vector(gray, gray, gray))),
+            _ => println!("Unknown ambient \"{}\", rendering without it.", value),
+        }
+    }
+
+    // --samples=<n> and --max-depth=<n> trade quality for speed
+    if let Some(value) = args.iter().find_map(|a| a.strip_prefix("--samples=")) {
+        match value.parse::<i32>() {
+            Ok(samples) if samples > 0 => scene.settings.samples = samples,
+            _ => println!(
+                "Samples \"{}\" is not a positive number, using {}.",
+                value, scene.settings.samples
+            ),
+        }
+    }
+    if let Some(value) = args.iter().find_map(|a| a.strip_prefix("--max-depth=")) {
+        match value.parse::<i32>() {
+            Ok(depth) if depth >= 0 => scene.settings.max_depth = depth,
+            _ => println!(
+                "Max depth \"{}\" is not a number, using {}.",
+                value, scene.settings.max_depth
+            ),
+        }
+    }
+
+    // --clamp-indirect=<radiance> trades bright bounced light for fewer fireflies
+    if let Some(value) = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--clamp-indirect="))
+    {
+        match value.parse::<f64>() {
+            Ok(limit) if limit >= 0.0 => scene.settings.clamp_indirect = limit,
+            _ => println!(
+                "Indirect clamp \"{}\" is not a number, leaving light unclamped.",
+                value
+            ),
+        }
+    }
+
+    // --noise-threshold=<error> samples adaptively, up to --max-samples=<n>
+    if let Some(value) = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--noise-threshold="))
+    {
+        match value.parse::<f64>() {
+            Ok(threshold) if threshold >= 0.0 => scene.settings.noise_threshold = threshold,
+            _ => println!(
+                "Noise threshold \"{}\" is not a number, sampling uniformly.",
+                value
+            ),
+        }
+    }
+    if let Some(value) = args.iter().find_map(|a| a.strip_prefix("--max-samples=")) {
+        match value.parse::<i32>() {
+            Ok(samples) if samples > 0 => scene.settings.max_samples = samples,
+            _ => println!(
+                "Max samples \"{}\" is not a positive number, using {}.",
+                value, scene.settings.max_samples
+            ),
+        }
+    }
+
+    // --integrator=path|whitted|ao|bdpt picks how pixels are shaded, --ao-radius=<r>
+    // how far ambient occlusion looks
+    if let Some(kind) = args.iter().find_map(|a| a.strip_prefix("--integrator=")) {
+        let ao_radius = args
+            .iter()
+            .find_map(|a| a.strip_prefix("--ao-radius="))
+            .and_then(|r| r.parse::<f64>().ok())
+            .unwrap_or(1.0);
+        match kind {
+            "path" => scene.set_integrator(Box::new(PathTracer)),
+            "whitted" => scene.set_integrator(Box::new(Whitted::new(1.5))),
+            "ao" => scene.set_integrator(Box::new(AmbientOcclusion::new(ao_radius))),
+            "bdpt" => scene.set_integrator(Box::new(Bidirectional)),
+            _ => println!("Unknown integrator \"{}\", path tracing.", kind),
+        }
+    }
+
+    // --caustics=<photons> adds the caustics of glass and mirrors, gathered
+    // within --caustic-radius=<r>
+    if let Some(value) = args.iter().find_map(|a| a.strip_prefix("--caustics=")) {
+        let radius = args
+            .iter()
+            .find_map(|a| a.strip_prefix("--caustic-radius="))
+            .and_then(|r| r.parse::<f64>().ok())
+            .unwrap_or(0.03);
+        match value.parse::<usize>() {
+            Ok(photons) => scene.build_caustics(photons, radius),
+            Err(_) => println!(
+                "Caustics \"{}\" is not a photon count, skipping them.",
+                value
+            ),
+        }
+    }
+
+    // --irradiance-cache=<rays> interpolates bounced light on diffuse surfaces
+    // from records gathered with that many rays, placed closer together at a
+    // smaller --irradiance-accuracy=<a>
+    if let Some(value) = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--irradiance-cache="))
+    {
+        let accuracy = args
+            .iter()
+            .find_map(|a| a.strip_prefix("--irradiance-accuracy="))
+            .and_then(|a| a.parse::<f64>().ok())
+            .unwrap_or(0.25);
+        match value.parse::<usize>() {
+            Ok(rays) => scene.build_irradiance_cache(rays, accuracy),
+            Err(_) => println!(
+                "Irradiance cache \"{}\" is not a ray count, skipping it.",
+                value
+            ),
+        }
+    }
+
+    let seed = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--seed="))
+        .and_then(|s| s.parse::<usize>().ok());
+
+    // --sampler=random|stratified|sobol|blue-noise picks where sample positions come from
+    if let Some(kind) = args.iter().find_map(|a| a.strip_prefix("--sampler=")) {
+        match kind {
+            "random" => scene.set_sampler(Box::new(RandomSampler)),
+            "stratified" => scene.set_sampler(Box::new(StratifiedSampler)),
+            "sobol" => scene.set_sampler(Box::new(SobolSampler::new())),
+            "blue-noise" => {
+                scene.set_sampler(Box::new(BlueNoiseSampler::new(seed.unwrap_or(0) as u32)))
+            }
+            _ => println!("Unknown sampler \"{}\", using stratified.", kind),
+        }
+    }
+
+    // --crop=x0,y0,x1,y1 re-renders only that pixel rectangle over the last image
+    if let Some(value) = args.iter().find_map(|a| a.strip_prefix("--crop=")) {
+        let bounds: Vec<i32> = value.split(',').filter_map(|v| v.parse().ok()).collect();
+        match bounds[..] {
+            [x0, y0, x1, y1] => scene.set_crop(x0, y0, x1, y1),
+            _ => println!(
+                "Crop \"{}\" is not x0,y0,x1,y1, rendering everything.",
+                value
+            ),
+        }
+    }
+
+    // remember how the image was made, see `settings-from-image`
+    scene.add_metadata("version", env!("CARGO_PKG_VERSION"));
+    scene.add_metadata("name", name);
+    for flag in RENDER_FLAGS.iter() {
+        let prefix = format!("--{}=", flag);
+        if let Some(value) = args.iter().find_map(|a| a.strip_prefix(prefix.as_str())) {
+            scene.add_metadata(flag, value);
+        }
+    }
+
+    if let Some(seed) = seed {
+        // one share of a render split across processes, combine them with `merge`
+        let path = format!("out/{}.{}.partial", name, seed);
+        match scene.render_partial(&path, seed) {
+            Ok(()) => println!("Partial render written to {}.", path),
+            Err(err) => println!("Error writing partial render \"{}\": {}", path, err),
+        }
+    } else if args.iter().any(|a| a == "--dataset") {
+        let dir = format!("out/{}", name);
+        match scene.render_dataset(&dir, 0) {
+            Ok(()) => println!("Dataset frame written to {}.", dir),
+            Err(err) => println!("Error writing dataset \"{}\": {}", dir, err),
+        }
+    } else if args.iter().any(|a| a == "--heatmap") {
+        scene.render_traversal_heatmap();
+    } else if let Some(value) = args.iter().find_map(|a| a.strip_prefix("--passes=")) {
+        // --passes=<n> refines the image pass after pass, 0 until stopped
+        match value.parse::<u32>() {
+            Ok(passes) => scene.render_progressive(passes),
+            Err(_) => println!("Passes \"{}\" is not a number.", value),
+        }
+    } else {
+        scene.render();
+    }
 }
@@ -1,7 +1,56 @@
+use crate::hittable::Hit;
 use crate::ray::Ray;
-use crate::sphere::Hit;
 use crate::vector::Vector;
 
-pub trait Scatter {
+pub trait Scatter: Send + Sync {
     fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)>;
+
+    // `scatter`, also telling whether the ray left by a mirror-like lobe, one
+    // that `eval` and `pdf` leave out. Materials blending such lobes with
+    // others, like `materials::mix::Mix`, have to say which one they picked
+    fn scatter_lobe(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector, bool)> {
+        let (scattered, attenuation) = self.scatter(ray, hit)?;
+        let specular = self
+            .pdf(ray, hit, scattered.direction.to_unit_vector())
+            .is_none();
+        Some((scattered, attenuation, specular))
+    }
+
+    // BRDF times cosine for light arriving from the unit vector `direction`,
+    // used to shade with `Light`s; None for materials with only mirror-like
+    // lobes, which can only pick up light by scattering into it
+    fn eval(&self, _ray: &Ray, _hit: &Hit, _direction: Vector) -> Option<Vector> {
+        None
+    }
+
+    // solid angle density with which `scatter` picks the unit vector
+    // `direction`, to weigh sampled environment light against scattered rays.
+    // Implemented by exactly the materials that implement `eval`
+    fn pdf(&self, _ray: &Ray, _hit: &Hit, _direction: Vector) -> Option<f64> {
+        None
+    }
+
+    // fraction of light a shadow ray carries straight through the surface, so
+    // glass casts tinted shadows instead of black ones; None for opaque materials
+    fn transmittance(&self, _ray: &Ray, _hit: &Hit) -> Option<Vector> {
+        None
+    }
+
+    // reflectance of materials that scatter light the same into every
+    // direction, whose bounced light the irradiance cache can stand in for;
+    // None for everything else
+    fn diffuse(&self, _hit: &Hit) -> Option<Vector> {
+        None
+    }
+
+    // fraction of indirect and ambient light that reaches the surface by a
+    // baked occlusion map, see `materials::occlusion_map::OcclusionMap`
+    fn occlusion(&self, _hit: &Hit) -> f64 {
+        1.0
+    }
+
+    // radiance the surface emits toward the ray origin, black for non-lights
+    fn emitted(&self, _ray: &Ray, _hit: &Hit) -> Vector {
+        Vector(0.0, 0.0, 0.0)
+    }
 }
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::hittable::Hit;
+use crate::material::Scatter;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// materials defined once by name and shared by any number of objects. Objects
+// hold a handle to the name rather than the material, so redefining a name
+// changes every object using it, even after the scene is built
+#[derive(Default)]
+pub struct MaterialLibrary {
+    slots: HashMap<String, Arc<Slot>>,
+}
+
+// the material currently defined under one name
+struct Slot {
+    material: RwLock<Arc<dyn Scatter>>,
+}
+
+impl MaterialLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // adds `name`, or replaces what it refers to everywhere if it exists
+    pub fn define(&mut self, name: &str, material: Arc<dyn Scatter>) {
+        match self.slots.get(name) {
+            Some(slot) => *slot.material.write().unwrap() = material,
+            None => {
+                let slot = Slot {
+                    material: RwLock::new(material),
+                };
+                self.slots.insert(name.to_string(), Arc::new(slot));
+            }
+        }
+    }
+
+    // handle to the material named `name`, to pass to shape constructors
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Scatter>> {
+        self.slots
+            .get(name)
+            .map(|slot| slot.clone() as Arc<dyn Scatter>)
+    }
+
+    // defined names in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.slots.keys().map(|k| k.as_str()).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+impl Scatter for Slot {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        self.material.read().unwrap().scatter(ray, hit)
+    }
+
+    fn scatter_lobe(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector, bool)> {
+        self.material.read().unwrap().scatter_lobe(ray, hit)
+    }
+
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        self.material.read().unwrap().eval(ray, hit, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        self.material.read().unwrap().pdf(ray, hit, direction)
+    }
+
+    fn transmittance(&self, ray: &Ray, hit: &Hit) -> Option<Vector> {
+        self.material.read().unwrap().transmittance(ray, hit)
+    }
+
+    fn diffuse(&self, hit: &Hit) -> Option<Vector> {
+        self.material.read().unwrap().diffuse(hit)
+    }
+
+    fn occlusion(&self, hit: &Hit) -> f64 {
+        self.material.read().unwrap().occlusion(hit)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Vector {
+        self.material.read().unwrap().emitted(ray, hit)
+    }
+}
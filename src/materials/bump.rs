@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::Hit,
+    material::Scatter,
+    ray::Ray,
+    texture::{Texture, TexturePoint},
+    vector::Vector,
+};
+
+// step in u and v for the finite differences
+const DELTA: f64 = 1.0 / 1024.0;
+
+// perturbs the shading normal of another material with the gradient of a
+// grayscale height texture, a cheap stand-in for real surface detail
+pub struct Bump {
+    material: Arc<dyn Scatter>,
+    height: Arc<dyn Texture>,
+    // height at texture value 1, in scene units per unit of u and v
+    strength: f64,
+}
+
+impl Bump {
+    pub fn new(material: Arc<dyn Scatter>, height: Arc<dyn Texture>, strength: f64) -> Self {
+        Self {
+            material,
+            height,
+            strength,
+        }
+    }
+
+    // copy of the hit with the bumped normal
+    fn bumped(&self, hit: &Hit) -> Hit {
+        let h = |du: f64, dv: f64| {
+            let offset = du * hit.dpdu + dv * hit.dpdv;
+            // the world space step stands in for the object space one, which
+            // only differs under scaled instances
+            let at = TexturePoint {
+                u: hit.u + du,
+                v: hit.v + dv,
+                p: hit.p + offset,
+                local: hit.local + offset,
+                normal: hit.normal,
+            };
+            self.strength * self.height.sample(&at).x()
+        };
+        let h0 = h(0.0, 0.0);
+        let dh_du = (h(DELTA, 0.0) - h0) / DELTA;
+        let dh_dv = (h(0.0, DELTA) - h0) / DELTA;
+
+        // displace the surface along the normal and rebuild it from the new tangents
+        let dpdu = hit.dpdu + dh_du * hit.normal;
+        let dpdv = hit.dpdv + dh_dv * hit.normal;
+        let mut normal = dpdu.cross(dpdv);
+
+        if normal.near_zero() {
+            normal = hit.normal;
+        } else if normal.dot(hit.normal) < 0.0 {
+            normal = -normal;
+        }
+
+        Hit {
+            t: hit.t,
+            p: hit.p,
+            local: hit.local,
+            normal: normal.to_unit_vector(),
+            front_face: hit.front_face,
+            material: hit.material.clone(),
+            u: hit.u,
+            v: hit.v,
+            dpdu,
+            dpdv,
+            object: hit.object,
+        }
+    }
+}
+
+impl Scatter for Bump {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        self.material.scatter(ray, &self.bumped(hit))
+    }
+
+    fn scatter_lobe(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector, bool)> {
+        self.material.scatter_lobe(ray, &self.bumped(hit))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        self.material.eval(ray, &self.bumped(hit), direction)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        self.material.pdf(ray, &self.bumped(hit), direction)
+    }
+
+    fn transmittance(&self, ray: &Ray, hit: &Hit) -> Option<Vector> {
+        self.material.transmittance(ray, &self.bumped(hit))
+    }
+
+    fn diffuse(&self, hit: &Hit) -> Option<Vector> {
+        self.material.diffuse(&self.bumped(hit))
+    }
+
+    fn occlusion(&self, hit: &Hit) -> f64 {
+        self.material.occlusion(hit)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Vector {
+        self.material.emitted(ray, hit)
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::Hit,
+    material::Scatter,
+    materials::{
+        cook_torrance::{ggx_distribution, sample_ggx_half_vector, smith_g1},
+        dielectric::Dielectric,
+    },
+    ray::Ray,
+    utils::random_in_range,
+    vector::Vector,
+};
+
+// clear varnish layer over another material, as on car paint or lacquered wood:
+// light either reflects off the coat (Fresnel-weighted, glossy) or passes
+// through to the base material
+pub struct Coat {
+    base: Arc<dyn Scatter>,
+    // index of refraction of the coat, 1.5 for most lacquers
+    ir: f64,
+    // 0 is a mirror-smooth coat
+    roughness: f64,
+}
+
+impl Coat {
+    pub fn new(base: Arc<dyn Scatter>, index_of_refraction: f64, roughness: f64) -> Self {
+        Self {
+            base,
+            ir: index_of_refraction,
+            roughness,
+        }
+    }
+
+    fn reflectance(&self, cosine: f64) -> f64 {
+        Dielectric::reflectance(cosine, 1.0 / self.ir)
+    }
+
+    // GGX reflection off the coat, weighted by its Fresnel term over the
+    // probability `p_coat` it was picked with
+    fn sample_coat(&self, n: Vector, v: Vector, p_coat: f64) -> Option<(Vector, Vector)> {
+        let alpha = self.roughness.clamp(0.01, 1.0).powi(2);
+        let h = sample_ggx_half_vector(n, alpha);
+        let v_dot_h = v.dot(h);
+        let l = 2.0 * v_dot_h * h - v;
+
+        let n_dot_l = n.dot(l);
+        let n_dot_v = n.dot(v).max(1.0e-4);
+        if n_dot_l <= 0.0 || v_dot_h <= 0.0 {
+            return None;
+        }
+
+        let g = smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha);
+        let scale = g * v_dot_h / (n_dot_v * n.dot(h).max(1.0e-4));
+        let weight = scale * self.reflectance(v_dot_h) / p_coat;
+        Some((l, Vector(weight, weight, weight)))
+    }
+}
+
+impl Scatter for Coat {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        self.scatter_lobe(ray, hit)
+            .map(|(scattered, attenuation, _)| (scattered, attenuation))
+    }
+
+    fn scatter_lobe(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector, bool)> {
+        // rays leaving the inside of a closed object never meet the coat
+        if !hit.front_face {
+            return self.base.scatter_lobe(ray, hit);
+        }
+
+        let n = hit.normal;
+        let v = -ray.direction.to_unit_vector();
+        let p_coat = self.reflectance(n.dot(v).clamp(0.0, 1.0));
+
+        // light passing the coat reaches the base with probability 1 - p_coat,
+        // which is also its transmittance, so the base weight stays as is
+        if random_in_range(0.0, 1.0) < p_coat {
+            let (l, weight) = self.sample_coat(n, v, p_coat)?;
+            // glossy, unless a mirror-like base leaves the coat without a `pdf`
+            let specular = self.pdf(ray, hit, l).is_none();
+            Some((Ray::new(hit.p, l), weight, specular))
+        } else {
+            self.base.scatter_lobe(ray, hit)
+        }
+    }
+
+    // the coat's glossy reflection plus what the base adds under it, only for
+    // bases that have an `eval` themselves
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        let base = self.base.eval(ray, hit, direction)?;
+        if !hit.front_face {
+            return Some(base);
+        }
+
+        let n = hit.normal;
+        let v = -ray.direction.to_unit_vector();
+        let n_dot_v = n.dot(v).max(1.0e-4);
+        let n_dot_l = n.dot(direction);
+        if n_dot_l <= 0.0 {
+            return Some(Vector(0.0, 0.0, 0.0));
+        }
+
+        let alpha = self.roughness.clamp(0.01, 1.0).powi(2);
+        let h = (v + direction).to_unit_vector();
+        let g = smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha);
+        let coat = ggx_distribution(n.dot(h).max(0.0), alpha) * g / (4.0 * n_dot_v)
+            * self.reflectance(v.dot(h).max(0.0));
+
+        let transmitted = 1.0 - self.reflectance(n_dot_v.min(1.0));
+        Some(Vector(coat, coat, coat) + transmitted * base)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        let base = self.base.pdf(ray, hit, direction)?;
+        if !hit.front_face {
+            return Some(base);
+        }
+
+        let n = hit.normal;
+        let v = -ray.direction.to_unit_vector();
+        let p_coat = self.reflectance(n.dot(v).clamp(0.0, 1.0));
+        let alpha = self.roughness.clamp(0.01, 1.0).powi(2);
+        let h = (v + direction).to_unit_vector();
+        let n_dot_h = n.dot(h).max(0.0);
+        let coat = ggx_distribution(n_dot_h, alpha) * n_dot_h / (4.0 * v.dot(h).max(1.0e-4));
+        Some(p_coat * coat + (1.0 - p_coat) * base)
+    }
+
+    // the glossy coat only covers the front
+    fn diffuse(&self, hit: &Hit) -> Option<Vector> {
+        if hit.front_face {
+            None
+        } else {
+            self.base.diffuse(hit)
+        }
+    }
+
+    fn occlusion(&self, hit: &Hit) -> f64 {
+        self.base.occlusion(hit)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Vector {
+        self.base.emitted(ray, hit)
+    }
+}
@@ -0,0 +1,191 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    hittable::Hit,
+    material::Scatter,
+    ray::Ray,
+    texture::{Channel, SolidColor, Texture},
+    utils::{orthonormal_basis, random_in_range, random_unit_vector},
+    vector::Vector,
+};
+
+// physically based specular: GGX normal distribution, Smith shadowing and Schlick
+// Fresnel over a Lambertian base, parameterized like common PBR assets
+pub struct CookTorrance {
+    base_color: Arc<dyn Texture>,
+    // perceptual roughness, squared to get the GGX alpha
+    roughness: Arc<dyn Texture>,
+    // 0 is a dielectric with 4% specular, 1 is a metal tinted by the base color
+    metallic: Arc<dyn Texture>,
+    roughness_channel: Channel,
+    metallic_channel: Channel,
+}
+
+impl CookTorrance {
+    pub fn new(base_color: Vector, roughness: f64, metallic: f64) -> Self {
+        Self::textured(
+            Arc::new(SolidColor::new(base_color)),
+            Arc::new(SolidColor::scalar(roughness)),
+            Arc::new(SolidColor::scalar(metallic)),
+        )
+    }
+
+    pub fn textured(
+        base_color: Arc<dyn Texture>,
+        roughness: Arc<dyn Texture>,
+        metallic: Arc<dyn Texture>,
+    ) -> Self {
+        Self {
+            base_color,
+            roughness,
+            metallic,
+            roughness_channel: Channel::Red,
+            metallic_channel: Channel::Red,
+        }
+    }
+
+    // where the roughness and metallic maps keep their values, red by default;
+    // glTF packs roughness in green and metallic in blue
+    pub fn set_channels(&mut self, roughness: Channel, metallic: Channel) {
+        self.roughness_channel = roughness;
+        self.metallic_channel = metallic;
+    }
+
+    // base color, metallic and GGX alpha at a hit
+    fn params(&self, hit: &Hit) -> (Vector, f64, f64) {
+        let base_color = self.base_color.value_at(hit);
+        let metallic = self
+            .metallic_channel
+            .pick(self.metallic.value_at(hit))
+            .clamp(0.0, 1.0);
+        // perfectly smooth GGX is a delta, keep a tiny lobe
+        let roughness = self
+            .roughness_channel
+            .pick(self.roughness.value_at(hit))
+            .clamp(0.02, 1.0);
+        (base_color, metallic, roughness * roughness)
+    }
+
+    // probability of sampling the specular lobe rather than the diffuse one,
+    // roughly in proportion to how much energy it carries
+    fn specular_probability(fresnel: Vector, metallic: f64) -> f64 {
+        let spec_weight = (fresnel.x() + fresnel.y() + fresnel.z()) / 3.0;
+        (spec_weight + metallic * (1.0 - spec_weight)).clamp(0.1, 1.0)
+    }
+}
+
+pub fn fresnel_schlick(f0: Vector, cosine: f64) -> Vector {
+    let w = (1.0 - cosine).clamp(0.0, 1.0).powi(5);
+    f0 + w * (Vector(1.0, 1.0, 1.0) - f0)
+}
+
+// GGX (Trowbridge-Reitz) density of microfacets facing along the half vector
+pub fn ggx_distribution(n_dot_h: f64, alpha: f64) -> f64 {
+    let a2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * d * d)
+}
+
+// Smith masking for one direction with the GGX distribution
+pub fn smith_g1(n_dot_x: f64, alpha: f64) -> f64 {
+    let a2 = alpha * alpha;
+    2.0 * n_dot_x / (n_dot_x + (a2 + (1.0 - a2) * n_dot_x * n_dot_x).sqrt())
+}
+
+// microfacet normal drawn proportionally to D(h) * (n . h)
+pub fn sample_ggx_half_vector(normal: Vector, alpha: f64) -> Vector {
+    let u1 = random_in_range(0.0, 1.0);
+    let u2 = random_in_range(0.0, 1.0);
+
+    let phi = 2.0 * PI * u1;
+    let cos_theta = ((1.0 - u2) / (1.0 + (alpha * alpha - 1.0) * u2)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+
+    let (t, b) = orthonormal_basis(normal);
+    (sin_theta * phi.cos() * t + sin_theta * phi.sin() * b + cos_theta * normal).to_unit_vector()
+}
+
+impl Scatter for CookTorrance {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        let n = hit.normal;
+        let v = -ray.direction.to_unit_vector();
+        let n_dot_v = n.dot(v).max(1.0e-4);
+        let (base_color, metallic, alpha) = self.params(hit);
+
+        let f0 = (1.0 - metallic) * Vector(0.04, 0.04, 0.04) + metallic * base_color;
+        let fresnel = fresnel_schlick(f0, n_dot_v);
+
+        let p_spec = Self::specular_probability(fresnel, metallic);
+
+        if random_in_range(0.0, 1.0) < p_spec {
+            let h = sample_ggx_half_vector(n, alpha);
+            let v_dot_h = v.dot(h);
+            let l = 2.0 * v_dot_h * h - v;
+            let n_dot_l = n.dot(l);
+
+            if n_dot_l <= 0.0 || v_dot_h <= 0.0 {
+                return None;
+            }
+
+            // f * cos / pdf with pdf = D * (n.h) / (4 v.h) reduces to F G (v.h) / ((n.v) (n.h))
+            let g = smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha);
+            let weight = g * v_dot_h / (n_dot_v * n.dot(h).max(1.0e-4) * p_spec);
+
+            Some((Ray::new(hit.p, l), weight * fresnel_schlick(f0, v_dot_h)))
+        } else {
+            let mut direction = n + random_unit_vector();
+            if direction.near_zero() {
+                direction = n;
+            }
+
+            // light that isn't reflected at the surface reaches the diffuse base
+            let diffuse = (1.0 - metallic) * base_color;
+            let transmitted = Vector(1.0, 1.0, 1.0) - fresnel;
+
+            Some((
+                Ray::new(hit.p, direction),
+                (1.0 / (1.0 - p_spec)) * (transmitted * diffuse),
+            ))
+        }
+    }
+
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        let n = hit.normal;
+        let v = -ray.direction.to_unit_vector();
+        let n_dot_l = n.dot(direction);
+        let n_dot_v = n.dot(v).max(1.0e-4);
+        if n_dot_l <= 0.0 {
+            return Some(Vector(0.0, 0.0, 0.0));
+        }
+
+        let (base_color, metallic, alpha) = self.params(hit);
+        let f0 = (1.0 - metallic) * Vector(0.04, 0.04, 0.04) + metallic * base_color;
+        let h = (v + direction).to_unit_vector();
+        let fresnel = fresnel_schlick(f0, v.dot(h).max(0.0));
+
+        let d = ggx_distribution(n.dot(h).max(0.0), alpha);
+        let g = smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha);
+        let specular = (d * g / (4.0 * n_dot_v)) * fresnel;
+        // the same share of light reaches the base as in `scatter`
+        let transmitted = Vector(1.0, 1.0, 1.0) - fresnel_schlick(f0, n_dot_v);
+        let diffuse = (n_dot_l / PI) * (transmitted * ((1.0 - metallic) * base_color));
+
+        Some(specular + diffuse)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        let n = hit.normal;
+        let v = -ray.direction.to_unit_vector();
+        let (base_color, metallic, alpha) = self.params(hit);
+        let f0 = (1.0 - metallic) * Vector(0.04, 0.04, 0.04) + metallic * base_color;
+        let p_spec =
+            Self::specular_probability(fresnel_schlick(f0, n.dot(v).max(1.0e-4)), metallic);
+
+        let h = (v + direction).to_unit_vector();
+        let n_dot_h = n.dot(h).max(0.0);
+        let specular = ggx_distribution(n_dot_h, alpha) * n_dot_h / (4.0 * v.dot(h).max(1.0e-4));
+        let diffuse = n.dot(direction).max(0.0) / PI;
+        Some(p_spec * specular + (1.0 - p_spec) * diffuse)
+    }
+}
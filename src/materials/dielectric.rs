@@ -0,0 +1,71 @@
+use crate::{hittable::Hit, material::Scatter, ray::Ray, utils::random_in_range, vector::Vector};
+
+pub struct Dielectric {
+    // index of refraction, e.g. 1.5 for glass, 1.33 for water
+    ir: f64,
+    // color kept by light each time it refracts through the surface
+    tint: Vector,
+}
+
+impl Dielectric {
+    pub fn new(index_of_refraction: f64) -> Self {
+        Self::tinted(index_of_refraction, Vector(1.0, 1.0, 1.0))
+    }
+
+    // colored glass, e.g. a wine bottle
+    pub fn tinted(index_of_refraction: f64, tint: Vector) -> Self {
+        Self {
+            ir: index_of_refraction,
+            tint,
+        }
+    }
+
+    fn refraction_ratio(&self, hit: &Hit) -> f64 {
+        if hit.front_face {
+            1.0 / self.ir
+        } else {
+            self.ir
+        }
+    }
+
+    // Schlick's approximation of the Fresnel reflectance
+    pub fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
+        let r0 = ((1.0 - ref_idx) / (1.0 + ref_idx)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+    }
+}
+
+impl Scatter for Dielectric {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        let refraction_ratio = self.refraction_ratio(hit);
+
+        let unit_direction = ray.direction.to_unit_vector();
+        let cos_theta = (-unit_direction).dot(hit.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+        // total internal reflection, or a Fresnel-weighted coin flip
+        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+        if cannot_refract
+            || Self::reflectance(cos_theta, refraction_ratio) > random_in_range(0.0, 1.0)
+        {
+            let direction = unit_direction.reflect(hit.normal);
+            Some((Ray::new(hit.p, direction), Vector(1.0, 1.0, 1.0)))
+        } else {
+            let direction = unit_direction.refract(hit.normal, refraction_ratio);
+            Some((Ray::new(hit.p, direction), self.tint))
+        }
+    }
+
+    // what isn't reflected passes straight on; shadow rays don't bend, so the
+    // light focused by the glass (caustics) is left to scattered rays
+    fn transmittance(&self, ray: &Ray, hit: &Hit) -> Option<Vector> {
+        let refraction_ratio = self.refraction_ratio(hit);
+        let cos_theta = (-ray.direction.to_unit_vector()).dot(hit.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        if refraction_ratio * sin_theta > 1.0 {
+            return Some(Vector(0.0, 0.0, 0.0));
+        }
+
+        Some((1.0 - Self::reflectance(cos_theta, refraction_ratio)) * self.tint)
+    }
+}
@@ -0,0 +1,56 @@
+use std::f64::consts::PI;
+
+use crate::{hittable::Hit, material::Scatter, ray::Ray, vector::Vector};
+
+// luminous efficacy of 555nm light, the peak of photopic vision
+const LUMENS_PER_WATT: f64 = 683.0;
+
+// what the strength passed to `Emissive::with_power` means
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightUnit {
+    // scene-relative, the same as `Emissive::new`
+    Arbitrary,
+    // total radiant flux, as in renderers that quote light power in W
+    Watts,
+    // total luminous flux, as on fixture spec sheets
+    Lumens,
+}
+
+// glowing surface: turns any object into a light source, absorbs incoming light
+pub struct Emissive {
+    // emitted radiance, channels may exceed 1 for bright lights
+    radiance: Vector,
+}
+
+impl Emissive {
+    pub fn new(color: Vector, strength: f64) -> Self {
+        Self {
+            radiance: strength * color,
+        }
+    }
+
+    // light with a physical output spread evenly over a surface of `area`
+    // square scene units (meters), e.g. 4 pi r^2 for a sphere. `color` only
+    // sets the tint, it is normalized to luminance 1
+    pub fn with_power(color: Vector, power: f64, unit: LightUnit, area: f64) -> Self {
+        let flux = match unit {
+            LightUnit::Arbitrary => return Self::new(color, power),
+            LightUnit::Watts => power,
+            LightUnit::Lumens => power / LUMENS_PER_WATT,
+        };
+
+        // a diffuse emitter's radiance is its exitance over pi
+        let tint = (1.0 / color.luminance().max(1.0e-9)) * color;
+        Self::new(tint, flux / (PI * area.max(1.0e-9)))
+    }
+}
+
+impl Scatter for Emissive {
+    fn scatter(&self, _: &Ray, _: &Hit) -> Option<(Ray, Vector)> {
+        None
+    }
+
+    fn emitted(&self, _: &Ray, _: &Hit) -> Vector {
+        self.radiance
+    }
+}
@@ -1,30 +1,55 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
 use crate::{
-    material::Scatter, ray::Ray, sphere::Hit, utils::random_vector_in_unit_sphere, vector::Vector,
+    hittable::Hit,
+    material::Scatter,
+    ray::Ray,
+    texture::{SolidColor, Texture},
+    utils::random_unit_vector,
+    vector::Vector,
 };
 
 pub struct Lambertian {
-    albedo: Vector,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo_color: Vector) -> Self {
-        Self {
-            albedo: albedo_color,
-        }
+        Self::textured(Arc::new(SolidColor::new(albedo_color)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
     }
 }
 
 impl Scatter for Lambertian {
+    // normal plus a uniform point on the unit sphere is distributed proportionally
+    // to cos(theta) over the hemisphere, which is exactly the Lambertian lobe
     fn scatter(&self, _: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
-        let mut target = hit.p + hit.normal + random_vector_in_unit_sphere();
+        let mut direction = hit.normal + random_unit_vector();
 
         // guard against direction being near 0 after random vector
-        if target.near_zero() {
-            target = hit.normal;
+        if direction.near_zero() {
+            direction = hit.normal;
         }
 
-        let scattered = Ray::new(hit.p, target - hit.p);
+        let scattered = Ray::new(hit.p, direction);
+
+        Some((scattered, self.albedo.value_at(hit)))
+    }
+
+    fn eval(&self, _: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        let cosine = hit.normal.dot(direction).max(0.0);
+        Some((cosine / PI) * self.albedo.value_at(hit))
+    }
+
+    fn pdf(&self, _: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        Some(hit.normal.dot(direction).max(0.0) / PI)
+    }
 
-        Some((scattered, self.albedo))
+    fn diffuse(&self, hit: &Hit) -> Option<Vector> {
+        Some(self.albedo.value_at(hit))
     }
 }
@@ -1,24 +1,42 @@
-use crate::{material::Scatter, ray::Ray, sphere::Hit, vector::Vector};
+use std::sync::Arc;
+
+use crate::{
+    hittable::Hit,
+    material::Scatter,
+    ray::Ray,
+    texture::{SolidColor, Texture},
+    utils::random_vector_in_unit_sphere,
+    vector::Vector,
+};
 
 pub struct Metal {
-    albedo: Vector,
+    albedo: Arc<dyn Texture>,
+    // radius of the sphere the mirror direction is jittered within, 0 is a perfect mirror
+    fuzz: Arc<dyn Texture>,
 }
 
 impl Metal {
-    pub fn new(albedo_color: Vector) -> Self {
-        Self {
-            albedo: albedo_color,
-        }
+    pub fn new(albedo_color: Vector, fuzz: f64) -> Self {
+        Self::textured(
+            Arc::new(SolidColor::new(albedo_color)),
+            Arc::new(SolidColor::scalar(fuzz)),
+        )
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, fuzz: Arc<dyn Texture>) -> Self {
+        Self { albedo, fuzz }
     }
 }
 
 impl Scatter for Metal {
     fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
-        let reflected = ray.direction.reflect(hit.normal).to_unit_vector();
-        let scattered_ray = Ray::new(hit.p, reflected);
+        let fuzz = self.fuzz.value_at(hit).x().clamp(0.0, 1.0);
+        let reflected = ray.direction.to_unit_vector().reflect(hit.normal);
+        let scattered_ray = Ray::new(hit.p, reflected + fuzz * random_vector_in_unit_sphere());
 
+        // fuzzed rays pointing into the surface are absorbed
         if scattered_ray.direction.dot(hit.normal) > 0.0 {
-            Some((scattered_ray, self.albedo))
+            Some((scattered_ray, self.albedo.value_at(hit)))
         } else {
             None
         }
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::Hit,
+    material::Scatter,
+    ray::Ray,
+    texture::{SolidColor, Texture},
+    utils::random_in_range,
+    vector::Vector,
+};
+
+// blends two materials by a mask: 0 is all `a`, 1 is all `b`. Good for worn
+// paint over metal or dirt over tiles without writing a new BRDF
+pub struct Mix {
+    a: Arc<dyn Scatter>,
+    b: Arc<dyn Scatter>,
+    // scalar mask, read from the x channel
+    mask: Arc<dyn Texture>,
+}
+
+impl Mix {
+    pub fn new(a: Arc<dyn Scatter>, b: Arc<dyn Scatter>, factor: f64) -> Self {
+        Self::textured(a, b, Arc::new(SolidColor::scalar(factor)))
+    }
+
+    pub fn textured(a: Arc<dyn Scatter>, b: Arc<dyn Scatter>, mask: Arc<dyn Texture>) -> Self {
+        Self { a, b, mask }
+    }
+
+    fn factor(&self, hit: &Hit) -> f64 {
+        self.mask.value_at(hit).x().clamp(0.0, 1.0)
+    }
+}
+
+impl Scatter for Mix {
+    // picking a child with probability equal to its weight averages to the
+    // blended BRDF, so the attenuation needs no correction
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        self.scatter_lobe(ray, hit)
+            .map(|(scattered, attenuation, _)| (scattered, attenuation))
+    }
+
+    fn scatter_lobe(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector, bool)> {
+        if random_in_range(0.0, 1.0) < self.factor(hit) {
+            self.b.scatter_lobe(ray, hit)
+        } else {
+            self.a.scatter_lobe(ray, hit)
+        }
+    }
+
+    // a mirror-like child adds nothing here, its light only arrives along the
+    // rays it scatters itself. None only when both children are mirror-like
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        let a = self.a.eval(ray, hit, direction);
+        let b = self.b.eval(ray, hit, direction);
+        if a.is_none() && b.is_none() {
+            return None;
+        }
+
+        let black = Vector(0.0, 0.0, 0.0);
+        let t = self.factor(hit);
+        Some((1.0 - t) * a.unwrap_or(black) + t * b.unwrap_or(black))
+    }
+
+    // density of the rays the non-mirror child scatters, picked (1 - t) or t
+    // of the time
+    fn pdf(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        let a = self.a.pdf(ray, hit, direction);
+        let b = self.b.pdf(ray, hit, direction);
+        if a.is_none() && b.is_none() {
+            return None;
+        }
+
+        let t = self.factor(hit);
+        Some((1.0 - t) * a.unwrap_or(0.0) + t * b.unwrap_or(0.0))
+    }
+
+    // an opaque child blocks its share of the light, so a mask blending glass
+    // (or a clear dielectric) with an opaque material gives partial shadows
+    fn transmittance(&self, ray: &Ray, hit: &Hit) -> Option<Vector> {
+        let a = self.a.transmittance(ray, hit);
+        let b = self.b.transmittance(ray, hit);
+        if a.is_none() && b.is_none() {
+            return None;
+        }
+
+        let black = Vector(0.0, 0.0, 0.0);
+        let t = self.factor(hit);
+        Some((1.0 - t) * a.unwrap_or(black) + t * b.unwrap_or(black))
+    }
+
+    // diffuse only if both sides are
+    fn diffuse(&self, hit: &Hit) -> Option<Vector> {
+        let a = self.a.diffuse(hit)?;
+        let b = self.b.diffuse(hit)?;
+        let t = self.factor(hit);
+        Some((1.0 - t) * a + t * b)
+    }
+
+    fn occlusion(&self, hit: &Hit) -> f64 {
+        let t = self.factor(hit);
+        (1.0 - t) * self.a.occlusion(hit) + t * self.b.occlusion(hit)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Vector {
+        let t = self.factor(hit);
+        (1.0 - t) * self.a.emitted(ray, hit) + t * self.b.emitted(ray, hit)
+    }
+}
@@ -1,2 +1,13 @@
+pub mod bump;
+pub mod coat;
+pub mod cook_torrance;
+pub mod dielectric;
+pub mod emissive;
 pub mod lambertian;
 pub mod metal;
+pub mod mix;
+pub mod occlusion_map;
+pub mod one_sided;
+pub mod oren_nayar;
+pub mod principled;
+pub mod subsurface;
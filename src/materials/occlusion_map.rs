@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use crate::{hittable::Hit, material::Scatter, ray::Ray, texture::Texture, vector::Vector};
+
+// another material with a pre-baked ambient occlusion map, as game assets
+// ship with: the first channel darkens the bounced, sky and ambient light the
+// surface receives, 1 leaving it as is. Direct light from `Light`s is left to
+// the shadow rays
+pub struct OcclusionMap {
+    material: Arc<dyn Scatter>,
+    occlusion: Arc<dyn Texture>,
+}
+
+impl OcclusionMap {
+    pub fn new(material: Arc<dyn Scatter>, occlusion: Arc<dyn Texture>) -> Self {
+        Self {
+            material,
+            occlusion,
+        }
+    }
+}
+
+impl Scatter for OcclusionMap {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        self.material.scatter(ray, hit)
+    }
+
+    fn scatter_lobe(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector, bool)> {
+        self.material.scatter_lobe(ray, hit)
+    }
+
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        self.material.eval(ray, hit, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        self.material.pdf(ray, hit, direction)
+    }
+
+    fn transmittance(&self, ray: &Ray, hit: &Hit) -> Option<Vector> {
+        self.material.transmittance(ray, hit)
+    }
+
+    fn diffuse(&self, hit: &Hit) -> Option<Vector> {
+        self.material.diffuse(hit)
+    }
+
+    fn occlusion(&self, hit: &Hit) -> f64 {
+        let baked = self.occlusion.value_at(hit).x().clamp(0.0, 1.0);
+        baked * self.material.occlusion(hit)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Vector {
+        self.material.emitted(ray, hit)
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use crate::{hittable::Hit, material::Scatter, ray::Ray, vector::Vector};
+
+// limits another material to the front of the surface: hit from behind it is
+// black and emits nothing. Materials are two-sided by default; wrap thin light
+// panels and open meshes in this so their backs don't glow or reflect
+pub struct OneSided {
+    material: Arc<dyn Scatter>,
+}
+
+impl OneSided {
+    pub fn new(material: Arc<dyn Scatter>) -> Self {
+        Self { material }
+    }
+}
+
+impl Scatter for OneSided {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        if hit.front_face {
+            self.material.scatter(ray, hit)
+        } else {
+            None
+        }
+    }
+
+    fn scatter_lobe(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector, bool)> {
+        if hit.front_face {
+            self.material.scatter_lobe(ray, hit)
+        } else {
+            None
+        }
+    }
+
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        if hit.front_face {
+            self.material.eval(ray, hit, direction)
+        } else {
+            Some(Vector(0.0, 0.0, 0.0))
+        }
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        self.material.pdf(ray, hit, direction)
+    }
+
+    fn transmittance(&self, ray: &Ray, hit: &Hit) -> Option<Vector> {
+        self.material.transmittance(ray, hit)
+    }
+
+    fn diffuse(&self, hit: &Hit) -> Option<Vector> {
+        if hit.front_face {
+            self.material.diffuse(hit)
+        } else {
+            Some(Vector(0.0, 0.0, 0.0))
+        }
+    }
+
+    fn occlusion(&self, hit: &Hit) -> f64 {
+        self.material.occlusion(hit)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Vector {
+        if hit.front_face {
+            self.material.emitted(ray, hit)
+        } else {
+            Vector(0.0, 0.0, 0.0)
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    hittable::Hit,
+    material::Scatter,
+    ray::Ray,
+    texture::{SolidColor, Texture},
+    utils::random_unit_vector,
+    vector::Vector,
+};
+
+// rough diffuse surface (Oren-Nayar) for matte materials like clay or concrete,
+// brighter toward the light at grazing angles than Lambertian
+pub struct OrenNayar {
+    albedo: Arc<dyn Texture>,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    // roughness is the standard deviation of the microfacet angle in degrees,
+    // 0 is Lambertian and around 30 looks like clay
+    pub fn new(albedo: Vector, roughness: f64) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)), roughness)
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, roughness: f64) -> Self {
+        let sigma2 = roughness.to_radians().powi(2);
+
+        Self {
+            albedo,
+            a: 1.0 - sigma2 / (2.0 * (sigma2 + 0.33)),
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
+        }
+    }
+}
+
+impl OrenNayar {
+    // Oren-Nayar factor over Lambertian for view direction `v` and light `l`
+    fn factor(&self, n: Vector, v: Vector, l: Vector) -> f64 {
+        let cos_l = n.dot(l).clamp(0.0, 1.0);
+        let cos_v = n.dot(v).clamp(0.0, 1.0);
+        let sin_l = (1.0 - cos_l * cos_l).sqrt();
+        let sin_v = (1.0 - cos_v * cos_v).sqrt();
+
+        // cosine of the azimuth between the two directions in the tangent plane
+        let mut cos_phi = 0.0;
+        if sin_l > 1.0e-4 && sin_v > 1.0e-4 {
+            let lt = (l - cos_l * n).to_unit_vector();
+            let vt = (v - cos_v * n).to_unit_vector();
+            cos_phi = lt.dot(vt).max(0.0);
+        }
+
+        // sin(alpha) * tan(beta), alpha the larger polar angle and beta the smaller
+        let (sin_alpha, tan_beta) = if cos_l > cos_v {
+            (sin_v, sin_l / cos_l.max(1.0e-4))
+        } else {
+            (sin_l, sin_v / cos_v.max(1.0e-4))
+        };
+
+        self.a + self.b * cos_phi * sin_alpha * tan_beta
+    }
+}
+
+impl Scatter for OrenNayar {
+    // cosine-weighted like Lambertian, so the weight is just albedo times the
+    // Oren-Nayar factor
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        let n = hit.normal;
+        let mut direction = n + random_unit_vector();
+        if direction.near_zero() {
+            direction = n;
+        }
+
+        let l = direction.to_unit_vector();
+        let v = -ray.direction.to_unit_vector();
+        Some((
+            Ray::new(hit.p, direction),
+            self.factor(n, v, l) * self.albedo.value_at(hit),
+        ))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        let n = hit.normal;
+        let v = -ray.direction.to_unit_vector();
+        let cosine = n.dot(direction).max(0.0);
+        let factor = self.factor(n, v, direction);
+        Some((factor * cosine / PI) * self.albedo.value_at(hit))
+    }
+
+    fn pdf(&self, _: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        Some(hit.normal.dot(direction).max(0.0) / PI)
+    }
+}
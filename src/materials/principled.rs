@@ -0,0 +1,289 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::Hit,
+    material::Scatter,
+    materials::cook_torrance::{
+        fresnel_schlick, ggx_distribution, sample_ggx_half_vector, smith_g1,
+    },
+    ray::Ray,
+    texture::{Channel, Texture},
+    utils::{orthonormal_basis, random_in_range, random_unit_vector},
+    vector::Vector,
+};
+
+// Disney "principled" BSDF: one material covering the usual
+// baseColor/metallic/roughness parameter set of Blender and glTF exports.
+// All parameters are in [0, 1]; build with struct update syntax, e.g.
+// `Principled { base_color: color.into(), ..Default::default() }`
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: Arc<dyn Texture>,
+    pub roughness: Arc<dyn Texture>,
+    // where the two maps keep their values, red by default; glTF packs
+    // roughness in green and metallic in blue
+    pub metallic_channel: Channel,
+    pub roughness_channel: Channel,
+    // scales the dielectric reflectance, 0.5 is the common 4%
+    pub specular: f64,
+    // tints dielectric specular toward the base color
+    pub specular_tint: f64,
+    // extra grazing reflection for cloth
+    pub sheen: f64,
+    pub sheen_tint: f64,
+    // second, white GGX-like lobe on top, as on car paint
+    pub clearcoat: f64,
+    pub clearcoat_gloss: f64,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Self {
+            base_color: Vector(0.8, 0.8, 0.8).into(),
+            metallic: Vector(0.0, 0.0, 0.0).into(),
+            roughness: Vector(0.5, 0.5, 0.5).into(),
+            metallic_channel: Channel::Red,
+            roughness_channel: Channel::Red,
+            specular: 0.5,
+            specular_tint: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+        }
+    }
+}
+
+fn lerp(a: Vector, b: Vector, t: f64) -> Vector {
+    (1.0 - t) * a + t * b
+}
+
+fn schlick_weight(cosine: f64) -> f64 {
+    (1.0 - cosine).clamp(0.0, 1.0).powi(5)
+}
+
+// Berry (GTR1) distribution of the clearcoat lobe
+fn gtr1_distribution(n_dot_h: f64, alpha: f64) -> f64 {
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (std::f64::consts::PI * a2.ln() * (1.0 + (a2 - 1.0) * n_dot_h * n_dot_h))
+}
+
+// the material with its textures looked up at one hit point
+struct Lobes<'a> {
+    m: &'a Principled,
+    base_color: Vector,
+    metallic: f64,
+    roughness: f64,
+}
+
+impl Lobes<'_> {
+    // base color normalized to luminance 1, used for the tint parameters
+    fn tint(&self) -> Vector {
+        let lum = self.base_color.luminance();
+        if lum > 0.0 {
+            (1.0 / lum) * self.base_color
+        } else {
+            Vector(1.0, 1.0, 1.0)
+        }
+    }
+
+    fn specular_color(&self) -> Vector {
+        let white = Vector(1.0, 1.0, 1.0);
+        let dielectric = 0.08 * self.m.specular * lerp(white, self.tint(), self.m.specular_tint);
+        lerp(dielectric, self.base_color, self.metallic)
+    }
+
+    fn alpha(&self) -> f64 {
+        self.roughness.clamp(0.02, 1.0).powi(2)
+    }
+
+    fn clearcoat_alpha(&self) -> f64 {
+        0.1 + (0.001 - 0.1) * self.m.clearcoat_gloss
+    }
+
+    // probabilities of picking the diffuse, specular and clearcoat lobes
+    fn lobe_weights(&self, n_dot_v: f64) -> [f64; 3] {
+        let diffuse = (1.0 - self.metallic) * self.base_color.luminance();
+        let specular = fresnel_schlick(self.specular_color(), n_dot_v).luminance();
+        let clearcoat = 0.25 * self.m.clearcoat * (0.04 + 0.96 * schlick_weight(n_dot_v));
+
+        let total = diffuse + specular + clearcoat;
+        if total <= 0.0 {
+            return [1.0, 0.0, 0.0];
+        }
+        [diffuse / total, specular / total, clearcoat / total]
+    }
+
+    // cosine-weighted bounce; f * cos / pdf of the Disney diffuse plus sheen
+    // diffuse with sheen, specular and clearcoat for light from `l`, times cosine
+    fn eval(&self, n: Vector, v: Vector, l: Vector) -> Vector {
+        let n_dot_l = n.dot(l);
+        let n_dot_v = n.dot(v).max(1.0e-4);
+        if n_dot_l <= 0.0 {
+            return Vector(0.0, 0.0, 0.0);
+        }
+        let h = (l + v).to_unit_vector();
+        let n_dot_h = n.dot(h).max(0.0);
+        let l_dot_h = l.dot(h).max(0.0);
+
+        let fd90 = 0.5 + 2.0 * self.roughness * l_dot_h * l_dot_h;
+        let fl = 1.0 + (fd90 - 1.0) * schlick_weight(n_dot_l);
+        let fv = 1.0 + (fd90 - 1.0) * schlick_weight(n_dot_v);
+        let white = Vector(1.0, 1.0, 1.0);
+        let sheen = (self.m.sheen * schlick_weight(l_dot_h) * std::f64::consts::PI)
+            * lerp(white, self.tint(), self.m.sheen_tint);
+        let diffuse = (n_dot_l / std::f64::consts::PI)
+            * ((1.0 - self.metallic) * (fl * fv * self.base_color + sheen));
+
+        let alpha = self.alpha();
+        let g = smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha);
+        let specular = (ggx_distribution(n_dot_h, alpha) * g / (4.0 * n_dot_v))
+            * fresnel_schlick(self.specular_color(), l_dot_h);
+
+        let d = gtr1_distribution(n_dot_h, self.clearcoat_alpha());
+        let cc_g = smith_g1(n_dot_v, 0.25) * smith_g1(n_dot_l, 0.25);
+        let clearcoat =
+            0.25 * self.m.clearcoat * (0.04 + 0.96 * schlick_weight(l_dot_h)) * d * cc_g
+                / (4.0 * n_dot_v);
+
+        diffuse + specular + Vector(clearcoat, clearcoat, clearcoat)
+    }
+
+    // density of `Principled::scatter` picking `l`, over all three lobes
+    fn pdf(&self, n: Vector, v: Vector, l: Vector) -> f64 {
+        let [p_diffuse, p_specular, p_clearcoat] = self.lobe_weights(n.dot(v).max(1.0e-4));
+        let h = (l + v).to_unit_vector();
+        let n_dot_h = n.dot(h).max(0.0);
+        // half vector densities converted to the reflected direction
+        let jacobian = n_dot_h / (4.0 * v.dot(h).max(1.0e-4));
+
+        p_diffuse * n.dot(l).max(0.0) / std::f64::consts::PI
+            + p_specular * ggx_distribution(n_dot_h, self.alpha()) * jacobian
+            + p_clearcoat * gtr1_distribution(n_dot_h, self.clearcoat_alpha()) * jacobian
+    }
+
+    fn sample_diffuse(&self, n: Vector, v: Vector) -> Option<(Vector, Vector)> {
+        let mut l = n + random_unit_vector();
+        if l.near_zero() {
+            l = n;
+        }
+        let l = l.to_unit_vector();
+        let h = (l + v).to_unit_vector();
+
+        let n_dot_l = n.dot(l);
+        let n_dot_v = n.dot(v);
+        let l_dot_h = l.dot(h);
+        if n_dot_l <= 0.0 {
+            return None;
+        }
+
+        // retro-reflection at grazing angles grows with roughness
+        let fd90 = 0.5 + 2.0 * self.roughness * l_dot_h * l_dot_h;
+        let fl = 1.0 + (fd90 - 1.0) * schlick_weight(n_dot_l);
+        let fv = 1.0 + (fd90 - 1.0) * schlick_weight(n_dot_v);
+
+        let white = Vector(1.0, 1.0, 1.0);
+        let sheen = (self.m.sheen * schlick_weight(l_dot_h) * std::f64::consts::PI)
+            * lerp(white, self.tint(), self.m.sheen_tint);
+
+        let weight = (1.0 - self.metallic) * (fl * fv * self.base_color + sheen);
+        Some((l, weight))
+    }
+
+    fn sample_specular(&self, n: Vector, v: Vector) -> Option<(Vector, Vector)> {
+        let alpha = self.alpha();
+        let h = sample_ggx_half_vector(n, alpha);
+        let v_dot_h = v.dot(h);
+        let l = 2.0 * v_dot_h * h - v;
+
+        let n_dot_l = n.dot(l);
+        let n_dot_v = n.dot(v).max(1.0e-4);
+        if n_dot_l <= 0.0 || v_dot_h <= 0.0 {
+            return None;
+        }
+
+        let g = smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha);
+        let scale = g * v_dot_h / (n_dot_v * n.dot(h).max(1.0e-4));
+        Some((l, scale * fresnel_schlick(self.specular_color(), v_dot_h)))
+    }
+
+    // GTR1 distribution with a fixed 4% Fresnel and roughness 0.25 shadowing
+    fn sample_clearcoat(&self, n: Vector, v: Vector) -> Option<(Vector, Vector)> {
+        let a2 = self.clearcoat_alpha().powi(2);
+
+        let u1 = random_in_range(0.0, 1.0);
+        let u2 = random_in_range(0.0, 1.0);
+        let phi = 2.0 * std::f64::consts::PI * u1;
+        let cos_theta = ((1.0 - a2.powf(1.0 - u2)) / (1.0 - a2)).max(0.0).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+
+        let (t, b) = orthonormal_basis(n);
+        let h = sin_theta * phi.cos() * t + sin_theta * phi.sin() * b + cos_theta * n;
+        let v_dot_h = v.dot(h);
+        let l = 2.0 * v_dot_h * h - v;
+
+        let n_dot_l = n.dot(l);
+        let n_dot_v = n.dot(v).max(1.0e-4);
+        if n_dot_l <= 0.0 || v_dot_h <= 0.0 {
+            return None;
+        }
+
+        let fresnel = 0.04 + 0.96 * schlick_weight(v_dot_h);
+        let g = smith_g1(n_dot_v, 0.25) * smith_g1(n_dot_l, 0.25);
+        let scale =
+            0.25 * self.m.clearcoat * fresnel * g * v_dot_h / (n_dot_v * cos_theta.max(1.0e-4));
+        Some((l, Vector(scale, scale, scale)))
+    }
+}
+
+impl Principled {
+    fn lobes(&self, hit: &Hit) -> Lobes<'_> {
+        Lobes {
+            m: self,
+            base_color: self.base_color.value_at(hit),
+            metallic: self
+                .metallic_channel
+                .pick(self.metallic.value_at(hit))
+                .clamp(0.0, 1.0),
+            roughness: self
+                .roughness_channel
+                .pick(self.roughness.value_at(hit))
+                .clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Scatter for Principled {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        let n = hit.normal;
+        let v = -ray.direction.to_unit_vector();
+        let lobes = self.lobes(hit);
+
+        // one lobe per bounce, weighted back up by its selection probability
+        let [p_diffuse, p_specular, p_clearcoat] = lobes.lobe_weights(n.dot(v).max(1.0e-4));
+        let u = random_in_range(0.0, 1.0);
+
+        let (direction, weight, p) = if u < p_diffuse {
+            let (l, w) = lobes.sample_diffuse(n, v)?;
+            (l, w, p_diffuse)
+        } else if u < p_diffuse + p_specular {
+            let (l, w) = lobes.sample_specular(n, v)?;
+            (l, w, p_specular)
+        } else {
+            let (l, w) = lobes.sample_clearcoat(n, v)?;
+            (l, w, p_clearcoat)
+        };
+
+        Some((Ray::new(hit.p, direction), (1.0 / p) * weight))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<Vector> {
+        let v = -ray.direction.to_unit_vector();
+        Some(self.lobes(hit).eval(hit.normal, v, direction))
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &Hit, direction: Vector) -> Option<f64> {
+        let v = -ray.direction.to_unit_vector();
+        Some(self.lobes(hit).pdf(hit.normal, v, direction))
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{
+    hittable::Hit,
+    material::Scatter,
+    materials::dielectric::Dielectric,
+    ray::Ray,
+    utils::{random_in_range, random_unit_vector},
+    vector::Vector,
+};
+
+// translucent solid (wax, skin, marble, milk) traced as a random walk: light
+// refracts in through a smooth dielectric boundary, bounces off particles inside
+// and leaves somewhere else. Only works on closed objects
+pub struct Subsurface {
+    // fraction of light kept at each bounce inside; many bounces compound it,
+    // so values near 1 (0.9 to 0.999) are typical
+    albedo: Vector,
+    // average distance between bounces in scene units; the smaller it is relative
+    // to the object the more opaque it looks and the more bounces a path needs
+    mean_free_path: f64,
+    ir: f64,
+}
+
+impl Subsurface {
+    pub fn new(albedo: Vector, mean_free_path: f64, index_of_refraction: f64) -> Self {
+        Self {
+            albedo,
+            mean_free_path,
+            ir: index_of_refraction,
+        }
+    }
+
+    // reflection or refraction at the boundary, as in `Dielectric`
+    fn cross_boundary(&self, unit_direction: Vector, hit: &Hit) -> Vector {
+        let refraction_ratio = if hit.front_face {
+            1.0 / self.ir
+        } else {
+            self.ir
+        };
+
+        let cos_theta = (-unit_direction).dot(hit.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+        if cannot_refract
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > random_in_range(0.0, 1.0)
+        {
+            unit_direction.reflect(hit.normal)
+        } else {
+            unit_direction.refract(hit.normal, refraction_ratio)
+        }
+    }
+}
+
+impl Scatter for Subsurface {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<(Ray, Vector)> {
+        let unit_direction = ray.direction.to_unit_vector();
+
+        if !hit.front_face {
+            // the ray travelled inside; sample how far it got before meeting a
+            // particle, and if that is short of the surface scatter it there
+            let distance = hit.t * ray.direction.length();
+            let free_flight = -(1.0 - random_in_range(0.0, 1.0)).ln() * self.mean_free_path;
+
+            if free_flight < distance {
+                let p = ray.origin + free_flight * unit_direction;
+                return Some((Ray::new(p, random_unit_vector()), self.albedo));
+            }
+        }
+
+        let direction = self.cross_boundary(unit_direction, hit);
+        Some((Ray::new(hit.p, direction), Vector(1.0, 1.0, 1.0)))
+    }
+}
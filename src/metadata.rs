@@ -0,0 +1,49 @@
+use std::io;
+
+// render settings stored as PNG tEXt chunks, so an image remembers how it was
+// made; `settings-from-image` reads them back
+
+pub fn write_png(
+    path: &str,
+    pixels: &[lodepng::RGB<u8>],
+    width: usize,
+    height: usize,
+    settings: &[(String, String)],
+) -> Result<(), lodepng::Error> {
+    let mut encoder = lodepng::Encoder::new();
+    encoder
+        .info_raw_mut()
+        .set_colortype(lodepng::ColorType::RGB);
+    encoder.info_raw_mut().set_bitdepth(8);
+
+    for (key, value) in settings {
+        encoder.info_png_mut().add_text(key, value)?;
+    }
+
+    encoder.encode_file(path, pixels, width, height)
+}
+
+// every tEXt chunk of the PNG at `path`, in file order
+pub fn read(path: &str) -> io::Result<Vec<(String, String)>> {
+    let data = std::fs::read(path)?;
+
+    let mut decoder = lodepng::Decoder::new();
+    decoder.read_text_chunks(true);
+    decoder.decode(&data).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("could not read \"{}\": {}", path, err),
+        )
+    })?;
+
+    Ok(decoder
+        .info_png()
+        .text_keys()
+        .map(|(k, v)| {
+            (
+                String::from_utf8_lossy(k).into_owned(),
+                String::from_utf8_lossy(v).into_owned(),
+            )
+        })
+        .collect())
+}
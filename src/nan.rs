@@ -0,0 +1,28 @@
+// Shading checks for the `debug-nan` feature: a NaN or infinity anywhere in a path
+// poisons the whole pixel, so stop at the first one and say where it came from.
+use std::cell::Cell;
+
+use crate::hittable::Hit;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+thread_local! {
+    static PIXEL: Cell<(i32, i32)> = const { Cell::new((0, 0)) };
+}
+
+// records the pixel being shaded for the panic message
+pub fn set_pixel(x: i32, y: i32) {
+    PIXEL.with(|p| p.set((x, y)));
+}
+
+pub fn check(what: &str, value: Vector, r: &Ray, hit: &Hit, depth: i32) {
+    if value.x().is_finite() && value.y().is_finite() && value.z().is_finite() {
+        return;
+    }
+
+    let (x, y) = PIXEL.with(|p| p.get());
+    panic!(
+        "non-finite {} {:?} at pixel ({}, {}), bounce {}: ray {:?} hit t = {} p = {:?} normal = {:?}",
+        what, value, x, y, depth, r, hit.t, hit.p, hit.normal
+    );
+}
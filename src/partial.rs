@@ -0,0 +1,113 @@
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+
+use crate::color::ColorConfig;
+use crate::vector::Vector;
+
+const PARTIAL_MAGIC: &[u8; 4] = b"PRT1";
+
+// unclamped per-pixel average of one process's samples, rows top first;
+// independent renders of the same frame are combined with `merge`
+pub struct Partial {
+    pub width: u32,
+    pub height: u32,
+    pub samples: u64,
+    pub pixels: Vec<Vector>,
+}
+
+impl Partial {
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let mut out = Vec::with_capacity(20 + self.pixels.len() * 24);
+        out.extend_from_slice(PARTIAL_MAGIC);
+        out.extend_from_slice(&self.width.to_le_bytes());
+        out.extend_from_slice(&self.height.to_le_bytes());
+        out.extend_from_slice(&self.samples.to_le_bytes());
+
+        for p in &self.pixels {
+            for axis in 0..3 {
+                out.extend_from_slice(&p.axis(axis).to_le_bytes());
+            }
+        }
+
+        fs::write(path, out)
+    }
+
+    pub fn read(path: &str) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a partial render");
+
+        if data.len() < 20 || &data[0..4] != PARTIAL_MAGIC {
+            return Err(invalid());
+        }
+        let width = u32::from_le_bytes(data[4..8].try_into().unwrap());
+        let height = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        let samples = u64::from_le_bytes(data[12..20].try_into().unwrap());
+
+        let count = width as usize * height as usize;
+        if data.len() != 20 + count * 24 {
+            return Err(invalid());
+        }
+
+        let pixels = data[20..]
+            .chunks_exact(24)
+            .map(|c| {
+                let f = |i: usize| f64::from_le_bytes(c[i * 8..i * 8 + 8].try_into().unwrap());
+                Vector(f(0), f(1), f(2))
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            samples,
+            pixels,
+        })
+    }
+
+    // average of the parts weighted by their sample counts, None if there are no
+    // parts or their sizes differ
+    pub fn merge(parts: &[Partial]) -> Option<Partial> {
+        let first = parts.first()?;
+        if parts
+            .iter()
+            .any(|p| p.width != first.width || p.height != first.height)
+        {
+            return None;
+        }
+
+        let samples: u64 = parts.iter().map(|p| p.samples).sum();
+        let mut pixels = vec![Vector(0.0, 0.0, 0.0); first.pixels.len()];
+        for part in parts {
+            let weight = part.samples as f64 / samples.max(1) as f64;
+            for (sum, &p) in pixels.iter_mut().zip(&part.pixels) {
+                *sum = *sum + weight * p;
+            }
+        }
+
+        Some(Partial {
+            width: first.width,
+            height: first.height,
+            samples,
+            pixels,
+        })
+    }
+
+    // the averaged radiance through `color`'s display pipeline, the way
+    // `Scene` writes its own output
+    pub fn make_png(&self, fname: &str, color: &ColorConfig) -> bool {
+        let rgb: Vec<lodepng::RGB<u8>> = self
+            .pixels
+            .iter()
+            .map(|&p| color.display(p).to_rgb())
+            .collect();
+
+        match lodepng::encode24_file(fname, &rgb, self.width as usize, self.height as usize) {
+            Ok(()) => true,
+            Err(err) => {
+                println!("Error writing file \"{}\": {}", fname, err);
+                false
+            }
+        }
+    }
+}
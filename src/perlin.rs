@@ -0,0 +1,97 @@
+use crate::rand::{Rng, SeedableRng, StdRng};
+use crate::vector::Vector;
+
+const POINT_COUNT: usize = 256;
+
+// gradient noise over 3D space with a lattice of random unit vectors,
+// the permutation tables come from `seed` so patterns are reproducible
+pub struct Perlin {
+    gradients: Vec<Vector>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: usize) -> Self {
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+
+        let gradients = (0..POINT_COUNT)
+            .map(|_| loop {
+                let g = Vector(
+                    rng.gen_range(-1.0, 1.0),
+                    rng.gen_range(-1.0, 1.0),
+                    rng.gen_range(-1.0, 1.0),
+                );
+                let len = g.length();
+                if len > 1.0e-3 && len <= 1.0 {
+                    break g / len;
+                }
+            })
+            .collect();
+
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            rng.shuffle(&mut p);
+            p
+        };
+        let perm_x = permutation();
+        let perm_y = permutation();
+        let perm_z = permutation();
+
+        Self {
+            gradients,
+            perm_x,
+            perm_y,
+            perm_z,
+        }
+    }
+
+    // smooth noise in roughly [-1, 1]
+    pub fn noise(&self, p: Vector) -> f64 {
+        let (fx, fy, fz) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (u, v, w) = (p.x() - fx, p.y() - fy, p.z() - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+
+        // Hermite smoothing hides the lattice
+        let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+        let (uu, vv, ww) = (smooth(u), smooth(v), smooth(w));
+
+        let wrap = |n: i64| (n & (POINT_COUNT as i64 - 1)) as usize;
+        let mut sum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let g = self.gradients[self.perm_x[wrap(i + di)]
+                        ^ self.perm_y[wrap(j + dj)]
+                        ^ self.perm_z[wrap(k + dk)]];
+                    let (a, b, c) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vector(u - a, v - b, w - c);
+
+                    sum += (a * uu + (1.0 - a) * (1.0 - uu))
+                        * (b * vv + (1.0 - b) * (1.0 - vv))
+                        * (c * ww + (1.0 - c) * (1.0 - ww))
+                        * g.dot(weight);
+                }
+            }
+        }
+
+        sum
+    }
+
+    // sum of `octaves` layers of noise at doubling frequency and halving weight,
+    // always positive
+    pub fn turbulence(&self, p: Vector, octaves: u32) -> f64 {
+        let mut sum = 0.0;
+        let mut point = p;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            sum += weight * self.noise(point);
+            weight *= 0.5;
+            point = 2.0 * point;
+        }
+
+        sum.abs()
+    }
+}
@@ -0,0 +1,150 @@
+use std::f64::consts::PI;
+
+use crate::environment::Environment;
+use crate::hittable::Hit;
+use crate::ray::Ray;
+use crate::utils::{orthonormal_basis, random_in_unit_disk, random_unit_vector};
+use crate::vector::Vector;
+
+// a bundle of light that reached a diffuse surface through glass or mirrors
+pub struct Photon {
+    pub position: Vector,
+    // unit vector it was travelling along
+    pub direction: Vector,
+    // of the surface it landed on, facing where it came from
+    pub normal: Vector,
+    pub flux: Vector,
+}
+
+// caustic photons in a balanced kd-tree, stored implicitly: the photon in the
+// middle of any range splits it along its axis, see `build`
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    // split axis of the photon at the same index
+    axes: Vec<u8>,
+    // photons within this distance of a shading point light it
+    radius: f64,
+}
+
+impl PhotonMap {
+    pub fn new(mut photons: Vec<Photon>, radius: f64) -> Self {
+        let mut axes = vec![0; photons.len()];
+        build(&mut photons, &mut axes);
+        Self {
+            photons,
+            axes,
+            radius,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    // light the caustic photons around `h` reflect back along `r`: their flux
+    // over the disc they were gathered from, weighted by the BRDF
+    pub fn radiance(&self, r: &Ray, h: &Hit) -> Vector {
+        let mut total = Vector(0.0, 0.0, 0.0);
+        self.search(0, self.photons.len(), h.p, &mut |photon| {
+            // photons on the other side of a thin wall
+            if photon.normal.dot(h.normal) < 0.5 {
+                return;
+            }
+            let incoming = -photon.direction;
+            let cosine = h.normal.dot(incoming);
+            if cosine <= 1e-3 {
+                return;
+            }
+            // `eval` includes the cosine, the photon's flux already does
+            if let Some(f) = h.material.eval(r, h, incoming) {
+                total = total + (f / cosine) * photon.flux;
+            }
+        });
+
+        total / (PI * self.radius * self.radius)
+    }
+
+    fn search(&self, lo: usize, hi: usize, p: Vector, f: &mut impl FnMut(&Photon)) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = (lo + hi) / 2;
+        let photon = &self.photons[mid];
+        if (photon.position - p).squared_length() <= self.radius * self.radius {
+            f(photon);
+        }
+
+        let axis = self.axes[mid] as usize;
+        let d = p.axis(axis) - photon.position.axis(axis);
+        let (near, far) = if d < 0.0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search(near.0, near.1, p, f);
+        if d * d <= self.radius * self.radius {
+            self.search(far.0, far.1, p, f);
+        }
+    }
+}
+
+// puts the median along the widest axis in the middle of the range, smaller
+// positions before it and larger after, then does the same for both halves
+fn build(photons: &mut [Photon], axes: &mut [u8]) {
+    if photons.len() <= 1 {
+        return;
+    }
+
+    let (mut min, mut max) = (photons[0].position, photons[0].position);
+    for p in photons.iter() {
+        min = Vector(
+            min.x().min(p.position.x()),
+            min.y().min(p.position.y()),
+            min.z().min(p.position.z()),
+        );
+        max = Vector(
+            max.x().max(p.position.x()),
+            max.y().max(p.position.y()),
+            max.z().max(p.position.z()),
+        );
+    }
+    let extent = max - min;
+    let axis = (0..3)
+        .max_by(|&a, &b| extent.axis(a).total_cmp(&extent.axis(b)))
+        .unwrap_or(0);
+
+    let mid = photons.len() / 2;
+    photons.select_nth_unstable_by(mid, |a, b| {
+        a.position.axis(axis).total_cmp(&b.position.axis(axis))
+    });
+    axes[mid] = axis as u8;
+
+    let (left, right) = photons.split_at_mut(mid);
+    let (left_axes, right_axes) = axes.split_at_mut(mid);
+    build(left, left_axes);
+    build(&mut right[1..], &mut right_axes[1..]);
+}
+
+// one photon from the sky toward the sphere at `target` with `radius`,
+// starting `far` away so distant geometry still shadows it: the ray, and the
+// flux it carries if it were the only one shot
+pub fn emit_from_environment(
+    environment: &Environment,
+    target: Vector,
+    radius: f64,
+    far: f64,
+) -> (Ray, Vector) {
+    let toward = random_unit_vector();
+    let (t, b) = orthonormal_basis(toward);
+    let d = random_in_unit_disk();
+    let origin = target + radius * (d.x() * t + d.y() * b) + far * toward;
+
+    // uniform over the sphere of directions and the disc facing each
+    let flux = (4.0 * PI * PI * radius * radius) * environment.radiance(toward);
+    (Ray::new(origin, -toward), flux)
+}
@@ -0,0 +1,129 @@
+use crate::sphere::Sphere;
+use crate::vector::Vector;
+
+pub struct SettleOptions {
+    // height of the ground plane (y)
+    pub ground: f64,
+    pub gravity: f64,
+    pub time_step: f64,
+    pub iterations: usize,
+    // fraction of normal velocity kept after a bounce
+    pub restitution: f64,
+    // fraction of tangential velocity lost per ground contact step
+    pub friction: f64,
+}
+
+impl Default for SettleOptions {
+    fn default() -> Self {
+        Self {
+            ground: 0.0,
+            gravity: 9.81,
+            time_step: 1.0 / 120.0,
+            iterations: 600,
+            restitution: 0.2,
+            friction: 0.1,
+        }
+    }
+}
+
+// overlap resolution passes per time step
+const RELAXATION_PASSES: usize = 4;
+
+// rigid-body pre-pass run before rendering: drops `dynamic` spheres under gravity
+// onto the ground plane and each other, leaving their centers where they come to
+// rest without interpenetrating; `fixed` spheres collide but never move
+pub fn settle(dynamic: &mut [Sphere], fixed: &[Sphere], options: &SettleOptions) {
+    let dt = options.time_step;
+    let mut velocity = vec![Vector(0.0, 0.0, 0.0); dynamic.len()];
+
+    for _ in 0..options.iterations {
+        for (s, v) in dynamic.iter_mut().zip(velocity.iter_mut()) {
+            *v = *v + Vector(0.0, -options.gravity * dt, 0.0);
+            s.center = s.center + dt * *v;
+        }
+
+        // friction acts once per step, however many passes touch the ground
+        let mut grounded = vec![false; dynamic.len()];
+        for _ in 0..RELAXATION_PASSES {
+            for i in 0..dynamic.len() {
+                // ground plane
+                let r = dynamic[i].radius.abs();
+                if dynamic[i].center.y() - r < options.ground {
+                    let c = dynamic[i].center;
+                    dynamic[i].center = Vector(c.x(), options.ground + r, c.z());
+
+                    let v = velocity[i];
+                    let vy = if v.y() < 0.0 {
+                        -v.y() * options.restitution
+                    } else {
+                        v.y()
+                    };
+                    velocity[i] = Vector(v.x(), vy, v.z());
+                    grounded[i] = true;
+                }
+
+                // static obstacles
+                for f in fixed {
+                    if let Some((n, overlap)) = contact(&dynamic[i], f) {
+                        dynamic[i].center = dynamic[i].center - overlap * n;
+                        velocity[i] = bounce(velocity[i], -n, options.restitution);
+                    }
+                }
+
+                // other moving spheres, split by mass (volume)
+                for j in (i + 1)..dynamic.len() {
+                    if let Some((n, overlap)) = contact(&dynamic[i], &dynamic[j]) {
+                        let mi = dynamic[i].radius.abs().powi(3);
+                        let mj = dynamic[j].radius.abs().powi(3);
+                        let share_i = mj / (mi + mj);
+
+                        dynamic[i].center = dynamic[i].center - overlap * share_i * n;
+                        dynamic[j].center = dynamic[j].center + overlap * (1.0 - share_i) * n;
+
+                        // remove the approaching part of the relative velocity
+                        let approach = (velocity[i] - velocity[j]).dot(n);
+                        if approach > 0.0 {
+                            let impulse = (1.0 + options.restitution) * approach;
+                            velocity[i] = velocity[i] - impulse * share_i * n;
+                            velocity[j] = velocity[j] + impulse * (1.0 - share_i) * n;
+                        }
+                    }
+                }
+            }
+        }
+
+        let keep = 1.0 - options.friction;
+        for (v, _) in velocity.iter_mut().zip(&grounded).filter(|(_, &g)| g) {
+            *v = Vector(v.x() * keep, v.y(), v.z() * keep);
+        }
+    }
+}
+
+// unit direction from a to b and their penetration depth, if they overlap
+fn contact(a: &Sphere, b: &Sphere) -> Option<(Vector, f64)> {
+    let d = b.center - a.center;
+    let distance = d.length();
+    let overlap = a.radius.abs() + b.radius.abs() - distance;
+
+    if overlap <= 0.0 {
+        return None;
+    }
+
+    let n = if distance > 1.0e-9 {
+        d / distance
+    } else {
+        Vector(0.0, 1.0, 0.0)
+    };
+
+    Some((n, overlap))
+}
+
+// reflects the part of v going against the surface normal n
+fn bounce(v: Vector, n: Vector, restitution: f64) -> Vector {
+    let vn = v.dot(n);
+    if vn < 0.0 {
+        v - (1.0 + restitution) * vn * n
+    } else {
+        v
+    }
+}
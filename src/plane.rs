@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::material::Scatter;
+use crate::ray::Ray;
+use crate::utils::orthonormal_basis;
+use crate::vector::Vector;
+
+// infinite plane through `point`. Its bounding box is infinite, so keep it out
+// of the accelerators; `Scene::set_ground` traces it separately
+pub struct Plane {
+    pub point: Vector,
+    pub normal: Vector,
+    pub material: Arc<dyn Scatter>,
+    // directions of u and v in the plane, one unit of texture space per scene unit
+    tangent: Vector,
+    bitangent: Vector,
+}
+
+impl Plane {
+    pub fn new(point: Vector, normal: Vector, m: Arc<dyn Scatter>) -> Self {
+        let normal = normal.to_unit_vector();
+        let (tangent, bitangent) = orthonormal_basis(normal);
+
+        Self {
+            point,
+            normal,
+            material: m,
+            tangent,
+            bitangent,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let denom = r.direction.dot(self.normal);
+        if denom.abs() < 1.0e-12 {
+            return None;
+        }
+
+        let t = (self.point - r.origin).dot(self.normal) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let p = r.line_to_p(t);
+        let offset = p - self.point;
+        let front_face = denom < 0.0;
+
+        Some(Hit {
+            t,
+            p,
+            local: p,
+            normal: if front_face {
+                self.normal
+            } else {
+                -self.normal
+            },
+            front_face,
+            material: self.material.clone(),
+            u: offset.dot(self.tangent),
+            v: offset.dot(self.bitangent),
+            dpdu: self.tangent,
+            dpdv: self.bitangent,
+            object: 0,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(
+            Vector(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Vector(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        )
+    }
+}
@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use crate::camera::{Camera, PerspectiveCamera};
+use crate::cuboid::Cuboid;
+use crate::fractals;
+use crate::hittable::Hittable;
+use crate::material::Scatter;
+use crate::materials::{
+    cook_torrance::CookTorrance, dielectric::Dielectric, emissive::Emissive,
+    lambertian::Lambertian, metal::Metal, oren_nayar::OrenNayar, principled::Principled,
+};
+use crate::procedural;
+use crate::sphere::Sphere;
+use crate::texture::{Checker, Marble, Texture, Turbulence, Wood};
+use crate::vector::Vector;
+use crate::ASPECT_RATIO;
+
+// camera and objects to hand to `Scene::new`
+pub type SceneParts = (Box<dyn Camera>, Vec<Box<dyn Hittable>>);
+
+// a named, ready-to-render scene
+pub struct Preset {
+    pub name: &'static str,
+    pub build: fn() -> SceneParts,
+}
+
+pub fn all() -> Vec<Preset> {
+    vec![
+        Preset {
+            name: "spheres",
+            build: spheres,
+        },
+        Preset {
+            name: "materials",
+            build: materials,
+        },
+        Preset {
+            name: "textures",
+            build: textures,
+        },
+        Preset {
+            name: "shapes",
+            build: shapes,
+        },
+    ]
+}
+
+pub fn find(name: &str) -> Option<Preset> {
+    all().into_iter().find(|p| p.name == name)
+}
+
+fn ground(material: Arc<Lambertian>) -> Box<dyn Hittable> {
+    Box::new(Sphere::new(&Vector(0.0, -1000.0, 0.0), 1000.0, material))
+}
+
+// a row of spheres seen from the front, shared by the material and texture presets
+fn row_camera() -> Box<dyn Camera> {
+    Box::new(PerspectiveCamera::new(
+        Vector(0.0, 1.2, 6.0),
+        Vector(0.0, 0.5, 0.0),
+        Vector(0.0, 1.0, 0.0),
+        35.0,
+        ASPECT_RATIO,
+        0.0,
+        6.0,
+    ))
+}
+
+// the original demo: three small diffuse spheres next to a large mirror
+pub fn spheres() -> SceneParts {
+    let lookfrom = Vector(16.0, 1.6, 3.0);
+    let lookat = Vector(0.0, 0.0, 0.0);
+    let vup = Vector(0.0, 1.0, 0.0);
+    let dist_to_focus = 15.0;
+    let aperture = 0.08;
+
+    let cam = Box::new(PerspectiveCamera::new(
+        lookfrom,
+        lookat,
+        vup,
+        20.0,
+        ASPECT_RATIO,
+        aperture,
+        dist_to_focus,
+    ));
+
+    let ground_material = Arc::new(Lambertian::new(Vector(0.7, 0.72, 0.62)));
+
+    let red_lambertian = Arc::new(Lambertian::new(Vector(0.6, 0.2, 0.2)));
+    let blue_lambertian = Arc::new(Lambertian::new(Vector(0.2, 0.6, 0.2)));
+    let green_lambertian = Arc::new(Lambertian::new(Vector(0.2, 0.2, 0.6)));
+
+    let metal = Arc::new(Metal::new(Vector(0.6, 0.6, 0.65), 0.0));
+
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        // diffuse material spheres
+        Box::new(Sphere::new(&Vector(0.0, -0.7, 0.4), 0.3, red_lambertian)),
+        Box::new(Sphere::new(&Vector(0.7, -0.7, 0.0), 0.3, blue_lambertian)),
+        Box::new(Sphere::new(&Vector(-0.7, -0.7, 0.8), 0.3, green_lambertian)),
+        // metal sphere
+        Box::new(Sphere::new(&Vector(-3.0, 0.0, 0.0), 1.0, metal)),
+        // ground
+        Box::new(Sphere::new(
+            &Vector(0.0, -1001.0, 0.0),
+            1000.0,
+            ground_material,
+        )),
+    ];
+
+    (cam, objects)
+}
+
+// one sphere per material model
+pub fn materials() -> SceneParts {
+    let copper = Vector(0.95, 0.64, 0.54);
+    let red = Vector(0.7, 0.15, 0.1);
+
+    let row: Vec<Arc<dyn Scatter>> = vec![
+        Arc::new(OrenNayar::new(Vector(0.7, 0.55, 0.45), 30.0)),
+        Arc::new(CookTorrance::new(copper, 0.3, 1.0)),
+        Arc::new(Dielectric::new(1.5)),
+        Arc::new(Principled {
+            base_color: red.into(),
+            clearcoat: 1.0,
+            ..Default::default()
+        }),
+        Arc::new(Metal::new(Vector(0.8, 0.8, 0.85), 0.2)),
+    ];
+
+    let mut objects = vec![ground(Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5))))];
+    for (i, material) in row.into_iter().enumerate() {
+        let x = -2.4 + 1.2 * i as f64;
+        objects.push(Box::new(Sphere::new(&Vector(x, 0.5, 0.0), 0.5, material)));
+    }
+
+    (row_camera(), objects)
+}
+
+// procedural and UV-mapped textures
+pub fn textures() -> SceneParts {
+    let checker = Checker::solid(
+        Vector(0.85, 0.85, 0.85).into(),
+        Vector(0.2, 0.3, 0.1).into(),
+        2.0,
+    );
+    let stripes = Checker::new(
+        Vector(0.9, 0.9, 0.9).into(),
+        Vector(0.1, 0.2, 0.6).into(),
+        8.0,
+    );
+    let marble = Marble::new(1, Vector(0.9, 0.9, 0.88), Vector(0.25, 0.25, 0.3), 4.0);
+    let wood = Wood::new(2, Vector(0.8, 0.6, 0.4), Vector(0.4, 0.25, 0.1), 10.0);
+    let clouds = Turbulence::new(3, Vector(0.3, 0.35, 0.5), Vector(0.95, 0.95, 0.95), 3.0);
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(Sphere::new(
+        &Vector(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::textured(Arc::new(checker))),
+    ))];
+
+    let spheres: Vec<Arc<dyn Texture>> = vec![
+        Arc::new(stripes),
+        Arc::new(marble),
+        Arc::new(wood),
+        Arc::new(clouds),
+    ];
+    for (i, texture) in spheres.into_iter().enumerate() {
+        let x = -1.8 + 1.2 * i as f64;
+        objects.push(Box::new(Sphere::new(
+            &Vector(x, 0.5, 0.0),
+            0.5,
+            Arc::new(Lambertian::textured(texture)),
+        )));
+    }
+
+    (row_camera(), objects)
+}
+
+// meshes, boxes and fractals lit by an emissive sphere
+pub fn shapes() -> SceneParts {
+    let clay = Arc::new(Lambertian::new(Vector(0.75, 0.7, 0.65)));
+    let gold = Arc::new(Metal::new(Vector(0.9, 0.7, 0.3), 0.1));
+    let teal = Arc::new(Lambertian::new(Vector(0.2, 0.5, 0.5)));
+
+    let vase_profile = [
+        (0.0, 0.0),
+        (0.35, 0.0),
+        (0.45, 0.3),
+        (0.25, 0.8),
+        (0.3, 1.1),
+        (0.0, 1.1),
+    ];
+
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        ground(Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5)))),
+        Box::new(fractals::menger_sponge(
+            Vector(-1.6, 0.5, 0.0),
+            1.0,
+            2,
+            clay.clone(),
+        )),
+        Box::new(procedural::lathe(&vase_profile, 32, gold)),
+        Box::new(fractals::sierpinski_tetrahedron(
+            Vector(1.6, 0.4, 0.0),
+            1.1,
+            3,
+            clay,
+        )),
+        Box::new(Cuboid::new(
+            Vector(-0.4, 0.0, 0.9),
+            Vector(0.4, 0.2, 1.3),
+            teal,
+        )),
+        Box::new(Sphere::new(
+            &Vector(0.0, 3.0, 1.5),
+            0.6,
+            Arc::new(Emissive::new(Vector(1.0, 0.9, 0.8), 4.0)),
+        )),
+    ];
+
+    (row_camera(), objects)
+}
@@ -0,0 +1,149 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::bvh::Bvh;
+use crate::hittable::Hittable;
+use crate::material::Scatter;
+use crate::triangle::Triangle;
+use crate::vector::Vector;
+
+// surface of revolution: `profile` is a list of (radius, height) points, bottom to
+// top, revolved around the y axis in `segments` steps; returns a smooth-shaded mesh
+pub fn lathe(profile: &[(f64, f64)], segments: usize, material: Arc<dyn Scatter>) -> Bvh {
+    let segments = segments.max(3);
+    let mut rings = Vec::with_capacity(profile.len());
+
+    for i in 0..profile.len() {
+        let (r, h) = profile[i];
+
+        // outward normal of the 2d profile from the neighbouring points
+        let (r0, h0) = profile[i.saturating_sub(1)];
+        let (r1, h1) = profile[(i + 1).min(profile.len() - 1)];
+        let (dr, dh) = (r1 - r0, h1 - h0);
+        let len = (dr * dr + dh * dh).sqrt().max(1.0e-12);
+        let (nr, nh) = (dh / len, -dr / len);
+
+        let ring: Vec<(Vector, Vector)> = (0..segments)
+            .map(|j| {
+                let (sin, cos) = (2.0 * PI * j as f64 / segments as f64).sin_cos();
+                (Vector(r * cos, h, r * sin), Vector(nr * cos, nh, nr * sin))
+            })
+            .collect();
+        rings.push(ring);
+    }
+
+    stitch_rings(&rings, material)
+}
+
+// tube of `radius` swept along a Catmull-Rom spline through `path`, with
+// `steps` samples per span and `sides` vertices around; the ends are left open
+pub fn sweep(
+    path: &[Vector],
+    radius: f64,
+    steps: usize,
+    sides: usize,
+    material: Arc<dyn Scatter>,
+) -> Bvh {
+    let sides = sides.max(3);
+    let samples = catmull_rom(path, steps.max(1));
+    if samples.len() < 2 {
+        return Bvh::new(Vec::new());
+    }
+
+    // parallel transport the frame along the curve so the tube doesn't twist
+    let first_tangent = samples[0].1;
+    let helper = if first_tangent.x().abs() < 0.9 {
+        Vector(1.0, 0.0, 0.0)
+    } else {
+        Vector(0.0, 1.0, 0.0)
+    };
+    let mut normal = first_tangent.cross(helper).to_unit_vector();
+
+    let mut rings = Vec::with_capacity(samples.len());
+    for (center, tangent) in samples {
+        normal = (normal - normal.dot(tangent) * tangent).to_unit_vector();
+        let binormal = tangent.cross(normal);
+
+        let ring: Vec<(Vector, Vector)> = (0..sides)
+            .map(|j| {
+                let (sin, cos) = (2.0 * PI * j as f64 / sides as f64).sin_cos();
+                let n = cos * normal + sin * binormal;
+                (center + radius * n, n)
+            })
+            .collect();
+        rings.push(ring);
+    }
+
+    // the lathe's rings run along +y with angles toward +z; the sweep's run along
+    // the tangent with angles toward the binormal, which has the opposite winding
+    for ring in rings.iter_mut() {
+        ring.reverse();
+    }
+
+    stitch_rings(&rings, material)
+}
+
+// joins consecutive closed rings of (position, normal) pairs with triangles
+fn stitch_rings(rings: &[Vec<(Vector, Vector)>], material: Arc<dyn Scatter>) -> Bvh {
+    let mut triangles: Vec<Box<dyn Hittable>> = Vec::new();
+
+    for pair in rings.windows(2) {
+        let (lower, upper) = (&pair[0], &pair[1]);
+        let n = lower.len();
+
+        for j in 0..n {
+            let k = (j + 1) % n;
+            let quad = [lower[j], upper[j], upper[k], lower[k]];
+
+            for [a, b, c] in [[0, 1, 2], [0, 2, 3]] {
+                let (pa, pb, pc) = (quad[a], quad[b], quad[c]);
+
+                // rings collapsed to a point produce slivers with no area
+                if (pb.0 - pa.0).cross(pc.0 - pa.0).near_zero() {
+                    continue;
+                }
+
+                triangles.push(Box::new(Triangle::with_normals(
+                    [pa.0, pb.0, pc.0],
+                    [pa.1, pb.1, pc.1],
+                    material.clone(),
+                )));
+            }
+        }
+    }
+
+    Bvh::new(triangles)
+}
+
+// samples a Catmull-Rom spline through `points` (ends clamped), returning
+// (position, unit tangent) pairs
+fn catmull_rom(points: &[Vector], steps: usize) -> Vec<(Vector, Vector)> {
+    let mut samples = Vec::new();
+    if points.len() < 2 {
+        return samples;
+    }
+
+    let last = points.len() - 1;
+    for i in 0..last {
+        let p0 = points[i.saturating_sub(1)];
+        let p1 = points[i];
+        let p2 = points[i + 1];
+        let p3 = points[(i + 2).min(last)];
+
+        let a = 2.0 * p1;
+        let b = p2 - p0;
+        let c = 2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3;
+        let d = 3.0 * p1 - p0 - 3.0 * p2 + p3;
+
+        // include the end point on the final span only
+        let count = if i == last - 1 { steps + 1 } else { steps };
+        for s in 0..count {
+            let t = s as f64 / steps as f64;
+            let position = 0.5 * (a + t * b + t * t * c + t * t * t * d);
+            let tangent = 0.5 * (b + 2.0 * t * c + 3.0 * t * t * d);
+            samples.push((position, tangent.to_unit_vector()));
+        }
+    }
+
+    samples
+}
@@ -4,6 +4,9 @@ use crate::vector::Vector;
 pub struct Ray {
     pub origin: Vector,
     pub direction: Vector,
+    // moment within the frame the ray travels at, 0 at the start of the frame
+    // and 1 at its end, see `Instance::set_motion`
+    pub time: f64,
 }
 
 impl Ray {
@@ -11,14 +14,47 @@ impl Ray {
         Self {
             origin: o,
             direction: d,
+            time: 0.0,
         }
     }
 
+    pub fn at_time(self, time: f64) -> Self {
+        Self { time, ..self }
+    }
+
     pub fn line_to_p(&self, p: f64) -> Vector {
         self.origin + p * self.direction
     }
 }
 
+// four rays traced together, with origins and reciprocal directions stored per axis
+// so box tests run across all lanes at once
+pub struct RayPacket {
+    pub rays: [Ray; 4],
+    pub origin: [[f64; 4]; 3],
+    pub inv_direction: [[f64; 4]; 3],
+}
+
+impl RayPacket {
+    pub fn new(rays: [Ray; 4]) -> Self {
+        let mut origin = [[0.0; 4]; 3];
+        let mut inv_direction = [[0.0; 4]; 3];
+
+        for axis in 0..3 {
+            for lane in 0..4 {
+                origin[axis][lane] = rays[lane].origin.axis(axis);
+                inv_direction[axis][lane] = 1.0 / rays[lane].direction.axis(axis);
+            }
+        }
+
+        Self {
+            rays,
+            origin,
+            inv_direction,
+        }
+    }
+}
+
 pub fn get_ray(origin: Vector, destination: Vector) -> Ray {
     Ray::new(origin, (destination - origin).to_unit_vector())
 }
@@ -0,0 +1,315 @@
+use crate::utils;
+
+// where the random numbers of a pixel's samples come from. Every draw made
+// while shading a sample (pixel position, lens, time, light and bounce
+// directions) is the next dimension of that sample, see `utils::begin_sample`.
+// Spreading each dimension evenly over the pixel's samples, rather than
+// drawing them independently, cleans up soft shadows and defocus with fewer
+// samples
+pub trait Sampler: Send + Sync {
+    // coordinate `dim` in [0, 1) of sample `index` of the `count` `pixel`
+    // takes; `scramble` is random per pixel so neighbors don't repeat a pattern
+    fn get(&self, pixel: (i32, i32), index: u32, count: u32, dim: u32, scramble: u32) -> f64;
+}
+
+// independent uniform numbers, the noisiest but free of any pattern
+pub struct RandomSampler;
+
+impl Sampler for RandomSampler {
+    fn get(&self, _pixel: (i32, i32), _index: u32, _count: u32, _dim: u32, _scramble: u32) -> f64 {
+        utils::random_uniform()
+    }
+}
+
+// pixel positions jittered within the cells of the largest square grid that
+// fits the sample count, and every other dimension jittered within `count`
+// shuffled strata. Indices past `count` start another, differently shuffled
+// pass. The default
+pub struct StratifiedSampler;
+
+impl Sampler for StratifiedSampler {
+    fn get(&self, _pixel: (i32, i32), index: u32, count: u32, dim: u32, scramble: u32) -> f64 {
+        let jitter = utils::random_uniform();
+        let count = count.max(1);
+        let scramble = scramble ^ hash(index / count);
+        let index = index % count;
+
+        if dim < 2 {
+            let side = (count as f64).sqrt() as u32;
+            // counts that aren't square fill up with random positions
+            if index >= side * side {
+                return jitter;
+            }
+            let cell = if dim == 0 { index % side } else { index / side };
+            return (cell as f64 + jitter) / side as f64;
+        }
+
+        let stratum = permute(index, count, hash(scramble ^ dim));
+        (stratum as f64 + jitter) / count as f64
+    }
+}
+
+// Sobol low-discrepancy sequence with a random digital shift per pixel, the
+// most even spread for power of two sample counts. Dimensions past the ones
+// it has direction numbers for are uniform random
+pub struct SobolSampler {
+    // generator matrix of each dimension, one direction number per bit
+    directions: Vec<[u32; 32]>,
+}
+
+// degree, polynomial coefficients and initial direction numbers of Sobol
+// dimensions after the first, from Joe and Kuo
+const SOBOL_POLYNOMIALS: [(u32, u32, &[u32]); 15] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+];
+
+impl SobolSampler {
+    pub fn new() -> Self {
+        // the first dimension is the van der Corput sequence
+        let mut first = [0u32; 32];
+        for (k, v) in first.iter_mut().enumerate() {
+            *v = 1 << (31 - k);
+        }
+
+        let mut directions = vec![first];
+        for (s, a, m) in SOBOL_POLYNOMIALS.iter() {
+            let s = *s as usize;
+            let mut v = [0u32; 32];
+            for k in 0..32 {
+                v[k] = if k < s {
+                    m[k] << (31 - k)
+                } else {
+                    let mut x = v[k - s] ^ (v[k - s] >> s);
+                    for j in 1..s {
+                        if (a >> (s - 1 - j)) & 1 == 1 {
+                            x ^= v[k - j];
+                        }
+                    }
+                    x
+                };
+            }
+            directions.push(v);
+        }
+
+        Self { directions }
+    }
+}
+
+impl Default for SobolSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn get(&self, _pixel: (i32, i32), index: u32, _count: u32, dim: u32, scramble: u32) -> f64 {
+        let v = match self.directions.get(dim as usize) {
+            Some(v) => v,
+            None => return utils::random_uniform(),
+        };
+
+        let mut bits = hash(scramble ^ dim.wrapping_mul(0x9e37_79b9));
+        let mut i = index;
+        let mut k = 0;
+        while i != 0 {
+            if i & 1 == 1 {
+                bits ^= v[k];
+            }
+            i >>= 1;
+            k += 1;
+        }
+
+        bits as f64 / 4_294_967_296.0
+    }
+}
+
+// width of the tileable blue noise texture `BlueNoiseSampler` dithers with
+const BLUE_NOISE_SIZE: usize = 64;
+
+// Sobol samples shifted by the same amount in every pixel, then rotated per
+// pixel by a blue noise texture, offset differently in each dimension
+// (Georgiev and Fajardo). Errors of neighboring pixels come out anticorrelated,
+// so the noise left at low sample counts is a fine, even grain instead of
+// blotches, and blurs away cleanly in a denoiser
+pub struct BlueNoiseSampler {
+    sobol: SobolSampler,
+    // values in [0, 1), BLUE_NOISE_SIZE squared, rows top to bottom
+    texture: Vec<f64>,
+    seed: u32,
+}
+
+impl BlueNoiseSampler {
+    // renders split across processes need a different seed each, see `--seed`
+    pub fn new(seed: u32) -> Self {
+        Self {
+            sobol: SobolSampler::new(),
+            texture: void_and_cluster(BLUE_NOISE_SIZE),
+            seed,
+        }
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn get(&self, pixel: (i32, i32), index: u32, count: u32, dim: u32, _scramble: u32) -> f64 {
+        let u = self.sobol.get(pixel, index, count, dim, self.seed);
+
+        let offset = hash(self.seed ^ hash(dim));
+        let size = BLUE_NOISE_SIZE as i32;
+        let x = (pixel.0 + (offset & 0xffff) as i32).rem_euclid(size) as usize;
+        let y = (pixel.1 + (offset >> 16) as i32).rem_euclid(size) as usize;
+
+        (u + self.texture[y * BLUE_NOISE_SIZE + x]).fract()
+    }
+}
+
+// blue noise texture `size` pixels square that tiles seamlessly: each pixel's
+// rank in the order void-and-cluster (Ulichney) fills the grid, scaled to [0, 1)
+fn void_and_cluster(size: usize) -> Vec<f64> {
+    let n = size * size;
+    let sigma: f64 = 1.5;
+
+    // gaussian energy a point spreads to each offset, wrapping around
+    let mut kernel = vec![0.0; n];
+    for dy in 0..size {
+        for dx in 0..size {
+            let x = dx.min(size - dx) as f64;
+            let y = dy.min(size - dy) as f64;
+            kernel[dy * size + dx] = (-(x * x + y * y) / (2.0 * sigma * sigma)).exp();
+        }
+    }
+
+    let mut points = vec![false; n];
+    let mut energy = vec![0.0; n];
+    let toggle = |points: &mut Vec<bool>, energy: &mut Vec<f64>, i: usize| {
+        points[i] = !points[i];
+        let sign = if points[i] { 1.0 } else { -1.0 };
+        let (px, py) = (i % size, i / size);
+        for y in 0..size {
+            let dy = (y + size - py) % size;
+            for x in 0..size {
+                let dx = (x + size - px) % size;
+                energy[y * size + x] += sign * kernel[dy * size + dx];
+            }
+        }
+    };
+    // tightest cluster among the points, or largest void among the gaps
+    let extreme = |points: &[bool], energy: &[f64], point: bool| -> usize {
+        let better = |a: f64, b: f64| if point { a > b } else { a < b };
+        let mut best = None;
+        for i in (0..n).filter(|&i| points[i] == point) {
+            best = match best {
+                Some(b) if !better(energy[i], energy[b]) => Some(b),
+                _ => Some(i),
+            };
+        }
+        best.unwrap_or(0)
+    };
+
+    // start from a tenth of the pixels picked by a fixed hash, so the texture
+    // is the same every run
+    let initial = n / 10;
+    let mut i = 0;
+    while points.iter().filter(|&&p| p).count() < initial {
+        let candidate = hash(i) as usize % n;
+        if !points[candidate] {
+            toggle(&mut points, &mut energy, candidate);
+        }
+        i += 1;
+    }
+
+    // spread it out: move the tightest cluster into the largest void until
+    // that would put the point straight back
+    loop {
+        let cluster = extreme(&points, &energy, true);
+        toggle(&mut points, &mut energy, cluster);
+        let void = extreme(&points, &energy, false);
+        toggle(&mut points, &mut energy, void);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut rank = vec![0; n];
+
+    // rank the initial points by taking the tightest clusters away first
+    let (mut scratch_points, mut scratch_energy) = (points.clone(), energy.clone());
+    for r in (0..initial).rev() {
+        let cluster = extreme(&scratch_points, &scratch_energy, true);
+        toggle(&mut scratch_points, &mut scratch_energy, cluster);
+        rank[cluster] = r;
+    }
+
+    // then fill the largest voids until every pixel has a rank
+    for r in initial..n {
+        let void = extreme(&points, &energy, false);
+        toggle(&mut points, &mut energy, void);
+        rank[void] = r;
+    }
+
+    rank.into_iter().map(|r| r as f64 / n as f64).collect()
+}
+
+// well mixed 32 bit value of `x`
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^ (x >> 16)
+}
+
+// position of `i` in a random shuffle of 0..l picked by `p`, without storing
+// the shuffle (Kensler, "Correlated Multi-Jittered Sampling")
+fn permute(mut i: u32, l: u32, p: u32) -> u32 {
+    if l <= 1 {
+        return 0;
+    }
+
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    // walks the shuffle of the next power of two until it lands below l
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            break;
+        }
+    }
+
+    (i.wrapping_add(p)) % l
+}
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::Hittable;
+use crate::instance::Instance;
+use crate::rand::{Rng, SeedableRng, StdRng};
+use crate::transform::Transform;
+use crate::vector::Vector;
+
+// where scattered copies are placed
+pub enum ScatterRegion {
+    // anywhere inside the box
+    Volume(Aabb),
+    // on the parallelogram spanned by two edges from a corner, with the object's
+    // +y axis aligned to the normal edge1 x edge2
+    Surface {
+        corner: Vector,
+        edge1: Vector,
+        edge2: Vector,
+    },
+}
+
+pub struct ScatterOptions {
+    pub count: usize,
+    // same seed, same layout
+    pub seed: usize,
+    // uniform scale picked from [min, max]
+    pub scale: (f64, f64),
+    // random spin around the up axis, up to this many degrees either way
+    pub max_rotation: f64,
+}
+
+impl Default for ScatterOptions {
+    fn default() -> Self {
+        Self {
+            count: 100,
+            seed: 0,
+            scale: (1.0, 1.0),
+            max_rotation: 180.0,
+        }
+    }
+}
+
+// places `options.count` jittered instances of a shared object in `region`
+pub fn scatter(
+    object: Arc<dyn Hittable>,
+    region: &ScatterRegion,
+    options: &ScatterOptions,
+) -> Vec<Box<dyn Hittable>> {
+    let mut rng: StdRng = SeedableRng::from_seed(&[options.seed][..]);
+    let mut instances: Vec<Box<dyn Hittable>> = Vec::with_capacity(options.count);
+
+    for _ in 0..options.count {
+        let scale = if options.scale.0 < options.scale.1 {
+            rng.gen_range(options.scale.0, options.scale.1)
+        } else {
+            options.scale.0
+        };
+        let spin = if options.max_rotation > 0.0 {
+            rng.gen_range(-options.max_rotation, options.max_rotation)
+        } else {
+            0.0
+        };
+
+        let local = Transform::scale(Vector(scale, scale, scale))
+            .then(&Transform::rotate(Vector(0.0, 1.0, 0.0), spin));
+
+        let placement = match region {
+            ScatterRegion::Volume(b) => Transform::translate(Vector(
+                lerp(b.min.x(), b.max.x(), rng.gen()),
+                lerp(b.min.y(), b.max.y(), rng.gen()),
+                lerp(b.min.z(), b.max.z(), rng.gen()),
+            )),
+            ScatterRegion::Surface {
+                corner,
+                edge1,
+                edge2,
+            } => {
+                let (u, v): (f64, f64) = (rng.gen(), rng.gen());
+                let position = *corner + u * *edge1 + v * *edge2;
+                align_up(edge1.cross(*edge2).to_unit_vector()).then(&Transform::translate(position))
+            }
+        };
+
+        let mut instance = Instance::new(object.clone());
+        instance.set_transform(local.then(&placement));
+        instances.push(Box::new(instance));
+    }
+
+    instances
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+// rotation taking +y onto `normal`
+fn align_up(normal: Vector) -> Transform {
+    let up = Vector(0.0, 1.0, 0.0);
+    let axis = up.cross(normal);
+    let cos = up.dot(normal).clamp(-1.0, 1.0);
+
+    if axis.near_zero() {
+        if cos > 0.0 {
+            Transform::identity()
+        } else {
+            Transform::rotate(Vector(1.0, 0.0, 0.0), 180.0)
+        }
+    } else {
+        Transform::rotate(axis, cos.acos().to_degrees())
+    }
+}
@@ -1,39 +1,805 @@
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex};
+
 use indicatif::ProgressStyle;
-use rand::Rng;
 
+use crate::aabb::Aabb;
+use crate::accelerator::{self, Accelerator, AcceleratorKind};
 use crate::camera::Camera;
+use crate::camera_path::CameraPath;
+use crate::color::ColorConfig;
+use crate::dataset::Frame;
+use crate::environment::Environment;
+use crate::framebuffer::Framebuffer;
+use crate::hittable::{Hit, Hittable};
 use crate::indicatif::ProgressBar;
+use crate::integrator::{Integrator, PathTracer};
+use crate::irradiance_cache::{IrradianceCache, IrradianceRecord};
+use crate::light::{Light, LightLinks};
+use crate::material::Scatter;
+use crate::materials::lambertian::Lambertian;
+use crate::metadata;
+use crate::partial::Partial;
+use crate::photon_map::{self, Photon, PhotonMap};
+use crate::plane::Plane;
 use crate::ray::*;
-use crate::sphere::Hit;
-use crate::sphere::Sphere;
+use crate::sampler::{Sampler, StratifiedSampler};
+use crate::settings::RenderSettings;
+use crate::stats;
+use crate::utils;
 use crate::vector::Vector;
 
+// minimum distance along a ray before a hit counts, avoids self-intersection.
+// Relative to the size of the scene so it behaves the same at any scale: this is
+// 0.0003 for the ~2000 unit wide default scene.
+const T_MIN_RELATIVE: f64 = 1.5e-7;
+
+// surface `Scene::set_ground` puts under the scene
+pub enum Ground {
+    // an ordinary surface of this material
+    Material(Arc<dyn Scatter>),
+    // invisible except for the shadows and occlusion the scene casts onto it,
+    // for compositing renders over photos
+    ShadowCatcher,
+}
+
+// unshadowed fill light every surface gets on top of the traced lighting, for
+// lifting dark interiors without more samples. Not physical, so off by default
+#[derive(Clone, Copy, Default)]
+pub enum Ambient {
+    #[default]
+    Off,
+    // the same radiance from every direction
+    Color(Vector),
+    // the environment again, ignoring anything that blocks it, times a strength
+    Environment(f64),
+}
+
+// how `Scene::render_stereo` puts the two eyes in one image
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StereoLayout {
+    // left eye then right eye, twice as wide as a normal render
+    SideBySide,
+    // left eye in the red channel, right eye in green and blue, for red/cyan glasses
+    Anaglyph,
+}
+
+// linear value a gray card is balanced to, the reflectance of an 18% photographic card
+const GRAY_CARD_TARGET: f64 = 0.18;
+
+// sampler dimensions a camera ray may use: pixel position, lens position and
+// time. Shading draws from the ones after
+const CAMERA_DIMENSIONS: u32 = 5;
+
+// grid of camera rays `build_caustics` looks for glass and mirrors with
+const CAUSTIC_PROBES: (i32, i32) = (64, 36);
+
+// pixel spacing of the first of the ever finer passes `build_irradiance_cache`
+// places records in, so the first ones spread out over the image
+const IRRADIANCE_PROBE_STEP: usize = 16;
+
+// how far, in pixels, an irradiance record reaches at least and at most
+const IRRADIANCE_REACH: (f64, f64) = (1.5, 32.0);
+
+// transparent surfaces a shadow ray passes before it counts as blocked
+const MAX_SHADOW_CROSSINGS: usize = 16;
+
 pub struct Scene {
-    camera: Camera,
-    objects: Vec<Sphere>,
+    camera: Box<dyn Camera>,
+    objects: Box<dyn Accelerator>,
+    t_min: f64,
     pub height: i32,
     pub width: i32,
+    pub settings: RenderSettings,
     pub pixels: Vec<lodepng::RGB<u8>>,
+    // linear passes rendered so far, see `render_pass`
+    framebuffer: Framebuffer,
+    // light traced into the camera during the current pass, see `splat`
+    splats: Mutex<Vec<Vector>>,
     filename: String,
+    // semantic class of each object by its index in the list, empty if unlabeled
+    labels: Vec<String>,
+    // object whose average color sets exposure and white balance, see `set_gray_card`
+    gray_card: Option<usize>,
+    color: ColorConfig,
+    environment: Environment,
+    ground: Option<Plane>,
+    shadow_catcher: bool,
+    ambient: Ambient,
+    integrator: Box<dyn Integrator>,
+    sampler: Arc<dyn Sampler>,
+    // pixel rectangle to render, see `set_crop`
+    crop: Option<(i32, i32, i32, i32)>,
+    lights: Vec<Light>,
+    // photons focused by glass and mirrors, see `build_caustics`
+    caustics: Option<PhotonMap>,
+    // bounced light on diffuse surfaces, see `build_irradiance_cache`
+    irradiance_cache: Option<IrradianceCache>,
+    // lights each object takes direct light from by its index in the list,
+    // missing entries take all of them
+    light_links: Vec<LightLinks>,
+    // settings embedded in the PNG, see `add_metadata`
+    metadata: Vec<(String, String)>,
 }
 
 impl Scene {
-    pub fn new(c: Camera, o: Vec<Sphere>, filename: String) -> Self {
+    pub fn new(c: Box<dyn Camera>, o: Vec<Box<dyn Hittable>>, filename: String) -> Self {
+        Self::with_accelerator(c, o, filename, AcceleratorKind::default())
+    }
+
+    pub fn with_accelerator(
+        c: Box<dyn Camera>,
+        o: Vec<Box<dyn Hittable>>,
+        filename: String,
+        kind: AcceleratorKind,
+    ) -> Self {
         let pixels: Vec<lodepng::RGB<u8>> = Vec::new();
         let h = (crate::VIEWPORT_WIDTH as f64 / crate::ASPECT_RATIO) as i32;
         let w = crate::VIEWPORT_WIDTH;
 
+        let objects = accelerator::build(kind, o);
+        let size = objects.bounding_box().extent();
+        let size = size.x().max(size.y()).max(size.z());
+        let t_min = if size.is_finite() && size > 0.0 {
+            T_MIN_RELATIVE * size
+        } else {
+            T_MIN_RELATIVE
+        };
+
         Self {
             camera: c,
-            objects: o,
+            objects,
+            t_min,
             height: h,
             width: w,
-            pixels: pixels,
-            filename: filename,
+            settings: RenderSettings::default(),
+            pixels,
+            framebuffer: Framebuffer::new(w, h),
+            splats: Mutex::new(Vec::new()),
+            filename,
+            labels: Vec::new(),
+            gray_card: None,
+            color: ColorConfig::default(),
+            environment: Environment::default(),
+            ground: None,
+            shadow_catcher: false,
+            ambient: Ambient::Off,
+            integrator: Box::new(PathTracer),
+            sampler: Arc::new(StratifiedSampler),
+            crop: None,
+            lights: Vec::new(),
+            caustics: None,
+            irradiance_cache: None,
+            light_links: Vec::new(),
+            metadata: Vec::new(),
+        }
+    }
+
+    // tags the object at `index` in the list the scene was built from with a
+    // semantic class, written out by `render_dataset`
+    pub fn set_label(&mut self, index: usize, class: &str) {
+        if self.labels.len() <= index {
+            self.labels.resize(index + 1, String::new());
+        }
+        self.labels[index] = class.to_string();
+    }
+
+    // lower resolution and sample count for quick looks, the height follows the
+    // aspect ratio
+    pub fn set_preview(&mut self, width: i32, samples: i32) {
+        self.width = width;
+        self.height = (width as f64 / crate::ASPECT_RATIO) as i32;
+        self.settings.samples = samples;
+    }
+
+    // output size in pixels. The camera keeps its vertical field of view and
+    // sees more or less to the sides, see `image_plane_position`
+    pub fn set_resolution(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    // where a point in pixel space (x right, y up) falls on the camera's image
+    // plane, which spans 0 to 1 over the camera's own aspect ratio. The image
+    // height always covers the vertical field of view and the width follows
+    // from the output's aspect ratio, so renders of any shape aren't stretched
+    fn image_plane_position(&self, x: f64, y: f64) -> (f64, f64) {
+        let height = self.height as f64;
+        let x = 0.5 + (x - 0.5 * self.width as f64) / (height * self.camera.aspect_ratio());
+        (x, y / height)
+    }
+
+    // pixel position (rows bottom up) the camera's (s, t) lands on, the
+    // inverse of `image_plane_position`. None outside the image
+    fn image_position(&self, s: f64, t: f64) -> Option<(f64, f64)> {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let x = 0.5 * width + (s - 0.5) * height * self.camera.aspect_ratio();
+        let y = t * height;
+        if (0.0..width).contains(&x) && (0.0..height).contains(&y) {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    // whether light paths can be joined to the camera, see `Camera::project`
+    pub fn traces_light(&self) -> bool {
+        let (_, _, back) = self.camera.basis();
+        self.camera.project(self.camera.origin() - back).is_some()
+    }
+
+    // light from `p` straight into the camera: the pixel it lands on (rows
+    // bottom up, as `antialias_color` takes them) and what a unit of radiance
+    // toward the camera adds to it there, before averaging over the pass's
+    // samples. None out of view, and for cameras that don't trace light
+    pub fn camera_importance(&self, p: Vector) -> Option<(i32, i32, f64)> {
+        let (s, t, importance) = self.camera.project(p)?;
+        let (x, y) = self.image_position(s, t)?;
+
+        let d = p - self.camera.origin();
+        let (_, _, back) = self.camera.basis();
+        let cosine = -d.to_unit_vector().dot(back);
+        // each pixel is 1 / height of the unit square tall, and 1 / (height *
+        // aspect) wide
+        let pixel_area =
+            1.0 / (self.height as f64 * self.height as f64 * self.camera.aspect_ratio());
+        Some((
+            x as i32,
+            y as i32,
+            importance / pixel_area * cosine / d.squared_length(),
+        ))
+    }
+
+    // solid angle density of a camera ray along `direction`, zero out of view
+    // and for cameras that don't trace light
+    pub fn camera_pdf(&self, direction: Vector) -> f64 {
+        let direction = direction.to_unit_vector();
+        let projected = self.camera.project(self.camera.origin() + direction);
+        let (s, t, importance) = match projected {
+            Some(p) => p,
+            None => return 0.0,
+        };
+        if self.image_position(s, t).is_none() {
+            return 0.0;
+        }
+
+        let (_, _, back) = self.camera.basis();
+        let cosine = -direction.dot(back);
+        // pixels are spread over more or less than the unit square
+        let image_area = self.width as f64 / (self.height as f64 * self.camera.aspect_ratio());
+        importance * cosine / image_area
+    }
+
+    // adds light traced into the camera to the pixel at (x, y), see
+    // `camera_importance`. Only full passes (`render_pass`) pick it up
+    pub fn splat(&self, x: i32, y: i32, color: Vector) {
+        let mut splats = self.splats.lock().unwrap();
+        let index = ((self.height - 1 - y) * self.width + x) as usize;
+        if let Some(pixel) = splats.get_mut(index) {
+            *pixel = *pixel + color;
+        }
+    }
+
+    // what rays that leave the scene see, and so the ambient light
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = environment;
+    }
+
+    // adds an infinite horizontal plane touching the bottom of the scene, so
+    // imported models don't float in a void. Hits on it report the object index
+    // one past the last object, which `set_label` accepts like any other
+    pub fn set_ground(&mut self, ground: Ground) {
+        let y = self.objects.bounding_box().min.y();
+        let y = if y.is_finite() { y } else { 0.0 };
+
+        let (material, shadow_catcher): (Arc<dyn Scatter>, bool) = match ground {
+            Ground::Material(m) => (m, false),
+            Ground::ShadowCatcher => (Arc::new(Lambertian::new(Vector(1.0, 1.0, 1.0))), true),
+        };
+
+        self.ground = Some(Plane::new(
+            Vector(0.0, y, 0.0),
+            Vector(0.0, 1.0, 0.0),
+            material,
+        ));
+        self.shadow_catcher = shadow_catcher;
+    }
+
+    // adds the caustics of glass and mirrors, which paths from the camera
+    // rarely find: `photons` are shot from the lights and the environment
+    // toward the mirror-like surfaces the camera sees, and those that land on
+    // a diffuse surface after bouncing off one are gathered within `radius`
+    // of each shading point. Glass then stops letting shadow rays through,
+    // the photons carry that light instead. Call it after adding lights;
+    // glass and mirrors out of view cast no caustics
+    pub fn build_caustics(&mut self, photons: usize, radius: f64) {
+        self.caustics = None;
+        let (target, target_radius) = match self.specular_bounds() {
+            Some(b) => b,
+            None => {
+                println!("No glass or mirrors in view, rendering without caustics.");
+                return;
+            }
+        };
+        let far = self.objects.bounding_box().extent().length();
+        let far = if far.is_finite() {
+            far.max(target_radius)
+        } else {
+            1.0e4
+        };
+
+        // every light and the environment shoot an equal share
+        let sources = self.lights.len() + 1;
+        let mut stored = Vec::new();
+        for i in 0..photons {
+            let emitted = match self.lights.get(i % sources) {
+                Some(light) => light.emit(target, target_radius, far),
+                None => Some(photon_map::emit_from_environment(
+                    &self.environment,
+                    target,
+                    target_radius,
+                    far,
+                )),
+            };
+            let (mut ray, flux) = match emitted {
+                Some(e) => e,
+                None => continue,
+            };
+            let mut flux = (sources as f64 / photons as f64) * flux;
+
+            let mut bounced = false;
+            for _ in 0..self.settings.max_depth {
+                let hit = match self.check_hits(&ray) {
+                    Some(h) => h,
+                    None => break,
+                };
+                if hit.material.pdf(&ray, &hit, hit.normal).is_some() {
+                    if bounced {
+                        stored.push(Photon {
+                            position: hit.p,
+                            direction: ray.direction.to_unit_vector(),
+                            normal: hit.normal,
+                            flux,
+                        });
+                    }
+                    break;
+                }
+
+                match hit.material.scatter(&ray, &hit) {
+                    Some((scattered, attenuation)) => {
+                        flux = flux * attenuation;
+                        ray = scattered;
+                        bounced = true;
+                    }
+                    None => break,
+                }
+                if flux.near_zero() {
+                    break;
+                }
+            }
+        }
+
+        println!("Stored {} caustic photons.", stored.len());
+        self.caustics = Some(PhotonMap::new(stored, radius));
+    }
+
+    // light focused onto `h` by glass and mirrors, black without `build_caustics`
+    pub fn caustic_light(&self, r: &Ray, h: &Hit) -> Vector {
+        self.caustics
+            .as_ref()
+            .map_or(Vector(0.0, 0.0, 0.0), |map| map.radiance(r, h))
+    }
+
+    // speeds up scenes lit mostly by bounced light, such as interiors, with
+    // Ward's irradiance caching: light bounced onto diffuse surfaces changes
+    // slowly, so it's gathered along `rays` paths at scattered points the
+    // camera sees and interpolated in between. Coarse to fine passes over the
+    // pixels add a record wherever the ones placed so far don't cover the
+    // surface; a smaller `accuracy` places them closer together. The path
+    // tracer then takes the indirect light of diffuse surfaces seen directly
+    // or through glass and mirrors from the cache. Call it after adding
+    // lights and setting the environment
+    pub fn build_irradiance_cache(&mut self, rays: usize, accuracy: f64) {
+        self.irradiance_cache = None;
+        let mut cache = IrradianceCache::new(accuracy);
+        let side = ((rays as f64).sqrt().round() as usize).max(1);
+
+        let mut step = IRRADIANCE_PROBE_STEP;
+        while step >= 1 {
+            for y in (0..self.height).step_by(step) {
+                for x in (0..self.width).step_by(step) {
+                    let (r, h, footprint) = match self.irradiance_probe(x, y) {
+                        Some(probe) => probe,
+                        None => continue,
+                    };
+                    if cache.irradiance(h.p, h.normal).is_none() {
+                        let mut record = self.gather_irradiance(&r, &h, side);
+                        let (least, most) = IRRADIANCE_REACH;
+                        record.radius = record
+                            .radius
+                            .clamp(least * footprint / accuracy, most * footprint / accuracy);
+                        cache.insert(record);
+                    }
+                }
+            }
+            step /= 2;
+        }
+
+        println!("Stored {} irradiance records.", cache.len());
+        self.irradiance_cache = Some(cache);
+    }
+
+    // the first surface through the middle of pixel (x, y) whose bounced light
+    // the irradiance cache stands in for, past any glass and mirrors, with the
+    // ray that reaches it and the distance between neighbouring pixels there
+    fn irradiance_probe(&self, x: i32, y: i32) -> Option<(Ray, Hit, f64)> {
+        let (s, t) = self.image_plane_position(x as f64 + 0.5, y as f64 + 0.5);
+        if !self.camera.covers(s, t) {
+            return None;
         }
+        let mut ray = self.camera.center_ray(s, t);
+        let (s, t) = self.image_plane_position(x as f64 + 1.5, y as f64 + 0.5);
+        let neighbour = self.camera.center_ray(s, t);
+        let spread =
+            (neighbour.direction.to_unit_vector() - ray.direction.to_unit_vector()).length();
+        let offset = (neighbour.origin - ray.origin).length();
+
+        let mut distance = 0.0;
+        for _ in 0..self.settings.max_depth.max(1) {
+            let h = self.check_hits(&ray)?;
+            distance += h.t * ray.direction.length();
+            if h.material.diffuse(&h).is_some() {
+                return Some((ray, h, offset + spread * distance));
+            }
+            // glossy surfaces are path traced as usual
+            if h.material.pdf(&ray, &h, h.normal).is_some() {
+                return None;
+            }
+            let (scattered, _) = h.material.scatter(&ray, &h)?;
+            ray = scattered.at_time(ray.time);
+        }
+        None
     }
 
-    pub fn render(mut self) {
+    // a new irradiance record at `h`, path tracing the light that arrives
+    // along side × side cosine-distributed directions on a stratified grid
+    fn gather_irradiance(&self, r: &Ray, h: &Hit, side: usize) -> IrradianceRecord {
+        let (tangent, bitangent) = utils::orthonormal_basis(h.normal);
+        let mut total = Vector(0.0, 0.0, 0.0);
+        let mut inverse_distances = 0.0;
+        for j in 0..side {
+            for i in 0..side {
+                let u = (i as f64 + utils::random_uniform()) / side as f64;
+                let v = (j as f64 + utils::random_uniform()) / side as f64;
+                let radius = u.sqrt();
+                let phi = 2.0 * PI * v;
+                let direction = radius * phi.cos() * tangent
+                    + radius * phi.sin() * bitangent
+                    + (1.0 - u).max(0.0).sqrt() * h.normal;
+
+                let scattered = Ray::new(h.p, direction).at_time(r.time);
+                let next = self.check_hits(&scattered);
+                if let Some(n) = &next {
+                    inverse_distances += 1.0 / (n.t * scattered.direction.length());
+                }
+                let bsdf_pdf = h.material.pdf(r, h, direction.to_unit_vector());
+                total = total
+                    + self.trace_bounce(h, scattered, bsdf_pdf, next, 1, false)
+                    + self.ambient_radiance(direction);
+            }
+        }
+
+        // the mean of cosine-distributed radiance samples is irradiance over pi
+        let samples = (side * side) as f64;
+        IrradianceRecord {
+            position: h.p,
+            normal: h.normal,
+            irradiance: (PI / samples) * total,
+            radius: samples / inverse_distances,
+        }
+    }
+
+    // light bounced onto `h`, from the irradiance cache where one covers it
+    fn cached_indirect(&self, h: &Hit) -> Option<Vector> {
+        let cache = self.irradiance_cache.as_ref()?;
+        let albedo = h.material.diffuse(h)?;
+        let irradiance = cache.irradiance(h.p, h.normal)?;
+        Some((h.material.occlusion(h) / PI) * (albedo * irradiance))
+    }
+
+    // bounding sphere of the mirror-like surfaces the camera sees
+    fn specular_bounds(&self) -> Option<(Vector, f64)> {
+        let (columns, rows) = CAUSTIC_PROBES;
+        let mut bounds: Option<Aabb> = None;
+        for j in 0..rows {
+            for i in 0..columns {
+                let s = (i as f64 + 0.5) / columns as f64;
+                let t = (j as f64 + 0.5) / rows as f64;
+                if !self.camera.covers(s, t) {
+                    continue;
+                }
+
+                let ray = self.camera.center_ray(s, t);
+                if let Some(h) = self.check_hits(&ray) {
+                    if h.material.pdf(&ray, &h, h.normal).is_none() {
+                        bounds = Some(bounds.unwrap_or_else(Aabb::empty).grow(h.p));
+                    }
+                }
+            }
+        }
+
+        // the probes only see the front, leave room for the back
+        bounds.map(|b| (b.centroid(), 0.75 * b.extent().length() + self.t_min))
+    }
+
+    // how camera rays are shaded, path tracing unless changed
+    pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+
+    // where each pixel sample's random numbers come from, stratified unless
+    // changed. See `sampler::BlueNoiseSampler` for the least objectionable
+    // noise at low sample counts
+    pub fn set_sampler(&mut self, sampler: Box<dyn Sampler>) {
+        self.sampler = Arc::from(sampler);
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    pub fn set_ambient(&mut self, ambient: Ambient) {
+        self.ambient = ambient;
+    }
+
+    // fill radiance from `direction`, see `Ambient`
+    fn ambient_radiance(&self, direction: Vector) -> Vector {
+        match self.ambient {
+            Ambient::Off => Vector(0.0, 0.0, 0.0),
+            Ambient::Color(c) => c,
+            Ambient::Environment(strength) => strength * self.environment.radiance(direction),
+        }
+    }
+
+    // the ambient fill `h` reflects back along `r`, darkened by its baked
+    // occlusion, for integrators that don't scatter rays off every surface
+    pub fn ambient_light(&self, r: &Ray, h: &Hit) -> Vector {
+        if let Ambient::Off = self.ambient {
+            return Vector(0.0, 0.0, 0.0);
+        }
+        match h.material.scatter(r, h) {
+            Some((scattered, attenuation)) => {
+                h.material.occlusion(h) * (attenuation * self.ambient_radiance(scattered.direction))
+            }
+            None => Vector(0.0, 0.0, 0.0),
+        }
+    }
+
+    // adds a light sampled directly at every hit, see `Light`. Returns its
+    // index for `link_lights`
+    pub fn add_light(&mut self, light: Light) -> usize {
+        self.lights.push(light);
+        self.lights.len() - 1
+    }
+
+    // restricts which lights shine on the object at `index` in the list the
+    // scene was built from. Light bounced off other objects still reaches it
+    pub fn link_lights(&mut self, index: usize, links: LightLinks) {
+        if self.light_links.len() <= index {
+            self.light_links.resize(index + 1, LightLinks::All);
+        }
+        self.light_links[index] = links;
+    }
+
+    fn is_linked(&self, object: usize, light: usize) -> bool {
+        self.light_links
+            .get(object)
+            .is_none_or(|links| links.includes(light))
+    }
+
+    // records a setting in the PNG written by `render`, next to the resolution
+    // and sample count, so the image can be reproduced later
+    pub fn add_metadata(&mut self, key: &str, value: &str) {
+        self.metadata.push((key.to_string(), value.to_string()));
+    }
+
+    pub fn set_color_config(&mut self, config: ColorConfig) {
+        self.color = config;
+    }
+
+    // linear radiance to an output pixel, exposed, tone mapped and encoded as
+    // configured, see `ColorConfig`
+    fn encode_pixel(&self, c: Vector) -> lodepng::RGB<u8> {
+        self.color.display(c).to_rgb()
+    }
+
+    // marks the object at `index` as a neutral gray card: `render` then scales
+    // each channel of the whole image so the card comes out at mid-gray, which
+    // fixes exposure and white balance regardless of the lighting
+    pub fn set_gray_card(&mut self, index: usize) {
+        self.gray_card = Some(index);
+    }
+
+    // class ids are 1-based positions in the sorted list of distinct labels, 0 is
+    // background or unlabeled
+    fn class_legend(&self) -> Vec<String> {
+        let mut legend: Vec<String> = self
+            .labels
+            .iter()
+            .filter(|l| !l.is_empty())
+            .cloned()
+            .collect();
+        legend.sort();
+        legend.dedup();
+        legend
+    }
+
+    // renders only pixels x0..x1, y0..y1 (y counted down from the top like in
+    // image viewers). `render` keeps the rest from the image already at the
+    // output path if it is the same size, or leaves it black
+    pub fn set_crop(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        self.crop = Some((x0, y0, x1, y1));
+    }
+
+    // whether the pixel at column `x` and image row `row` (top to bottom) is in
+    // the crop window
+    fn in_crop(&self, x: i32, row: i32) -> bool {
+        match self.crop {
+            None => true,
+            Some((x0, y0, x1, y1)) => (x0..x1).contains(&x) && (y0..y1).contains(&row),
+        }
+    }
+
+    // fills the pixels outside the crop window from the previous render
+    fn keep_previous_outside_crop(&mut self) {
+        let previous = match lodepng::decode24_file(&self.filename) {
+            Ok(image)
+                if image.width == self.width as usize && image.height == self.height as usize =>
+            {
+                image.buffer
+            }
+            _ => return,
+        };
+
+        for (i, color) in previous.into_iter().enumerate() {
+            let (x, row) = (i as i32 % self.width, i as i32 / self.width);
+            if !self.in_crop(x, row) {
+                self.pixels[i] = color;
+            }
+        }
+    }
+
+    pub fn set_camera(&mut self, camera: Box<dyn Camera>) {
+        self.camera = camera;
+    }
+
+    // renders frame `frame` of an animation played at `fps` to the scene's
+    // file name with the frame number added, e.g. out/flight.0042.png
+    pub fn render_frame(&mut self, path: &CameraPath, frame: u32, fps: f64) {
+        let camera = match path.camera_at(frame as f64 / fps) {
+            Some(c) => c,
+            None => {
+                println!("Camera path has no keyframes.");
+                return;
+            }
+        };
+        self.camera = Box::new(camera);
+
+        let filename = self.filename.clone();
+        let stem = filename.trim_end_matches(".png");
+        self.filename = format!("{}.{:04}.png", stem, frame);
+        self.render();
+        self.filename = filename;
+    }
+
+    pub fn render(&mut self) {
+        self.framebuffer = Framebuffer::new(self.width, self.height);
+        self.render_pass();
+        self.write_estimate();
+        println!("Render complete.");
+    }
+
+    // renders `passes` passes of `settings.samples` per pixel, writing the
+    // average so far to the output after each, so a long render can be
+    // watched converge and stopped once it looks clean. Zero keeps going
+    // until the process is stopped
+    pub fn render_progressive(&mut self, passes: u32) {
+        self.framebuffer = Framebuffer::new(self.width, self.height);
+        while passes == 0 || self.framebuffer.passes() < passes {
+            self.render_pass();
+            self.write_estimate();
+            println!(
+                "Pass {} written, {} samples per pixel.",
+                self.framebuffer.passes(),
+                self.framebuffer.passes() as i64 * self.settings.samples as i64
+            );
+        }
+        println!("Render complete.");
+    }
+
+    // adds another `settings.samples` per pixel to the framebuffer, starting
+    // it over if the resolution changed
+    pub fn render_pass(&mut self) {
+        if self.framebuffer.width != self.width || self.framebuffer.height != self.height {
+            self.framebuffer = Framebuffer::new(self.width, self.height);
+        }
+        let colors = self.trace_pass();
+        self.framebuffer.add_pass(&colors);
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    // writes the framebuffer's current estimate to the output file
+    pub fn write_estimate(&mut self) {
+        let colors = self.balanced(self.framebuffer.estimate());
+        self.pixels = colors.iter().map(|&c| self.encode_pixel(c)).collect();
+        if self.crop.is_some() {
+            self.keep_previous_outside_crop();
+        }
+        self.make_png(self.filename.clone());
+    }
+
+    // renders the scene once per eye from cameras `interocular` apart, either
+    // next to each other (left eye on the left, for parallel viewing and VR) or
+    // as a red/cyan anaglyph. The eyes look straight ahead like the scene's
+    // camera, which must be a perspective one, and share its lens and shutter
+    pub fn render_stereo(&mut self, interocular: f64, layout: StereoLayout) {
+        let (right, _, _) = self.camera.basis();
+        let eyes = (
+            self.camera.translated(-0.5 * interocular * right),
+            self.camera.translated(0.5 * interocular * right),
+        );
+        let (left_camera, right_camera) = match eyes {
+            (Some(l), Some(r)) => (l, r),
+            _ => {
+                println!("Stereo needs a perspective camera.");
+                return;
+            }
+        };
+
+        let camera = std::mem::replace(&mut self.camera, left_camera);
+        let left = self.render_linear();
+        self.camera = right_camera;
+        let right_eye = self.render_linear();
+        self.camera = camera;
+
+        let width = self.width as usize;
+        let original_width = self.width;
+        self.pixels = match layout {
+            StereoLayout::SideBySide => {
+                self.width *= 2;
+                left.chunks(width)
+                    .zip(right_eye.chunks(width))
+                    .flat_map(|(l, r)| l.iter().chain(r))
+                    .map(|&c| self.encode_pixel(c))
+                    .collect()
+            }
+            // red filter over the left eye, cyan over the right
+            StereoLayout::Anaglyph => left
+                .iter()
+                .zip(&right_eye)
+                .map(|(l, r)| self.encode_pixel(Vector(l.x(), r.y(), r.z())))
+                .collect(),
+        };
+        self.make_png(self.filename.clone());
+        self.width = original_width;
+        println!("Render complete.");
+    }
+
+    // every pixel's linear color, top row first, balanced by the gray card if
+    // there is one
+    fn render_linear(&self) -> Vec<Vector> {
+        self.balanced(self.trace_pass())
+    }
+
+    // one pass of `settings.samples` per pixel, top row first
+    fn trace_pass(&self) -> Vec<Vector> {
         let progress = ProgressBar::new(self.height as u64);
         progress.set_style(
             ProgressStyle::with_template(
@@ -42,103 +808,650 @@ impl Scene {
             .unwrap()
             .progress_chars("##-"),
         );
+
+        let mut colors = Vec::with_capacity((self.width * self.height) as usize);
+        stats::take_samples();
+        *self.splats.lock().unwrap() =
+            vec![Vector(0.0, 0.0, 0.0); (self.width * self.height) as usize];
+
         for i in (0..self.height).rev() {
             progress.inc(1);
             for j in 0..self.width {
-                let color = self.antialias_color(crate::ANTIALIAS_SAMPLES, j, i);
+                // rows run bottom to top here
+                if !self.in_crop(j, self.height - 1 - i) {
+                    colors.push(Vector(0.0, 0.0, 0.0));
+                    continue;
+                }
+                colors.push(self.antialias_color(self.settings.samples, j, i));
+            }
+        }
+
+        progress.finish();
+        let samples = stats::take_samples();
+        if self.settings.noise_threshold > 0.0 {
+            let pixels = (self.width * self.height).max(1) as f64;
+            println!(
+                "Adaptive sampling took {:.1} samples per pixel on average.",
+                samples as f64 / pixels
+            );
+        }
+
+        // every camera sample traced one light path, any of which could land
+        // anywhere
+        let splats = std::mem::take(&mut *self.splats.lock().unwrap());
+        if samples > 0 {
+            for (c, s) in colors.iter_mut().zip(splats) {
+                *c = *c + s / samples as f64;
+            }
+        }
+        colors
+    }
+
+    // `colors` (top row first) scaled so the gray card comes out neutral
+    fn balanced(&self, colors: Vec<Vector>) -> Vec<Vector> {
+        let card = match self.gray_card {
+            Some(card) => card,
+            None => return colors,
+        };
 
-                self.pixels.push(color.to_rgb());
+        let mut card_sum = Vector(0.0, 0.0, 0.0);
+        let mut card_pixels = 0;
+        for (k, &color) in colors.iter().enumerate() {
+            let (j, row) = (k as i32 % self.width, k as i32 / self.width);
+            if self.in_crop(j, row) && self.object_at(j, self.height - 1 - row) == Some(card) {
+                card_sum = card_sum + color;
+                card_pixels += 1;
             }
         }
+
+        if card_pixels == 0 {
+            println!("Gray card not visible, leaving exposure unchanged.");
+            return colors;
+        }
+        let average = card_sum / card_pixels as f64;
+        let scale = |c: f64| if c > 0.0 { GRAY_CARD_TARGET / c } else { 1.0 };
+        let balance = Vector(scale(average.x()), scale(average.y()), scale(average.z()));
+        colors.into_iter().map(|c| balance * c).collect()
+    }
+
+    // index of the object seen through the center of a pixel
+    fn object_at(&self, pixel_x: i32, pixel_y: i32) -> Option<usize> {
+        let (x, y) = self.image_plane_position(pixel_x as f64 + 0.5, pixel_y as f64 + 0.5);
+        if !self.camera.covers(x, y) {
+            return None;
+        }
+
+        self.check_hits(&self.camera.center_ray(x, y))
+            .map(|h| h.object)
+    }
+
+    // renders `samples` per pixel with the random generator seeded by
+    // `seed` and keeps the unclamped average, so several processes using
+    // different seeds can be combined afterwards with `Partial::merge`
+    pub fn render_partial(&self, path: &str, seed: usize) -> std::io::Result<()> {
+        utils::seed_rng(seed);
+        // a full pass, so light paths the bidirectional integrator splats
+        // onto other pixels are kept
+        let pixels = self.trace_pass();
+
+        let partial = Partial {
+            width: self.width as u32,
+            height: self.height as u32,
+            samples: self.settings.samples as u64,
+            pixels,
+        };
+        partial.write(path)
+    }
+
+    // instead of shading, counts the node and primitive tests made by each pixel's
+    // camera ray and writes them as a false-color image (blue cheap, red expensive)
+    pub fn render_traversal_heatmap(mut self) {
+        let mut costs = Vec::with_capacity((self.width * self.height) as usize);
+        let mut total = stats::TraversalStats::default();
+
+        stats::take();
+        stats::set_counting(true);
+        for i in (0..self.height).rev() {
+            for j in 0..self.width {
+                let (x, y) = self.image_plane_position(j as f64 + 0.5, i as f64 + 0.5);
+                if self.camera.covers(x, y) {
+                    self.check_hits(&self.camera.generate_ray(x, y));
+                }
+
+                let pixel = stats::take();
+                total.nodes += pixel.nodes;
+                total.primitives += pixel.primitives;
+                costs.push(pixel.total());
+            }
+        }
+        stats::set_counting(false);
+
+        let max = costs.iter().copied().max().unwrap_or(0).max(1) as f64;
+        self.pixels = costs
+            .iter()
+            .map(|&c| stats::heat_color(c as f64 / max).to_rgb())
+            .collect();
+
+        let n = costs.len().max(1) as f64;
+        println!(
+            "Traversal per pixel: {:.1} node tests, {:.1} primitive tests (max {} combined).",
+            total.nodes as f64 / n,
+            total.primitives as f64 / n,
+            max
+        );
+
         self.make_png(self.filename.clone());
-        progress.finish();
-        println!("Render complete.");
+        println!("Heat map complete.");
     }
 
-    pub fn check_hits(&self, ray: &Ray) -> Option<Hit> {
-        let mut min = None;
-
-        for object in &self.objects {
-            if let Some(hit) = object.ray_intersect(ray) {
-                match min {
-                    None => min = Some(hit),
-                    Some(prev) => {
-                        if hit.t < prev.t {
-                            min = Some(hit);
-                        } else {
-                            min = Some(prev);
-                        }
+    // renders the shaded image along with depth, normals, instance ids and the
+    // camera pose as frame `index` of a dataset in `dir`, see `dataset::Frame`
+    pub fn render_dataset(&self, dir: &str, index: u32) -> std::io::Result<()> {
+        let mut frame = Frame::new(self.width as usize, self.height as usize);
+        let (_, _, back) = self.camera.basis();
+
+        frame.legend = self.class_legend();
+        let class_ids: Vec<u16> = self
+            .labels
+            .iter()
+            .map(|l| match frame.legend.binary_search(l) {
+                Ok(i) if !l.is_empty() => i as u16 + 1,
+                _ => 0,
+            })
+            .collect();
+
+        for i in (0..self.height).rev() {
+            for j in 0..self.width {
+                let color = self.antialias_color(self.settings.samples, j, i);
+                frame.rgb.push(self.encode_pixel(color));
+
+                // auxiliary channels come from a single ray through the pixel center
+                let (x, y) = self.image_plane_position(j as f64 + 0.5, i as f64 + 0.5);
+                let hit = if self.camera.covers(x, y) {
+                    self.check_hits(&self.camera.center_ray(x, y))
+                } else {
+                    None
+                };
+
+                match hit {
+                    Some(h) => {
+                        frame
+                            .depth
+                            .push((h.p - self.camera.origin()).dot(-back) as f32);
+                        frame.normal.push(h.normal);
+                        frame
+                            .instance
+                            .push((h.object + 1).min(u16::MAX as usize) as u16);
+                        frame
+                            .class
+                            .push(class_ids.get(h.object).copied().unwrap_or(0));
+                    }
+                    None => {
+                        frame.depth.push(0.0);
+                        frame.normal.push(Vector(0.0, 0.0, 0.0));
+                        frame.instance.push(0);
+                        frame.class.push(0);
                     }
                 }
             }
         }
 
-        return min;
+        frame.write(std::path::Path::new(dir), index, self.camera.as_ref())
+    }
+
+    pub fn check_hits(&self, ray: &Ray) -> Option<Hit> {
+        let hit = self.objects.ray_intersect(ray, self.t_min, f64::INFINITY);
+        self.with_ground(ray, hit)
+    }
+
+    // the ground plane's hit if it is closer than `hit`
+    fn with_ground(&self, ray: &Ray, hit: Option<Hit>) -> Option<Hit> {
+        let ground = match &self.ground {
+            Some(g) => g,
+            None => return hit,
+        };
+
+        let t_max = hit.as_ref().map_or(f64::INFINITY, |h| h.t);
+        match ground.ray_intersect(ray, self.t_min, t_max) {
+            Some(mut h) => {
+                h.object = self.objects.len();
+                Some(h)
+            }
+            None => hit,
+        }
+    }
+
+    fn is_ground(&self, hit: &Hit) -> bool {
+        self.ground.is_some() && hit.object == self.objects.len()
+    }
+
+    // true if anything blocks the ray before max_t, for shadow rays that only
+    // need to know whether a light is visible
+    pub fn check_occluded(&self, ray: &Ray, max_t: f64) -> bool {
+        self.objects.occluded(ray, self.t_min, max_t)
+            || self
+                .ground
+                .as_ref()
+                .is_some_and(|g| g.occluded(ray, self.t_min, max_t))
+    }
+
+    // fraction of light that gets through to max_t along a shadow ray: opaque
+    // surfaces block it, transparent ones (see `Scatter::transmittance`) tint it
+    fn transmittance(&self, ray: &Ray, max_t: f64) -> Vector {
+        let full = Vector(1.0, 1.0, 1.0);
+        let black = Vector(0.0, 0.0, 0.0);
+        if !self.check_occluded(ray, max_t) {
+            return full;
+        }
+        // the caustic photons carry the light glass lets through
+        if self.caustics.is_some() {
+            return black;
+        }
+
+        let mut throughput = full;
+        let mut ray = *ray;
+        let mut max_t = max_t;
+        for _ in 0..MAX_SHADOW_CROSSINGS {
+            let hit = self.objects.ray_intersect(&ray, self.t_min, max_t);
+            let hit = match self.with_ground(&ray, hit).filter(|h| h.t < max_t) {
+                Some(h) => h,
+                None => return throughput,
+            };
+
+            match hit.material.transmittance(&ray, &hit) {
+                Some(t) => throughput = throughput * t,
+                None => return black,
+            }
+            if throughput.near_zero() {
+                return black;
+            }
+
+            // carry on from the surface, the direction keeps its length
+            ray = Ray::new(hit.p, ray.direction).at_time(ray.time);
+            max_t -= hit.t;
+        }
+
+        black
+    }
+
+    // light reaching `h` straight from the `Light`s, through shadow rays
+    pub fn direct_light(&self, r: &Ray, h: &Hit) -> Vector {
+        let mut total = Vector(0.0, 0.0, 0.0);
+
+        for (i, light) in self.lights.iter().enumerate() {
+            if !self.is_linked(h.object, i) {
+                continue;
+            }
+            let sample = match light.sample(h.p) {
+                Some(s) => s,
+                None => continue,
+            };
+            let f = match h.material.eval(r, h, sample.direction) {
+                Some(f) => f,
+                // mirror-like materials can't see point-like lights
+                None => continue,
+            };
+            if f.near_zero() {
+                continue;
+            }
+
+            let shadow = Ray::new(h.p, sample.direction).at_time(r.time);
+            let visible = self.transmittance(&shadow, sample.distance);
+            if !visible.near_zero() {
+                // lights that scattered rays can also hit share the work with
+                // them, see `trace_bounce`
+                let weight = match (sample.pdf, h.material.pdf(r, h, sample.direction)) {
+                    (Some(light_pdf), Some(bsdf_pdf)) => power_heuristic(light_pdf, bsdf_pdf),
+                    _ => 1.0,
+                };
+                total = total + weight * (visible * (f * sample.irradiance));
+            }
+        }
+
+        // one sample toward the bright parts of an HDR environment, weighted
+        // against scattered rays finding the same light (see `trace_bounce`)
+        if let Some((direction, env_pdf)) = self.environment.sample() {
+            let f = h.material.eval(r, h, direction);
+            let bsdf_pdf = h.material.pdf(r, h, direction);
+            if let (Some(f), Some(bsdf_pdf)) = (f, bsdf_pdf) {
+                let shadow = Ray::new(h.p, direction).at_time(r.time);
+                if env_pdf > 0.0 && !f.near_zero() {
+                    let visible = self.transmittance(&shadow, f64::INFINITY);
+                    let weight = power_heuristic(env_pdf, bsdf_pdf) / env_pdf;
+                    total = total + weight * (visible * (f * self.environment.radiance(direction)));
+                }
+            }
+        }
+
+        total
+    }
+
+    // color seen along a ray scattered at `h`, whose closest hit is `next`. Where it reaches a light or a
+    // sampled environment that `direct_light` also sampled, only its multiple
+    // importance sampling share counts. `bsdf_pdf` is the density it was
+    // scattered with, None for mirror-like lobes. `from_diffuse` is true when
+    // only mirror-like bounces lie between `h` and the last diffuse surface
+    fn trace_bounce(
+        &self,
+        h: &Hit,
+        scattered: Ray,
+        bsdf_pdf: Option<f64>,
+        next: Option<Hit>,
+        depth: i32,
+        from_diffuse: bool,
+    ) -> Vector {
+        let direction = scattered.direction.to_unit_vector();
+
+        // light reaching a diffuse surface through glass or mirrors is already
+        // in the caustic photons
+        let caustic = self.caustics.is_some() && from_diffuse && bsdf_pdf.is_none();
+
+        // the nearest light in front of the next surface
+        let surface_distance = next
+            .as_ref()
+            .map_or(f64::INFINITY, |n| n.t * scattered.direction.length());
+        let light = self
+            .lights
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.is_linked(h.object, i))
+            .filter_map(|(_, l)| l.hit(h.p, direction))
+            .filter(|&(distance, _, _)| distance < surface_distance)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        if let Some((_, radiance, light_pdf)) = light {
+            if caustic {
+                return Vector(0.0, 0.0, 0.0);
+            }
+            // mirror-like materials never sampled the light directly
+            let weight = bsdf_pdf.map_or(1.0, |p| power_heuristic(p, light_pdf));
+            return weight * radiance;
+        }
+
+        // everything but the lights is indirect light, which baked occlusion darkens
+        let occlusion = h.material.occlusion(h);
+        if next.is_none() {
+            if caustic {
+                return Vector(0.0, 0.0, 0.0);
+            }
+            let env_pdf = self.environment.pdf(direction);
+            if let (Some(env_pdf), Some(bsdf_pdf)) = (env_pdf, bsdf_pdf) {
+                return (occlusion * power_heuristic(bsdf_pdf, env_pdf))
+                    * self.environment.radiance(direction);
+            }
+        }
+
+        let bounced = self.shade_path(scattered, next, depth, from_diffuse || bsdf_pdf.is_some());
+        occlusion * clamp_radiance(bounced, self.settings.clamp_indirect)
+    }
+
+    // the environment behind a shadow catcher, dimmed by how much of the sky the
+    // scene hides from the hit point (one cosine-weighted visibility ray)
+    fn catch_shadow(&self, r: Ray, h: &Hit) -> Vector {
+        let mut direction = h.normal + utils::random_unit_vector();
+        if direction.near_zero() {
+            direction = h.normal;
+        }
+
+        let probe = Ray::new(h.p, direction).at_time(r.time);
+        if self.objects.occluded(&probe, self.t_min, f64::INFINITY) {
+            Vector(0.0, 0.0, 0.0)
+        } else {
+            self.environment.radiance(r.direction)
+        }
     }
 
     pub fn color_model(&self, r: Ray, depth: i32) -> Vector {
         let obj_hit = self.check_hits(&r);
+        self.shade(r, obj_hit, depth)
+    }
+
+    // color seen along `r` given its (possibly already traced) closest hit,
+    // path traced. See `integrator::PathTracer`
+    pub fn shade(&self, r: Ray, obj_hit: Option<Hit>, depth: i32) -> Vector {
+        self.shade_path(r, obj_hit, depth, false)
+    }
+
+    // `shade` partway down a path, see `trace_bounce` for `from_diffuse`
+    fn shade_path(&self, r: Ray, obj_hit: Option<Hit>, depth: i32, from_diffuse: bool) -> Vector {
         let color: Vector;
 
         match obj_hit {
+            Some(h) if self.shadow_catcher && self.is_ground(&h) => self.catch_shadow(r, &h),
             Some(h) => {
-                if crate::REFLECTION_DEPTH > depth {
-                    if let Some((scattered, attenuation)) = h.material.scatter(&r, &h) {
-                        color = attenuation * self.color_model(scattered, depth - 1)
+                // light given off by the surface itself, then whatever it reflects
+                // of the lights and, through scattering, of everything else
+                let emitted = h.material.emitted(&r, &h);
+                let direct = self.direct_light(&r, &h);
+                #[cfg(feature = "debug-nan")]
+                {
+                    crate::nan::check("emission", emitted, &r, &h, depth);
+                    crate::nan::check("direct light", direct, &r, &h, depth);
+                }
+                let emitted = emitted + direct + self.caustic_light(&r, &h);
+
+                if self.settings.max_depth > depth {
+                    // diffuse surfaces first seen from the camera
+                    if !from_diffuse {
+                        if let Some(indirect) = self.cached_indirect(&h) {
+                            return emitted + indirect;
+                        }
+                    }
+                    if let Some((scattered, attenuation, specular)) =
+                        h.material.scatter_lobe(&r, &h)
+                    {
+                        // the whole path happens at the same moment
+                        let scattered = scattered.at_time(r.time);
+                        let bsdf_pdf = if specular {
+                            None
+                        } else {
+                            h.material.pdf(&r, &h, scattered.direction.to_unit_vector())
+                        };
+                        #[cfg(feature = "debug-nan")]
+                        {
+                            crate::nan::check("attenuation", attenuation, &r, &h, depth);
+                            crate::nan::check("direction", scattered.direction, &r, &h, depth);
+                        }
+                        // the scattered ray's weight averages to the surface's albedo,
+                        // so one ambient lookup along it shades like a fill light
+                        let next = self.check_hits(&scattered);
+                        let incoming = self.trace_bounce(
+                            &h,
+                            scattered,
+                            bsdf_pdf,
+                            next,
+                            depth + 1,
+                            from_diffuse,
+                        ) + h.material.occlusion(&h)
+                            * self.ambient_radiance(scattered.direction);
+                        color = emitted + attenuation * incoming
                     } else {
-                        color = Vector(0.0, 0.0, 0.0)
+                        color = emitted
                     }
                 } else {
-                    color = Vector(0.0, 0.0, 0.0);
+                    color = emitted;
                 }
 
                 // color with normals
                 // color = 0.5 * (h.normal + Vector(1.0, 1.0, 1.0));
 
-                return color;
+                color
             }
-            None => {
-                let unit_direction = r.direction.to_unit_vector();
-                let t = 0.5 * (unit_direction.y() + 1.0);
-                let color = (1.0 - t) * Vector(1.0, 1.0, 1.0) + t * crate::BACKGROUND_COLOR;
+            None => self.environment.radiance(r.direction),
+        }
+    }
+
+    pub fn antialias_color(&self, n_samples: i32, pixel_x: i32, pixel_y: i32) -> Vector {
+        #[cfg(feature = "debug-nan")]
+        crate::nan::set_pixel(pixel_x, pixel_y);
+
+        let pixel = (pixel_x, pixel_y);
+        let scramble = utils::random_u32();
+        let pass = n_samples.max(1) as u32;
+        let budget = if self.settings.noise_threshold > 0.0 {
+            (self.settings.max_samples as u32).max(pass)
+        } else {
+            pass
+        };
+        // later passes continue the sampler's sequence instead of repeating
+        // it, which deterministic ones such as `BlueNoiseSampler` would
+        let first = self.framebuffer.passes() * budget;
 
-                return color;
+        let mut total = Vector(0.0, 0.0, 0.0);
+        // sum and squared sum of the samples' luminance
+        let (mut sum, mut sum_sq) = (0.0, 0.0);
+        let mut taken = 0;
+        while taken < budget {
+            let n = pass.min(budget - taken);
+            for color in self.sample_pass(pixel, scramble, first + taken..first + taken + n, pass) {
+                let l = color.luminance();
+                total = total + color;
+                sum += l;
+                sum_sq += l * l;
+            }
+            taken += n;
+
+            if taken >= budget || taken < 2 {
+                continue;
+            }
+            let mean = sum / taken as f64;
+            let variance = (sum_sq - sum * mean).max(0.0) / (taken - 1) as f64;
+            let error = (variance / taken as f64).sqrt();
+            if error <= self.settings.noise_threshold * mean.sqrt() {
+                break;
             }
         }
+
+        utils::end_sample();
+        stats::count_samples(taken);
+        total / taken as f64
     }
 
-    pub fn antialias_color(&self, n_samples: i32, pixel_x: i32, pixel_y: i32) -> Vector {
-        let mut aa_color = Vector(0.0, 0.0, 0.0);
-        for _ in 0..n_samples {
-            let mut rng = rand::thread_rng();
-            let random_u: f64 = rng.gen();
-            let random_v: f64 = rng.gen();
+    // colors of the samples `indices` of a pixel, taken `count` at a time;
+    // samples the camera can't see are black
+    fn sample_pass(
+        &self,
+        pixel: (i32, i32),
+        scramble: u32,
+        indices: std::ops::Range<u32>,
+        count: u32,
+    ) -> Vec<Vector> {
+        let mut colors = vec![Vector(0.0, 0.0, 0.0); indices.len()];
+        let first = indices.start;
+
+        // each ray with the index of the sample it belongs to
+        let mut rays = Vec::with_capacity(indices.len());
+        for index in indices {
+            utils::begin_sample(&self.sampler, pixel, scramble, index, count, 0);
+            let u = utils::random_in_range(0.0, 1.0);
+            let v = utils::random_in_range(0.0, 1.0);
+
+            let (x, y) = self.image_plane_position(pixel.0 as f64 + u, pixel.1 as f64 + v);
+            if self.camera.covers(x, y) {
+                rays.push((index, self.camera.generate_ray(x, y)));
+            }
+        }
 
-            let x = (pixel_x as f64 + random_u) / ((self.width - 1) as f64);
-            let y = (pixel_y as f64 + random_v) / ((self.height - 1) as f64);
-            let (origin, direction) = self.camera.get_pixel_direction(x, y);
-            let ray = get_ray(origin, direction);
-            let c = self.color_model(ray, 0);
+        // primary rays of one pixel are coherent, so trace them as packets
+        let mut packets = rays.chunks_exact(4);
+        for chunk in &mut packets {
+            let packet = RayPacket::new([chunk[0].1, chunk[1].1, chunk[2].1, chunk[3].1]);
+            let hits = self
+                .objects
+                .intersect_packet(&packet, self.t_min, f64::INFINITY);
 
-            aa_color = aa_color + (1.0 / n_samples as f64) * c;
+            for ((index, ray), hit) in chunk.iter().zip(hits) {
+                let hit = self.with_ground(ray, hit);
+                utils::begin_sample(
+                    &self.sampler,
+                    pixel,
+                    scramble,
+                    *index,
+                    count,
+                    CAMERA_DIMENSIONS,
+                );
+                colors[(index - first) as usize] = self.integrator.li(self, *ray, hit);
+            }
         }
-        return aa_color;
+
+        for (index, ray) in packets.remainder() {
+            let hit = self.check_hits(ray);
+            utils::begin_sample(
+                &self.sampler,
+                pixel,
+                scramble,
+                *index,
+                count,
+                CAMERA_DIMENSIONS,
+            );
+            colors[(index - first) as usize] = self.integrator.li(self, *ray, hit);
+        }
+
+        colors
     }
 
     pub fn make_png(&self, fname: String) -> bool {
-        let filename = fname.clone();
+        let mut settings = vec![
+            ("width".to_string(), self.width.to_string()),
+            ("height".to_string(), self.height.to_string()),
+            ("samples".to_string(), self.settings.samples.to_string()),
+            ("max-depth".to_string(), self.settings.max_depth.to_string()),
+        ];
+        if self.framebuffer.passes() > 1 {
+            settings.push(("passes".to_string(), self.framebuffer.passes().to_string()));
+        }
+        if self.settings.clamp_indirect > 0.0 {
+            settings.push((
+                "clamp-indirect".to_string(),
+                self.settings.clamp_indirect.to_string(),
+            ));
+        }
+        if self.settings.noise_threshold > 0.0 {
+            settings.push((
+                "noise-threshold".to_string(),
+                self.settings.noise_threshold.to_string(),
+            ));
+            settings.push((
+                "max-samples".to_string(),
+                self.settings.max_samples.to_string(),
+            ));
+        }
+        settings.extend(self.metadata.iter().cloned());
 
-        match lodepng::encode24_file(
-            fname,
+        match metadata::write_png(
+            &fname,
             &self.pixels,
             self.width as usize,
             self.height as usize,
+            &settings,
         ) {
             Ok(()) => true,
             Err(err) => {
-                println!("Error writing file \"{}\": {}", filename, err);
+                println!("Error writing file \"{}\": {}", fname, err);
                 false
             }
         }
     }
 }
+
+// multiple importance sampling weight of a sample drawn with density `pdf`
+// when `other` could also have produced it
+fn power_heuristic(pdf: f64, other: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other * other);
+    if a + b > 0.0 {
+        a / (a + b)
+    } else {
+        0.0
+    }
+}
+
+// `color` scaled down so no channel exceeds `limit`, keeping its hue; a
+// limit of zero leaves it alone
+fn clamp_radiance(color: Vector, limit: f64) -> Vector {
+    let brightest = color.x().max(color.y()).max(color.z());
+    if limit > 0.0 && brightest > limit {
+        (limit / brightest) * color
+    } else {
+        color
+    }
+}
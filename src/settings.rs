@@ -0,0 +1,33 @@
+// quality knobs of a render, changeable at run time. Resolution lives on
+// `Scene` (see `set_resolution`) and the background on its `Environment`
+#[derive(Clone, Copy, Debug)]
+pub struct RenderSettings {
+    // antialiasing samples per pixel, the minimum with adaptive sampling
+    pub samples: i32,
+    // bounces a path takes before it is cut off
+    pub max_depth: i32,
+    // above zero, pixels keep taking another `samples` while the standard
+    // error of their brightness is above this fraction of its square root
+    // (roughly what survives the output curve), so flat sky settles early
+    // while penumbrae and caustics get the effort. Zero samples uniformly
+    pub noise_threshold: f64,
+    // most samples an adaptive pixel takes
+    pub max_samples: i32,
+    // above zero, light that reaches a surface off other surfaces is scaled
+    // down until no channel exceeds this (path tracer only), so rare bright
+    // paths can't leave fireflies in a converged image, at the cost of
+    // dimming strong bounced highlights. Zero keeps the render unbiased
+    pub clamp_indirect: f64,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            samples: 300,
+            max_depth: 100,
+            noise_threshold: 0.0,
+            max_samples: 1200,
+            clamp_indirect: 0.0,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::f64::consts::PI;
+
+use crate::vector::Vector;
+
+// Preetham et al. "A Practical Analytic Model for Daylight": clear sky radiance
+// from the sun position and the atmosphere's turbidity, no HDR file needed.
+// The sun disc itself is left out; pair the sky with a `Light::Directional`
+pub struct Sky {
+    // unit vector toward the sun
+    sun: Vector,
+    // zenith angle of the sun
+    theta_s: f64,
+    // Perez coefficients A..E for luminance Y and chromaticities x, y
+    perez: [[f64; 5]; 3],
+    // zenith values of Y, x and y
+    zenith: [f64; 3],
+    // Y at 10 kcd/m^2 becomes radiance `strength`
+    strength: f64,
+}
+
+// Perez et al. sky distribution relative to the zenith
+fn perez(c: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    (1.0 + c[0] * (c[1] / cos_theta).exp())
+        * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * gamma.cos().powi(2))
+}
+
+impl Sky {
+    // turbidity runs from about 2 (very clear) to 10 (hazy); the model only
+    // covers the sun above the horizon, lower suns are clamped to it
+    pub fn new(sun_direction: Vector, turbidity: f64, strength: f64) -> Self {
+        let sun = sun_direction.to_unit_vector();
+        let theta_s = sun.y().clamp(0.01, 1.0).acos();
+        let t = turbidity;
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_y = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let (s, s2, s3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x = t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886);
+        let zenith_y_chroma = t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688);
+
+        Self {
+            sun,
+            theta_s,
+            perez,
+            zenith: [zenith_y, zenith_x, zenith_y_chroma],
+            strength,
+        }
+    }
+
+    // linear sRGB radiance toward `direction`; below the horizon the horizon
+    // is repeated at half brightness as a stand-in for the ground
+    pub fn radiance(&self, direction: Vector) -> Vector {
+        let d = direction.to_unit_vector();
+        let (cos_theta, ground) = if d.y() > 0.0 {
+            (d.y().max(0.01), 1.0)
+        } else {
+            (0.01, 0.5)
+        };
+        let horizontal = Vector(d.x(), cos_theta, d.z()).to_unit_vector();
+        let gamma = horizontal.dot(self.sun).clamp(-1.0, 1.0).acos();
+
+        let mut values = [0.0; 3];
+        for (i, value) in values.iter_mut().enumerate() {
+            let c = &self.perez[i];
+            *value = self.zenith[i] * perez(c, cos_theta, gamma) / perez(c, 1.0, self.theta_s);
+        }
+        let [luminance, x, y] = values;
+
+        // xyY to XYZ to linear sRGB
+        let luminance = 0.1 * self.strength * ground * luminance.max(0.0);
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+        Vector(
+            3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+            -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+            0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+        )
+    }
+}
+
+// where the sun is for a place and local time, for daylight studies; feed
+// `direction()` to `Sky::new` and (negated) to `Light::directional`. Uses the
+// NOAA approximation, good to a fraction of a degree. World axes are +y up,
+// -z north and +x east
+pub struct SunPosition {
+    // degrees, north and east positive
+    pub latitude: f64,
+    pub longitude: f64,
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    // local clock time in hours, e.g. 14.5 for half past two
+    pub hour: f64,
+    // hours the local clock is ahead of UTC, e.g. -5 for New York in winter
+    pub utc_offset: f64,
+}
+
+impl SunPosition {
+    fn day_of_year(&self) -> u32 {
+        let leap = (self.year % 4 == 0 && self.year % 100 != 0) || self.year % 400 == 0;
+        let days = [
+            31,
+            if leap { 29 } else { 28 },
+            31,
+            30,
+            31,
+            30,
+            31,
+            31,
+            30,
+            31,
+            30,
+            31,
+        ];
+        let month = (self.month.clamp(1, 12) - 1) as usize;
+        days[..month].iter().sum::<u32>() + self.day
+    }
+
+    // unit vector toward the sun, pointing below the horizon at night
+    pub fn direction(&self) -> Vector {
+        let utc_hour = self.hour - self.utc_offset;
+        let gamma = 2.0 * PI / 365.0 * (self.day_of_year() as f64 - 1.0 + (utc_hour - 12.0) / 24.0);
+
+        // equation of time in minutes and solar declination in radians
+        let eqtime = 229.18
+            * (0.000075 + 0.001868 * gamma.cos()
+                - 0.032077 * gamma.sin()
+                - 0.014615 * (2.0 * gamma).cos()
+                - 0.040849 * (2.0 * gamma).sin());
+        let decl = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+            - 0.006758 * (2.0 * gamma).cos()
+            + 0.000907 * (2.0 * gamma).sin()
+            - 0.002697 * (3.0 * gamma).cos()
+            + 0.00148 * (3.0 * gamma).sin();
+
+        let solar_minutes = utc_hour * 60.0 + eqtime + 4.0 * self.longitude;
+        let hour_angle = (solar_minutes / 4.0 - 180.0).to_radians();
+        let lat = self.latitude.to_radians();
+
+        let east = -decl.cos() * hour_angle.sin();
+        let north = lat.cos() * decl.sin() - lat.sin() * decl.cos() * hour_angle.cos();
+        let up = lat.sin() * decl.sin() + lat.cos() * decl.cos() * hour_angle.cos();
+        Vector(east, up, -north).to_unit_vector()
+    }
+}
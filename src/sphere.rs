@@ -1,20 +1,17 @@
 use std::sync::Arc;
 
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
 use crate::material::Scatter;
 use crate::ray::Ray;
 use crate::vector::Vector;
 
-pub struct Hit {
-    pub t: f64,
-    pub p: Vector,
-    pub normal: Vector,
-    pub material: Arc<dyn Scatter>,
-}
-
 pub struct Sphere {
     pub center: Vector,
     pub radius: f64,
     pub material: Arc<dyn Scatter>,
+    // ignore hits from inside the sphere (backface culling for thin shells)
+    pub one_sided: bool,
 }
 
 impl Sphere {
@@ -23,10 +20,43 @@ impl Sphere {
             center: Vector(pos.x(), pos.y(), pos.z()),
             radius: r,
             material: m,
+            one_sided: false,
         }
     }
+}
+
+// latitude/longitude coordinates of a point on the unit sphere: u runs around the
+// y axis starting at -x, v from the bottom pole (0) to the top (1)
+pub fn sphere_uv(p: Vector) -> (f64, f64) {
+    let theta = (-p.y()).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z()).atan2(p.x()) + std::f64::consts::PI;
+
+    (
+        phi / (2.0 * std::f64::consts::PI),
+        theta / std::f64::consts::PI,
+    )
+}
 
-    pub fn ray_intersect(&self, r: &Ray) -> Option<Hit> {
+// partial derivatives of the point with unit direction p from the center along
+// the `sphere_uv` coordinates
+fn sphere_derivatives(p: Vector, radius: f64) -> (Vector, Vector) {
+    let pi = std::f64::consts::PI;
+    let sin_theta = (p.x() * p.x() + p.z() * p.z()).sqrt().max(1.0e-6);
+
+    let dpdu = 2.0 * pi * radius * Vector(p.z(), 0.0, -p.x());
+    let dpdv = pi
+        * radius
+        * Vector(
+            -p.x() * p.y() / sin_theta,
+            sin_theta,
+            -p.y() * p.z() / sin_theta,
+        );
+
+    (dpdu, dpdv)
+}
+
+impl Hittable for Sphere {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
         let oc = r.origin - self.center;
         let a = r.direction.dot(r.direction);
         let b = oc.dot(r.direction);
@@ -35,31 +65,53 @@ impl Sphere {
 
         if discriminant < 0.0 {
             return None;
-        } else {
-            let t = (-b - discriminant.sqrt()) / a;
-
-            if t > 0.0003 {
-                let intersection = r.line_to_p(t);
-
-                let normal = (intersection - self.center).to_unit_vector();
-                let mut outward_normal = normal;
-                let front_face = r.direction.dot(outward_normal) < 0.0;
-
-                if front_face {
-                    outward_normal = outward_normal;
-                } else {
-                    outward_normal = -outward_normal;
-                }
-
-                return Some(Hit {
-                    t: t,
-                    p: intersection,
-                    normal: outward_normal,
-                    material: self.material.clone(),
-                });
-            } else {
-                return None;
+        }
+
+        // try the near root first, then the far one for rays starting inside the sphere
+        let sqrt_d = discriminant.sqrt();
+        for t in [(-b - sqrt_d) / a, (-b + sqrt_d) / a] {
+            if t <= t_min || t >= t_max {
+                continue;
+            }
+
+            let intersection = r.line_to_p(t);
+
+            let outward_normal = (intersection - self.center).to_unit_vector();
+            let front_face = r.direction.dot(outward_normal) < 0.0;
+
+            if self.one_sided && !front_face {
+                continue;
             }
+
+            let normal = if front_face {
+                outward_normal
+            } else {
+                -outward_normal
+            };
+
+            let (u, v) = sphere_uv(outward_normal);
+            let (dpdu, dpdv) = sphere_derivatives(outward_normal, self.radius);
+
+            return Some(Hit {
+                t,
+                p: intersection,
+                local: intersection,
+                normal,
+                front_face,
+                material: self.material.clone(),
+                u,
+                v,
+                dpdu,
+                dpdv,
+                object: 0,
+            });
         }
+
+        None
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = Vector(self.radius.abs(), self.radius.abs(), self.radius.abs());
+        Aabb::new(self.center - r, self.center + r)
     }
 }
@@ -0,0 +1,79 @@
+use std::cell::Cell;
+
+use crate::vector::Vector;
+
+// per-thread counters bumped by the accelerators during traversal, and by
+// the scene for each camera sample
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static NODE_TESTS: Cell<u64> = const { Cell::new(0) };
+    static PRIMITIVE_TESTS: Cell<u64> = const { Cell::new(0) };
+    static CAMERA_SAMPLES: Cell<u64> = const { Cell::new(0) };
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraversalStats {
+    // bounding box / cell visits
+    pub nodes: u64,
+    // ray-primitive intersection tests
+    pub primitives: u64,
+}
+
+impl TraversalStats {
+    pub fn total(&self) -> u64 {
+        self.nodes + self.primitives
+    }
+}
+
+// whether traversals on this thread count their tests, off unless a traversal
+// heatmap is being rendered. Accelerators check it once per traversal
+pub fn counting() -> bool {
+    COUNTING.with(|c| c.get())
+}
+
+pub fn set_counting(on: bool) {
+    COUNTING.with(|c| c.set(on));
+}
+
+pub fn count_node() {
+    NODE_TESTS.with(|c| c.set(c.get() + 1));
+}
+
+pub fn count_primitives(n: usize) {
+    PRIMITIVE_TESTS.with(|c| c.set(c.get() + n as u64));
+}
+
+pub fn count_samples(n: u32) {
+    CAMERA_SAMPLES.with(|c| c.set(c.get() + n as u64));
+}
+
+// camera samples taken since the last call, resets the count
+pub fn take_samples() -> u64 {
+    CAMERA_SAMPLES.with(|c| c.replace(0))
+}
+
+// returns the counts since the last call and resets them
+pub fn take() -> TraversalStats {
+    TraversalStats {
+        nodes: NODE_TESTS.with(|c| c.replace(0)),
+        primitives: PRIMITIVE_TESTS.with(|c| c.replace(0)),
+    }
+}
+
+// false color for a cost in [0, 1]: blue (cheap) through green to red (expensive)
+pub fn heat_color(t: f64) -> Vector {
+    let stops = [
+        Vector(0.0, 0.0, 0.5),
+        Vector(0.0, 0.0, 1.0),
+        Vector(0.0, 1.0, 1.0),
+        Vector(0.0, 1.0, 0.0),
+        Vector(1.0, 1.0, 0.0),
+        Vector(1.0, 0.0, 0.0),
+    ];
+
+    let x = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let i = (x as usize).min(stops.len() - 2);
+    let f = x - i as f64;
+
+    (1.0 - f) * stops[i] + f * stops[i + 1]
+}
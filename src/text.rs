@@ -0,0 +1,297 @@
+use std::fs;
+use std::io;
+use std::sync::Arc;
+
+use ttf_parser::{Face, OutlineBuilder};
+
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::hittable::{Hit, Hittable};
+use crate::material::Scatter;
+use crate::ray::Ray;
+use crate::triangle::Triangle;
+use crate::vector::Vector;
+
+// straight segments each quadratic or cubic outline curve is flattened into
+const CURVE_STEPS: usize = 8;
+
+// extrudes `string` set in the TrueType/OpenType font at `font_path` into solid
+// letters: the baseline runs along +x from the origin, glyphs are `size` units
+// per em tall and `depth` thick, centered on z = 0 with the front facing +z
+pub fn text(
+    font_path: &str,
+    string: &str,
+    size: f64,
+    depth: f64,
+    material: Arc<dyn Scatter>,
+) -> io::Result<Bvh> {
+    let data = fs::read(font_path)?;
+    let face = Face::parse(&data, 0).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("could not parse font \"{}\": {}", font_path, err),
+        )
+    })?;
+
+    let scale = size / face.units_per_em() as f64;
+    let (front, back) = (0.5 * depth, -0.5 * depth);
+    let mut parts: Vec<Box<dyn Hittable>> = Vec::new();
+    let mut pen = 0.0;
+
+    for c in string.chars() {
+        let id = match face.glyph_index(c) {
+            Some(id) => id,
+            None => continue,
+        };
+
+        let mut outline = Flattener {
+            contours: Vec::new(),
+            scale,
+            offset: pen,
+        };
+
+        if face.outline_glyph(id, &mut outline).is_some() {
+            let contours: Vec<Vec<(f64, f64)>> = outline
+                .contours
+                .into_iter()
+                .filter(|c| c.len() >= 3)
+                .collect();
+
+            extrude_walls(&contours, back, front, &material, &mut parts);
+            parts.push(Box::new(Cap::new(
+                contours.clone(),
+                front,
+                1.0,
+                material.clone(),
+            )));
+            parts.push(Box::new(Cap::new(contours, back, -1.0, material.clone())));
+        }
+
+        pen += face.glyph_hor_advance(id).unwrap_or(0) as f64 * scale;
+    }
+
+    Ok(Bvh::new(parts))
+}
+
+// side walls of the extrusion, two triangles per outline edge
+fn extrude_walls(
+    contours: &[Vec<(f64, f64)>],
+    z0: f64,
+    z1: f64,
+    material: &Arc<dyn Scatter>,
+    parts: &mut Vec<Box<dyn Hittable>>,
+) {
+    // the largest contour is an outer one, clockwise in TrueType outlines and
+    // counterclockwise in CFF ones. Holes run the other way, so either way the
+    // solid lies on the same side of every edge
+    let counterclockwise = contours
+        .iter()
+        .map(|c| signed_area(c))
+        .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .is_some_and(|area| area > 0.0);
+
+    for contour in contours {
+        for i in 0..contour.len() {
+            let (mut ax, mut ay) = contour[i];
+            let (mut bx, mut by) = contour[(i + 1) % contour.len()];
+            if counterclockwise {
+                std::mem::swap(&mut ax, &mut bx);
+                std::mem::swap(&mut ay, &mut by);
+            }
+            if ax == bx && ay == by {
+                continue;
+            }
+
+            let (a0, a1) = (Vector(ax, ay, z0), Vector(ax, ay, z1));
+            let (b0, b1) = (Vector(bx, by, z0), Vector(bx, by, z1));
+
+            // wound so the face normal points away from the solid, taken as
+            // on the right of a -> b
+            parts.push(Box::new(Triangle::new(a0, b1, b0, material.clone())));
+            parts.push(Box::new(Triangle::new(a0, a1, b1, material.clone())));
+        }
+    }
+}
+
+// shoelace area of a closed polyline, positive when it runs counterclockwise
+fn signed_area(contour: &[(f64, f64)]) -> f64 {
+    let mut twice = 0.0;
+    for i in 0..contour.len() {
+        let (ax, ay) = contour[i];
+        let (bx, by) = contour[(i + 1) % contour.len()];
+        twice += ax * by - bx * ay;
+    }
+    0.5 * twice
+}
+
+// collects glyph outlines as closed polylines in scene units
+struct Flattener {
+    contours: Vec<Vec<(f64, f64)>>,
+    scale: f64,
+    offset: f64,
+}
+
+impl Flattener {
+    fn point(&self, x: f32, y: f32) -> (f64, f64) {
+        (self.offset + x as f64 * self.scale, y as f64 * self.scale)
+    }
+
+    fn last(&self) -> (f64, f64) {
+        self.contours
+            .last()
+            .and_then(|c| c.last())
+            .copied()
+            .unwrap_or((self.offset, 0.0))
+    }
+
+    fn push(&mut self, p: (f64, f64)) {
+        if let Some(contour) = self.contours.last_mut() {
+            contour.push(p);
+        }
+    }
+}
+
+impl OutlineBuilder for Flattener {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.contours.push(vec![p]);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.push(p);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p0, c, p1) = (self.last(), self.point(x1, y1), self.point(x, y));
+
+        for s in 1..=CURVE_STEPS {
+            let t = s as f64 / CURVE_STEPS as f64;
+            let u = 1.0 - t;
+            self.push((
+                u * u * p0.0 + 2.0 * u * t * c.0 + t * t * p1.0,
+                u * u * p0.1 + 2.0 * u * t * c.1 + t * t * p1.1,
+            ));
+        }
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let p0 = self.last();
+        let (c0, c1, p1) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+
+        for s in 1..=CURVE_STEPS {
+            let t = s as f64 / CURVE_STEPS as f64;
+            let u = 1.0 - t;
+            let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+            self.push((
+                w0 * p0.0 + w1 * c0.0 + w2 * c1.0 + w3 * p1.0,
+                w0 * p0.1 + w1 * c0.1 + w2 * c1.1 + w3 * p1.1,
+            ));
+        }
+    }
+
+    fn close(&mut self) {
+        // contours are treated as closed, drop a repeated start point
+        if let Some(contour) = self.contours.last_mut() {
+            if contour.len() > 1 && contour.first() == contour.last() {
+                contour.pop();
+            }
+        }
+    }
+}
+
+// flat face of a glyph in the plane z = const, filled by the even-odd rule so
+// holes (the inside of an "o") need no triangulation
+struct Cap {
+    contours: Vec<Vec<(f64, f64)>>,
+    z: f64,
+    // +1 faces +z, -1 faces -z
+    facing: f64,
+    bbox: Aabb,
+    material: Arc<dyn Scatter>,
+}
+
+impl Cap {
+    fn new(
+        contours: Vec<Vec<(f64, f64)>>,
+        z: f64,
+        facing: f64,
+        material: Arc<dyn Scatter>,
+    ) -> Self {
+        let mut bbox = Aabb::empty();
+        for &(x, y) in contours.iter().flatten() {
+            bbox = bbox.grow(Vector(x, y, z));
+        }
+        let pad = Vector(0.0, 0.0, 1.0e-6);
+
+        Self {
+            contours,
+            z,
+            facing,
+            bbox: Aabb::new(bbox.min - pad, bbox.max + pad),
+            material,
+        }
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let mut inside = false;
+
+        for contour in &self.contours {
+            let mut j = contour.len() - 1;
+            for i in 0..contour.len() {
+                let (xi, yi) = contour[i];
+                let (xj, yj) = contour[j];
+                if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                    inside = !inside;
+                }
+                j = i;
+            }
+        }
+
+        inside
+    }
+}
+
+impl Hittable for Cap {
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        if r.direction.z() == 0.0 {
+            return None;
+        }
+
+        let t = (self.z - r.origin.z()) / r.direction.z();
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let p = r.line_to_p(t);
+        if !self.contains(p.x(), p.y()) {
+            return None;
+        }
+
+        let outward_normal = Vector(0.0, 0.0, self.facing);
+        let front_face = r.direction.dot(outward_normal) < 0.0;
+
+        Some(Hit {
+            t,
+            p,
+            local: p,
+            normal: if front_face {
+                outward_normal
+            } else {
+                -outward_normal
+            },
+            front_face,
+            material: self.material.clone(),
+            // planar over the glyph outline's bounds
+            u: (p.x() - self.bbox.min.x()) / (self.bbox.max.x() - self.bbox.min.x()),
+            v: (p.y() - self.bbox.min.y()) / (self.bbox.max.y() - self.bbox.min.y()),
+            dpdu: Vector(self.bbox.max.x() - self.bbox.min.x(), 0.0, 0.0),
+            dpdv: Vector(0.0, self.bbox.max.y() - self.bbox.min.y(), 0.0),
+            object: 0,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,712 @@
+use std::io;
+use std::sync::Arc;
+
+use crate::color::ColorSpace;
+use crate::hittable::Hit;
+use crate::perlin::Perlin;
+use crate::vector::Vector;
+
+// spatially varying material input, looked up by surface (u, v) coordinates and
+// the hit point. Scalar inputs such as roughness read the first channel
+// unless the material lets a `Channel` be chosen.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector;
+
+    // lookup with everything known about the point, for textures that need
+    // more than (u, v, p) and for wrappers, which pass it on; `value` by default
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        self.value(at.u, at.v, at.p)
+    }
+
+    // `sample` at a surface hit, how materials read their inputs
+    fn value_at(&self, hit: &Hit) -> Vector {
+        self.sample(&TexturePoint::from_hit(hit))
+    }
+}
+
+// which part of a texture's color a scalar input reads, packed PBR maps keep
+// e.g. occlusion, roughness and metallic in red, green and blue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Luminance,
+}
+
+impl Channel {
+    pub fn pick(self, color: Vector) -> f64 {
+        match self {
+            Channel::Red => color.x(),
+            Channel::Green => color.y(),
+            Channel::Blue => color.z(),
+            Channel::Luminance => color.luminance(),
+        }
+    }
+}
+
+// where a texture is looked up: surface coordinates, the point in world and in
+// object space and the surface normal
+#[derive(Clone, Copy, Debug)]
+pub struct TexturePoint {
+    pub u: f64,
+    pub v: f64,
+    pub p: Vector,
+    // see `Hit::local`
+    pub local: Vector,
+    // zero when unknown
+    pub normal: Vector,
+}
+
+impl TexturePoint {
+    // a lookup by (u, v, p) alone, with p standing in for the object space point
+    pub fn new(u: f64, v: f64, p: Vector) -> Self {
+        Self {
+            u,
+            v,
+            p,
+            local: p,
+            normal: Vector(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn from_hit(hit: &Hit) -> Self {
+        Self {
+            u: hit.u,
+            v: hit.v,
+            p: hit.p,
+            local: hit.local,
+            normal: hit.normal,
+        }
+    }
+}
+
+// coordinates a pattern is laid out in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureSpace {
+    // the hit point in the scene, the pattern stays put when objects move
+    World,
+    // the hit point before `Instance` transforms, the pattern moves and scales
+    // with its object
+    Object,
+    // the surface's (u, v) as (u, v, 0), the pattern follows the mapping
+    Uv,
+}
+
+impl TextureSpace {
+    pub fn point(self, at: &TexturePoint) -> Vector {
+        match self {
+            TextureSpace::World => at.p,
+            TextureSpace::Object => at.local,
+            TextureSpace::Uv => Vector(at.u, at.v, 0.0),
+        }
+    }
+}
+
+// the same value everywhere, what plain colors and constants become
+pub struct SolidColor {
+    color: Vector,
+}
+
+impl SolidColor {
+    pub fn new(color: Vector) -> Self {
+        Self { color }
+    }
+
+    pub fn scalar(value: f64) -> Self {
+        Self::new(Vector(value, value, value))
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: Vector) -> Vector {
+        self.color
+    }
+}
+
+// lets a plain color go wherever a texture is expected, via `.into()`
+impl From<Vector> for Arc<dyn Texture> {
+    fn from(color: Vector) -> Self {
+        Arc::new(SolidColor::new(color))
+    }
+}
+
+// alternates between two textures in a checkerboard pattern
+pub struct Checker {
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+    // squares per unit of u/v, or per unit of space for the solid variant
+    scale: f64,
+    // squares in (u, v), or cubes in world or object space
+    space: TextureSpace,
+}
+
+impl Checker {
+    // squares laid out over the surface's (u, v)
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, scale: f64) -> Self {
+        Self {
+            even,
+            odd,
+            scale,
+            space: TextureSpace::Uv,
+        }
+    }
+
+    // cubes of size 1 / scale filling world space, handy for ground planes
+    pub fn solid(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, scale: f64) -> Self {
+        Self {
+            even,
+            odd,
+            scale,
+            space: TextureSpace::World,
+        }
+    }
+
+    pub fn set_space(&mut self, space: TextureSpace) {
+        self.space = space;
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        let cell = |x: f64| (x * self.scale).floor() as i64;
+        let p = self.space.point(at);
+        let parity = cell(p.x()) + cell(p.y()) + cell(p.z());
+
+        if parity.rem_euclid(2) == 0 {
+            self.even.sample(at)
+        } else {
+            self.odd.sample(at)
+        }
+    }
+}
+
+// blends between two colors with layered noise, for clouds, stains and rough
+// surfaces
+pub struct Turbulence {
+    noise: Perlin,
+    low: Vector,
+    high: Vector,
+    scale: f64,
+    octaves: u32,
+    space: TextureSpace,
+}
+
+impl Turbulence {
+    pub fn new(seed: usize, low: Vector, high: Vector, scale: f64) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            low,
+            high,
+            scale,
+            octaves: 7,
+            space: TextureSpace::World,
+        }
+    }
+
+    pub fn set_space(&mut self, space: TextureSpace) {
+        self.space = space;
+    }
+}
+
+impl Texture for Turbulence {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        let p = self.space.point(at);
+        let t = self
+            .noise
+            .turbulence(self.scale * p, self.octaves)
+            .clamp(0.0, 1.0);
+        (1.0 - t) * self.low + t * self.high
+    }
+}
+
+// veins running along x, bent by turbulence
+pub struct Marble {
+    noise: Perlin,
+    base: Vector,
+    vein: Vector,
+    scale: f64,
+    // how strongly turbulence bends the veins
+    distortion: f64,
+    space: TextureSpace,
+}
+
+impl Marble {
+    pub fn new(seed: usize, base: Vector, vein: Vector, scale: f64) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            base,
+            vein,
+            scale,
+            distortion: 10.0,
+            space: TextureSpace::World,
+        }
+    }
+
+    pub fn set_space(&mut self, space: TextureSpace) {
+        self.space = space;
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        let p = self.space.point(at);
+        let phase = self.scale * p.z() + self.distortion * self.noise.turbulence(p, 7);
+        let t = 0.5 * (1.0 + phase.sin());
+        t * self.base + (1.0 - t) * self.vein
+    }
+}
+
+// concentric growth rings around the y axis, wobbled by noise
+pub struct Wood {
+    noise: Perlin,
+    light: Vector,
+    dark: Vector,
+    // rings per unit of radius
+    rings: f64,
+    space: TextureSpace,
+}
+
+impl Wood {
+    pub fn new(seed: usize, light: Vector, dark: Vector, rings: f64) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            light,
+            dark,
+            rings,
+            space: TextureSpace::World,
+        }
+    }
+
+    pub fn set_space(&mut self, space: TextureSpace) {
+        self.space = space;
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        let p = self.space.point(at);
+        let radius = (p.x() * p.x() + p.z() * p.z()).sqrt();
+        let grain = radius * self.rings + 2.0 * self.noise.noise(Vector(p.x(), 0.2 * p.y(), p.z()));
+        // sharpen the ring edges so the late wood forms thin dark bands
+        let t = grain.rem_euclid(1.0).powi(3);
+        (1.0 - t) * self.light + t * self.dark
+    }
+}
+
+// projects a (u, v) texture along the three axes and blends the projections
+// by how squarely the surface faces each, so surfaces without a usable
+// mapping, such as CSG results and scans, can take image textures without
+// stretching. Works in world space
+pub struct Triplanar {
+    texture: Arc<dyn Texture>,
+    // repeats of the texture per world unit
+    scale: f64,
+    // higher values narrow the seams where projections blend
+    sharpness: f64,
+}
+
+impl Triplanar {
+    pub fn new(texture: Arc<dyn Texture>, scale: f64) -> Self {
+        Self {
+            texture,
+            scale,
+            sharpness: 4.0,
+        }
+    }
+
+    pub fn set_sharpness(&mut self, sharpness: f64) {
+        self.sharpness = sharpness;
+    }
+}
+
+impl Texture for Triplanar {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        let n = at.normal;
+        let mut weights = [
+            n.x().abs().powf(self.sharpness),
+            n.y().abs().powf(self.sharpness),
+            n.z().abs().powf(self.sharpness),
+        ];
+        let total: f64 = weights.iter().sum();
+        if total > 0.0 {
+            weights.iter_mut().for_each(|w| *w /= total);
+        } else {
+            // no normal to go by
+            weights = [1.0 / 3.0; 3];
+        }
+
+        let p = self.scale * at.p;
+        let projections = [(p.z(), p.y()), (p.x(), p.z()), (p.x(), p.y())];
+        let mut total = Vector(0.0, 0.0, 0.0);
+        for (&w, &(u, v)) in weights.iter().zip(&projections) {
+            if w > 0.0 {
+                let lookup = TexturePoint { u, v, ..*at };
+                total = total + w * self.texture.sample(&lookup);
+            }
+        }
+        total
+    }
+}
+
+// places a texture on the surface by transforming the (u, v) it is looked up
+// with: rotated about the middle of the [0, 1] square, then repeated, then
+// shifted, so images can be tiled and lined up without editing them
+pub struct UvTransform {
+    texture: Arc<dyn Texture>,
+    // tiles across u and v
+    repeat: (f64, f64),
+    // in tiles, applied after the repeat
+    offset: (f64, f64),
+    // degrees, counterclockwise
+    rotation: f64,
+}
+
+impl UvTransform {
+    // starts out as the identity
+    pub fn new(texture: Arc<dyn Texture>) -> Self {
+        Self {
+            texture,
+            repeat: (1.0, 1.0),
+            offset: (0.0, 0.0),
+            rotation: 0.0,
+        }
+    }
+
+    pub fn set_repeat(&mut self, u: f64, v: f64) {
+        self.repeat = (u, v);
+    }
+
+    pub fn set_offset(&mut self, u: f64, v: f64) {
+        self.offset = (u, v);
+    }
+
+    pub fn set_rotation(&mut self, degrees: f64) {
+        self.rotation = degrees;
+    }
+
+    pub fn uv(&self, u: f64, v: f64) -> (f64, f64) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (du, dv) = (u - 0.5, v - 0.5);
+        let (u, v) = (cos * du - sin * dv + 0.5, sin * du + cos * dv + 0.5);
+        (
+            u * self.repeat.0 + self.offset.0,
+            v * self.repeat.1 + self.offset.1,
+        )
+    }
+}
+
+impl Texture for UvTransform {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        let (u, v) = self.uv(at.u, at.v);
+        self.texture.sample(&TexturePoint { u, v, ..*at })
+    }
+}
+
+// how a `Ramp` fills in between its stops
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    // the color of the stop below, for toon shading bands
+    Constant,
+    Linear,
+    // smoothstep, eases in and out of each stop
+    Smooth,
+}
+
+// maps the first channel of another texture onto a gradient of colors, for
+// sky gradients, toon ramps and remapping masks that drive other inputs
+pub struct Ramp {
+    input: Arc<dyn Texture>,
+    // (position, color), sorted by position
+    stops: Vec<(f64, Vector)>,
+    interpolation: Interpolation,
+}
+
+impl Ramp {
+    pub fn new(input: Arc<dyn Texture>, interpolation: Interpolation) -> Self {
+        Self {
+            input,
+            stops: Vec::new(),
+            interpolation,
+        }
+    }
+
+    pub fn add_stop(&mut self, position: f64, color: Vector) {
+        let i = self.stops.partition_point(|&(p, _)| p <= position);
+        self.stops.insert(i, (position, color));
+    }
+
+    // the gradient at `t`, held at the end colors outside the stops and black
+    // without any
+    pub fn color(&self, t: f64) -> Vector {
+        let i = self.stops.partition_point(|&(p, _)| p <= t);
+        if i == 0 {
+            return self.stops.first().map_or(Vector(0.0, 0.0, 0.0), |s| s.1);
+        }
+        if i == self.stops.len() {
+            return self.stops[i - 1].1;
+        }
+
+        let (p0, c0) = self.stops[i - 1];
+        let (p1, c1) = self.stops[i];
+        let x = (t - p0) / (p1 - p0);
+        let x = match self.interpolation {
+            Interpolation::Constant => 0.0,
+            Interpolation::Linear => x,
+            Interpolation::Smooth => x * x * (3.0 - 2.0 * x),
+        };
+        (1.0 - x) * c0 + x * c1
+    }
+}
+
+impl Texture for Ramp {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        self.color(self.input.sample(at).x())
+    }
+}
+
+// Nodes combine other textures, image, procedural or constant, into one input
+// evaluated at shade time; plain colors and numbers go in with `.into()` or
+// `SolidColor`
+
+// blends from `a` to `b` by the first channel of `factor`
+pub struct MixNode {
+    a: Arc<dyn Texture>,
+    b: Arc<dyn Texture>,
+    factor: Arc<dyn Texture>,
+}
+
+impl MixNode {
+    pub fn new(a: Arc<dyn Texture>, b: Arc<dyn Texture>, factor: Arc<dyn Texture>) -> Self {
+        Self { a, b, factor }
+    }
+}
+
+impl Texture for MixNode {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        let t = self.factor.sample(at).x();
+        (1.0 - t) * self.a.sample(at) + t * self.b.sample(at)
+    }
+}
+
+// channel by channel product, e.g. a color tinted by a grime mask
+pub struct MultiplyNode {
+    a: Arc<dyn Texture>,
+    b: Arc<dyn Texture>,
+}
+
+impl MultiplyNode {
+    pub fn new(a: Arc<dyn Texture>, b: Arc<dyn Texture>) -> Self {
+        Self { a, b }
+    }
+}
+
+impl Texture for MultiplyNode {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        self.a.sample(at) * self.b.sample(at)
+    }
+}
+
+pub struct AddNode {
+    a: Arc<dyn Texture>,
+    b: Arc<dyn Texture>,
+}
+
+impl AddNode {
+    pub fn new(a: Arc<dyn Texture>, b: Arc<dyn Texture>) -> Self {
+        Self { a, b }
+    }
+}
+
+impl Texture for AddNode {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        self.a.sample(at) + self.b.sample(at)
+    }
+}
+
+// 1 - x in every channel, turns a roughness map into a gloss map and back
+pub struct InvertNode {
+    input: Arc<dyn Texture>,
+}
+
+impl InvertNode {
+    pub fn new(input: Arc<dyn Texture>) -> Self {
+        Self { input }
+    }
+}
+
+impl Texture for InvertNode {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        Vector(1.0, 1.0, 1.0) - self.input.sample(at)
+    }
+}
+
+// keeps every channel within [min, max], e.g. after an `AddNode`
+pub struct ClampNode {
+    input: Arc<dyn Texture>,
+    min: f64,
+    max: f64,
+}
+
+impl ClampNode {
+    pub fn new(input: Arc<dyn Texture>, min: f64, max: f64) -> Self {
+        Self { input, min, max }
+    }
+}
+
+impl Texture for ClampNode {
+    fn value(&self, u: f64, v: f64, p: Vector) -> Vector {
+        self.sample(&TexturePoint::new(u, v, p))
+    }
+
+    fn sample(&self, at: &TexturePoint) -> Vector {
+        let c = self.input.sample(at);
+        let clamp = |x: f64| x.clamp(self.min, self.max);
+        Vector(clamp(c.x()), clamp(c.y()), clamp(c.z()))
+    }
+}
+
+// what happens to (u, v) outside [0, 1]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Addressing {
+    // the image tiles
+    Wrap,
+    // edge texels are stretched outward
+    Clamp,
+}
+
+// a bitmap mapped over (u, v) with bilinear filtering, v = 0 is the bottom row
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // linear texel colors, rows top to bottom
+    texels: Vec<Vector>,
+    addressing: Addressing,
+}
+
+impl ImageTexture {
+    // reads a PNG whose pixels are encoded in `space`, usually `ColorSpace::Srgb`
+    pub fn load(path: &str, space: ColorSpace, addressing: Addressing) -> io::Result<Self> {
+        let image = lodepng::decode24_file(path).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("could not read \"{}\": {}", path, err),
+            )
+        })?;
+
+        let texels = image
+            .buffer
+            .iter()
+            .map(|p| {
+                let c = Vector(p.r as f64, p.g as f64, p.b as f64) / 255.0;
+                space.decode(c)
+            })
+            .collect();
+
+        Ok(Self {
+            width: image.width,
+            height: image.height,
+            texels,
+            addressing,
+        })
+    }
+
+    // the alpha channel of a PNG as a grayscale texture, e.g. the opacity of a
+    // `Cutout`; images without alpha come out opaque
+    pub fn load_alpha(path: &str, addressing: Addressing) -> io::Result<Self> {
+        let image = lodepng::decode32_file(path).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("could not read \"{}\": {}", path, err),
+            )
+        })?;
+
+        let texels = image
+            .buffer
+            .iter()
+            .map(|p| {
+                let a = p.a as f64 / 255.0;
+                Vector(a, a, a)
+            })
+            .collect();
+
+        Ok(Self {
+            width: image.width,
+            height: image.height,
+            texels,
+            addressing,
+        })
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vector {
+        let (w, h) = (self.width as i64, self.height as i64);
+        let (x, y) = match self.addressing {
+            Addressing::Wrap => (x.rem_euclid(w), y.rem_euclid(h)),
+            Addressing::Clamp => (x.clamp(0, w - 1), y.clamp(0, h - 1)),
+        };
+        self.texels[(y * w + x) as usize]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Vector) -> Vector {
+        if self.texels.is_empty() {
+            return Vector(1.0, 0.0, 1.0);
+        }
+
+        // continuous texel coordinates with texel centers at half integers
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = (1.0 - tx) * self.texel(x0, y0) + tx * self.texel(x0 + 1, y0);
+        let bottom = (1.0 - tx) * self.texel(x0, y0 + 1) + tx * self.texel(x0 + 1, y0 + 1);
+        (1.0 - ty) * top + ty * bottom
+    }
+}
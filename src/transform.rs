@@ -0,0 +1,182 @@
+use crate::aabb::Aabb;
+use crate::vector::Vector;
+
+// affine transform stored as a 3x4 matrix (rotation/scale columns plus translation)
+// together with its inverse
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    m: [[f64; 4]; 3],
+    inv: [[f64; 4]; 3],
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        let m = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+        ];
+        Self { m, inv: m }
+    }
+
+    pub fn translate(offset: Vector) -> Self {
+        Self::from_matrix([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+        ])
+    }
+
+    pub fn scale(factors: Vector) -> Self {
+        Self::from_matrix([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+        ])
+    }
+
+    // rotation by `degrees` around `axis` (right handed)
+    pub fn rotate(axis: Vector, degrees: f64) -> Self {
+        let a = axis.to_unit_vector();
+        let theta = std::f64::consts::PI / 180.0 * degrees;
+        let (s, c) = theta.sin_cos();
+        let t = 1.0 - c;
+
+        Self::from_matrix([
+            [
+                t * a.x() * a.x() + c,
+                t * a.x() * a.y() - s * a.z(),
+                t * a.x() * a.z() + s * a.y(),
+                0.0,
+            ],
+            [
+                t * a.x() * a.y() + s * a.z(),
+                t * a.y() * a.y() + c,
+                t * a.y() * a.z() - s * a.x(),
+                0.0,
+            ],
+            [
+                t * a.x() * a.z() - s * a.y(),
+                t * a.y() * a.z() + s * a.x(),
+                t * a.z() * a.z() + c,
+                0.0,
+            ],
+        ])
+    }
+
+    fn from_matrix(m: [[f64; 4]; 3]) -> Self {
+        Self { m, inv: invert(&m) }
+    }
+
+    // applies `self` first, then `other`
+    pub fn then(&self, other: &Transform) -> Transform {
+        Self::from_matrix(multiply(&other.m, &self.m))
+    }
+
+    // blend of the two matrices, `t` from 0 (self) to 1 (other). Exact for
+    // moves and scales, rotations cut the corner a little
+    pub fn lerp(&self, other: &Transform, t: f64) -> Transform {
+        let mut m = self.m;
+        for (row, other_row) in m.iter_mut().zip(&other.m) {
+            for (a, b) in row.iter_mut().zip(other_row) {
+                *a += t * (b - *a);
+            }
+        }
+        Self::from_matrix(m)
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Self {
+            m: self.inv,
+            inv: self.m,
+        }
+    }
+
+    pub fn point(&self, p: Vector) -> Vector {
+        apply(&self.m, p, 1.0)
+    }
+
+    pub fn vector(&self, v: Vector) -> Vector {
+        apply(&self.m, v, 0.0)
+    }
+
+    // normals transform by the inverse transpose
+    pub fn normal(&self, n: Vector) -> Vector {
+        let i = &self.inv;
+        Vector(
+            i[0][0] * n.x() + i[1][0] * n.y() + i[2][0] * n.z(),
+            i[0][1] * n.x() + i[1][1] * n.y() + i[2][1] * n.z(),
+            i[0][2] * n.x() + i[1][2] * n.y() + i[2][2] * n.z(),
+        )
+        .to_unit_vector()
+    }
+
+    pub fn inverse_point(&self, p: Vector) -> Vector {
+        apply(&self.inv, p, 1.0)
+    }
+
+    pub fn inverse_vector(&self, v: Vector) -> Vector {
+        apply(&self.inv, v, 0.0)
+    }
+
+    // bounds of the transformed corners of `b`
+    pub fn bounding_box(&self, b: &Aabb) -> Aabb {
+        let mut out = Aabb::empty();
+        for i in 0..8 {
+            let corner = Vector(
+                if i & 1 == 0 { b.min.x() } else { b.max.x() },
+                if i & 2 == 0 { b.min.y() } else { b.max.y() },
+                if i & 4 == 0 { b.min.z() } else { b.max.z() },
+            );
+            out = out.grow(self.point(corner));
+        }
+        out
+    }
+}
+
+fn apply(m: &[[f64; 4]; 3], v: Vector, w: f64) -> Vector {
+    Vector(
+        m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z() + m[0][3] * w,
+        m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z() + m[1][3] * w,
+        m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z() + m[2][3] * w,
+    )
+}
+
+// a * b, treating both as 4x4 matrices with an implicit (0, 0, 0, 1) last row
+fn multiply(a: &[[f64; 4]; 3], b: &[[f64; 4]; 3]) -> [[f64; 4]; 3] {
+    let mut out = [[0.0; 4]; 3];
+    for (r, row) in out.iter_mut().enumerate() {
+        for (c, value) in row.iter_mut().enumerate() {
+            *value = a[r][0] * b[0][c] + a[r][1] * b[1][c] + a[r][2] * b[2][c];
+            if c == 3 {
+                *value += a[r][3];
+            }
+        }
+    }
+    out
+}
+
+fn invert(m: &[[f64; 4]; 3]) -> [[f64; 4]; 3] {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    let d = 1.0 / det;
+
+    let mut inv = [[0.0; 4]; 3];
+    inv[0][0] = (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * d;
+    inv[0][1] = (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * d;
+    inv[0][2] = (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * d;
+    inv[1][0] = (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * d;
+    inv[1][1] = (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * d;
+    inv[1][2] = (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * d;
+    inv[2][0] = (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * d;
+    inv[2][1] = (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * d;
+    inv[2][2] = (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * d;
+
+    // inverse translation is -R^-1 * t
+    for row in inv.iter_mut() {
+        row[3] = -(row[0] * m[0][3] + row[1] * m[1][3] + row[2] * m[2][3]);
+    }
+
+    inv
+}
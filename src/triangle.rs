@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::material::Scatter;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// boxes of axis-aligned triangles are padded so they never have zero thickness
+const BOX_PADDING: f64 = 1.0e-6;
+
+pub struct Triangle {
+    pub vertices: [Vector; 3],
+    // per-vertex shading normals, interpolated across the face when present
+    pub normals: Option<[Vector; 3]>,
+    // per-vertex texture coordinates, the barycentric coordinates are used without them
+    pub uvs: Option<[(f64, f64); 3]>,
+    pub material: Arc<dyn Scatter>,
+    // ignore hits from the back of the face (counter-clockwise winding is the front)
+    pub one_sided: bool,
+}
+
+impl Triangle {
+    pub fn new(a: Vector, b: Vector, c: Vector, m: Arc<dyn Scatter>) -> Self {
+        Self {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: None,
+            material: m,
+            one_sided: false,
+        }
+    }
+
+    pub fn with_normals(vertices: [Vector; 3], normals: [Vector; 3], m: Arc<dyn Scatter>) -> Self {
+        Self {
+            vertices,
+            normals: Some(normals),
+            uvs: None,
+            material: m,
+            one_sided: false,
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    // Moller-Trumbore
+    fn ray_intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let [v0, v1, v2] = self.vertices;
+        let e1 = v1 - v0;
+        let e2 = v2 - v0;
+
+        let pvec = r.direction.cross(e2);
+        let det = e1.dot(pvec);
+        if det.abs() < 1.0e-12 {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let tvec = r.origin - v0;
+        let u = tvec.dot(pvec) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let qvec = tvec.cross(e1);
+        let v = r.direction.dot(qvec) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = e2.dot(qvec) * inv_det;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let geometric = e1.cross(e2).to_unit_vector();
+        let front_face = r.direction.dot(geometric) < 0.0;
+        if self.one_sided && !front_face {
+            return None;
+        }
+
+        let outward_normal = match self.normals {
+            Some([n0, n1, n2]) => ((1.0 - u - v) * n0 + u * n1 + v * n2).to_unit_vector(),
+            None => geometric,
+        };
+
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+
+        let (tex_u, tex_v, dpdu, dpdv) = match self.uvs {
+            Some([a, b, c]) => {
+                // invert the 2x2 map from uv deltas to the edges
+                let (du1, dv1) = (b.0 - a.0, b.1 - a.1);
+                let (du2, dv2) = (c.0 - a.0, c.1 - a.1);
+                let uv_det = du1 * dv2 - du2 * dv1;
+                let (dpdu, dpdv) = if uv_det.abs() < 1.0e-12 {
+                    (e1, e2)
+                } else {
+                    (
+                        (dv2 * e1 - dv1 * e2) / uv_det,
+                        (du1 * e2 - du2 * e1) / uv_det,
+                    )
+                };
+
+                (
+                    (1.0 - u - v) * a.0 + u * b.0 + v * c.0,
+                    (1.0 - u - v) * a.1 + u * b.1 + v * c.1,
+                    dpdu,
+                    dpdv,
+                )
+            }
+            None => (u, v, e1, e2),
+        };
+
+        Some(Hit {
+            t,
+            p: r.line_to_p(t),
+            local: r.line_to_p(t),
+            normal,
+            front_face,
+            material: self.material.clone(),
+            u: tex_u,
+            v: tex_v,
+            dpdu,
+            dpdv,
+            object: 0,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let [a, b, c] = self.vertices;
+        let pad = Vector(BOX_PADDING, BOX_PADDING, BOX_PADDING);
+        let b = Aabb::new(a, a).grow(b).grow(c);
+        Aabb::new(b.min - pad, b.max + pad)
+    }
+}
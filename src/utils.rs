@@ -1,8 +1,83 @@
-use crate::rand::Rng;
+use std::cell::{Cell, RefCell};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::rand::{Rng, SeedableRng, StdRng};
+use crate::sampler::Sampler;
 use crate::vector::Vector;
 
+thread_local! {
+    // every random number the renderer draws comes from here so a render can be
+    // reproduced, or split across processes, by seeding it
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::new().expect("no OS randomness"));
+    // pixel sample being shaded on this thread, see `begin_sample`
+    static SAMPLE: RefCell<Option<SampleState>> = const { RefCell::new(None) };
+}
+
+struct SampleState {
+    sampler: Arc<dyn Sampler>,
+    pixel: (i32, i32),
+    index: u32,
+    count: u32,
+    scramble: u32,
+    // dimension the next draw takes
+    dim: Cell<u32>,
+}
+
+// until `end_sample`, `random_in_range` draws dimension `dim`, `dim + 1`, ...
+// of sample `index` of the `count` `pixel` takes from `sampler` instead of the
+// generator. `scramble` must be the same for all samples of the pixel
+pub fn begin_sample(
+    sampler: &Arc<dyn Sampler>,
+    pixel: (i32, i32),
+    scramble: u32,
+    index: u32,
+    count: u32,
+    dim: u32,
+) {
+    SAMPLE.with(|s| {
+        *s.borrow_mut() = Some(SampleState {
+            sampler: Arc::clone(sampler),
+            pixel,
+            index,
+            count,
+            scramble,
+            dim: Cell::new(dim),
+        })
+    });
+}
+
+pub fn end_sample() {
+    SAMPLE.with(|s| *s.borrow_mut() = None);
+}
+
+// restarts this thread's generator from a fixed seed
+pub fn seed_rng(seed: usize) {
+    RNG.with(|rng| *rng.borrow_mut() = SeedableRng::from_seed(&[seed][..]));
+}
+
 pub fn random_in_range(min: f64, max: f64) -> f64 {
-    return rand::thread_rng().gen_range(min, max);
+    let u = SAMPLE.with(|s| {
+        s.borrow().as_ref().map(|s| {
+            let dim = s.dim.get();
+            s.dim.set(dim + 1);
+            s.sampler.get(s.pixel, s.index, s.count, dim, s.scramble)
+        })
+    });
+
+    match u {
+        Some(u) => min + u * (max - min),
+        None => RNG.with(|rng| rng.borrow_mut().gen_range(min, max)),
+    }
+}
+
+// uniform in [0, 1) straight from the generator, bypassing any sampler
+pub fn random_uniform() -> f64 {
+    RNG.with(|rng| rng.borrow_mut().gen_range(0.0, 1.0))
+}
+
+pub fn random_u32() -> u32 {
+    RNG.with(|rng| rng.borrow_mut().gen())
 }
 
 pub fn random_vector_in_unit_sphere() -> Vector {
@@ -13,8 +88,49 @@ pub fn random_vector_in_unit_sphere() -> Vector {
     );
 
     if vec.length() <= 1.0 {
-        return vec.to_unit_vector();
+        vec
+    } else {
+        random_vector_in_unit_sphere()
+    }
+}
+
+// uniformly distributed direction on the unit sphere
+pub fn random_unit_vector() -> Vector {
+    let vec = random_vector_in_unit_sphere();
+
+    if vec.near_zero() {
+        random_unit_vector()
     } else {
-        return random_vector_in_unit_sphere();
+        vec.to_unit_vector()
     }
 }
+
+// uniformly distributed point inside the unit disk in the xy plane, mapped
+// concentrically from two draws so stratified samples stay stratified
+pub fn random_in_unit_disk() -> Vector {
+    let a = random_in_range(-1.0, 1.0);
+    let b = random_in_range(-1.0, 1.0);
+    if a == 0.0 && b == 0.0 {
+        return Vector(0.0, 0.0, 0.0);
+    }
+
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    Vector(r * theta.cos(), r * theta.sin(), 0.0)
+}
+
+// two unit vectors completing an orthonormal basis with the unit vector n
+pub fn orthonormal_basis(n: Vector) -> (Vector, Vector) {
+    let helper = if n.x().abs() > 0.9 {
+        Vector(0.0, 1.0, 0.0)
+    } else {
+        Vector(1.0, 0.0, 0.0)
+    };
+    let tangent = n.cross(helper).to_unit_vector();
+    let bitangent = n.cross(tangent);
+
+    (tangent, bitangent)
+}
@@ -14,6 +14,15 @@ impl Vector {
         self.2
     }
 
+    // component by index: 0 = x, 1 = y, 2 = z
+    pub fn axis(&self, i: usize) -> f64 {
+        match i {
+            0 => self.0,
+            1 => self.1,
+            _ => self.2,
+        }
+    }
+
     pub fn dot(&self, other: Vector) -> f64 {
         self.0 * other.0 + self.1 * other.1 + self.2 * other.2
     }
@@ -37,7 +46,42 @@ impl Vector {
         self - 2.0 * self.dot(n) * n
     }
 
-    pub fn to_u8(&self) -> [u8; 3] {
+    // Snell's law for a unit vector hitting a surface with unit normal n,
+    // eta_ratio is eta_incident / eta_transmitted
+    pub fn refract(self, n: Vector, eta_ratio: f64) -> Vector {
+        let cos_theta = (-self).dot(n).min(1.0);
+        let r_out_perp = eta_ratio * (self + cos_theta * n);
+        let r_out_parallel = -(1.0 - r_out_perp.squared_length()).abs().sqrt() * n;
+        r_out_perp + r_out_parallel
+    }
+
+    // Rec. 709 relative luminance
+    pub fn luminance(self) -> f64 {
+        0.2126 * self.0 + 0.7152 * self.1 + 0.0722 * self.2
+    }
+
+    // brings a color with channels above 1 back into range by blending it toward
+    // white while keeping its luminance, so bright colored light blooms to white
+    // instead of clipping to a more saturated hue
+    pub fn desaturate_highlights(self) -> Vector {
+        let peak = self.0.max(self.1).max(self.2);
+        if peak <= 1.0 {
+            return self;
+        }
+
+        let lum = self.luminance();
+        if lum >= 1.0 {
+            return Vector(1.0, 1.0, 1.0);
+        }
+
+        // the hue scaled to fit, mixed with white until the luminance matches
+        let t = (1.0 - lum) / (1.0 - lum / peak);
+        t * (self / peak) + (1.0 - t) * Vector(1.0, 1.0, 1.0)
+    }
+
+    // quantizes channels in [0, 1], anything outside is clipped; tone map
+    // radiance first, see `color::ToneMap`
+    pub fn to_u8(self) -> [u8; 3] {
         fn u(f: f64) -> u8 {
             if f < 0.0 {
                 0
@@ -50,7 +94,7 @@ impl Vector {
         [u(self.0), u(self.1), u(self.2)]
     }
 
-    pub fn to_rgb(&self) -> lodepng::RGB<u8> {
+    pub fn to_rgb(self) -> lodepng::RGB<u8> {
         let v = self.to_u8();
 
         lodepng::RGB {
@@ -60,8 +104,8 @@ impl Vector {
         }
     }
 
-    pub fn to_unit_vector(&self) -> Vector {
-        *self / self.length()
+    pub fn to_unit_vector(self) -> Vector {
+        self / self.length()
     }
 
     pub fn print(&self) {
@@ -0,0 +1,235 @@
+// Every accelerator indexes the same primitives, so all of them have to find
+// the same closest hit as the BVH for any ray.
+use std::sync::Arc;
+
+use ray_tracer::accelerator::{self, Accelerator, AcceleratorKind};
+use ray_tracer::bvh::{self, Bvh, BvhBuildOptions, SplitMethod};
+use ray_tracer::cuboid::Cuboid;
+use ray_tracer::hittable::Hittable;
+use ray_tracer::material::Scatter;
+use ray_tracer::materials::lambertian::Lambertian;
+use ray_tracer::ray::{Ray, RayPacket};
+use ray_tracer::sphere::Sphere;
+use ray_tracer::stats;
+use ray_tracer::utils::{self, random_in_range, random_unit_vector};
+use ray_tracer::vector::Vector;
+
+// scattered balls and boxes over rows of zero thickness floor tiles, whose
+// bounding boxes are flat and lie exactly on likely split planes
+fn objects() -> Vec<Box<dyn Hittable>> {
+    let material: Arc<dyn Scatter> = Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5)));
+    let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+
+    for level in 0..3 {
+        let y = level as f64;
+        for i in 0..6 {
+            let x = i as f64 - 3.0;
+            objects.push(Box::new(Cuboid::new(
+                Vector(x, y, -3.0),
+                Vector(x + 0.8, y, 3.0),
+                material.clone(),
+            )));
+        }
+    }
+
+    utils::seed_rng(5);
+    for _ in 0..40 {
+        let center = Vector(
+            random_in_range(-3.0, 3.0),
+            random_in_range(-0.5, 2.5),
+            random_in_range(-3.0, 3.0),
+        );
+        let size = random_in_range(0.05, 0.3);
+        if random_in_range(0.0, 1.0) < 0.5 {
+            objects.push(Box::new(Sphere::new(&center, size, material.clone())));
+        } else {
+            let half = Vector(size, size, size);
+            objects.push(Box::new(Cuboid::new(
+                center - half,
+                center + half,
+                material.clone(),
+            )));
+        }
+    }
+    objects
+}
+
+fn random_ray() -> Ray {
+    let origin = Vector(
+        random_in_range(-5.0, 5.0),
+        random_in_range(-2.0, 4.0),
+        random_in_range(-5.0, 5.0),
+    );
+    Ray::new(origin, random_unit_vector())
+}
+
+// closest hit distances of the same random rays, infinity for misses
+fn hit_distances(accelerator: &dyn Accelerator) -> Vec<f64> {
+    utils::seed_rng(9);
+    (0..2000)
+        .map(|_| {
+            accelerator
+                .ray_intersect(&random_ray(), 1e-6, f64::INFINITY)
+                .map_or(f64::INFINITY, |h| h.t)
+        })
+        .collect()
+}
+
+fn assert_matches_bvh(kind: AcceleratorKind) {
+    let reference = hit_distances(&*accelerator::build(AcceleratorKind::default(), objects()));
+    let tested = accelerator::build(kind.clone(), objects());
+    assert!(reference.iter().any(|t| t.is_finite()));
+
+    for (i, (a, b)) in reference.iter().zip(&hit_distances(&*tested)).enumerate() {
+        assert!(
+            a == b || (a - b).abs() < 1e-9,
+            "ray {}: BVH hit at {}, {:?} at {}",
+            i,
+            a,
+            kind,
+            b
+        );
+    }
+
+    // any-hit queries agree with the closest hit, cut off halfway to it
+    utils::seed_rng(9);
+    for t in &reference {
+        let ray = random_ray();
+        if t.is_finite() {
+            assert!(tested.occluded(&ray, 1e-6, t + 1e-6));
+            assert!(!tested.occluded(&ray, 1e-6, 0.5 * t));
+        } else {
+            assert!(!tested.occluded(&ray, 1e-6, f64::INFINITY));
+        }
+    }
+
+    // packets of four find what the rays find one by one
+    utils::seed_rng(9);
+    for chunk in reference.chunks_exact(4) {
+        let packet = RayPacket::new([random_ray(), random_ray(), random_ray(), random_ray()]);
+        let hits = tested.intersect_packet(&packet, 1e-6, f64::INFINITY);
+        for (hit, t) in hits.iter().zip(chunk) {
+            let found = hit.as_ref().map_or(f64::INFINITY, |h| h.t);
+            assert!(found == *t || (found - t).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn bvh_any_hit_and_packet_queries_agree() {
+    assert_matches_bvh(AcceleratorKind::default());
+}
+
+#[test]
+fn median_split_bvh_matches_sah_bvh() {
+    assert_matches_bvh(AcceleratorKind::Bvh(BvhBuildOptions {
+        split: SplitMethod::Median,
+        max_leaf_size: 4,
+    }));
+}
+
+#[test]
+fn kd_tree_matches_bvh() {
+    assert_matches_bvh(AcceleratorKind::KdTree);
+}
+
+#[test]
+fn grid_matches_bvh() {
+    assert_matches_bvh(AcceleratorKind::Grid);
+}
+
+#[test]
+fn bvh_cache_round_trips_and_goes_stale() {
+    let path = std::env::temp_dir()
+        .join(format!("ray-tracer-{}-scene.bvh", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let options = BvhBuildOptions::default();
+    let key = |path: &str| std::fs::read(path).unwrap()[4..12].to_vec();
+    let reference = hit_distances(&Bvh::with_options(objects(), options));
+
+    // the first run writes the cache, the second reads it back
+    let kind = AcceleratorKind::CachedBvh {
+        options,
+        path: path.clone(),
+    };
+    accelerator::build(kind.clone(), objects());
+    let written = std::fs::read(&path).unwrap();
+    assert_eq!(
+        key(&path),
+        bvh::fingerprint(&objects(), &options).to_le_bytes()
+    );
+    let loaded = accelerator::build(kind, objects());
+    assert_eq!(std::fs::read(&path).unwrap(), written);
+    assert_eq!(hit_distances(&*loaded), reference);
+
+    // moving one object makes the cache stale, so it is rebuilt and replaced
+    let moved = || {
+        let mut objects = objects();
+        objects[0] = Box::new(Sphere::new(
+            &Vector(0.0, 5.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5))),
+        ));
+        objects
+    };
+    let key_before = key(&path);
+    let rebuilt = Bvh::load_or_build(moved(), options, &path);
+    assert_ne!(key(&path), key_before);
+    assert_eq!(
+        hit_distances(&rebuilt),
+        hit_distances(&Bvh::with_options(moved(), options))
+    );
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn bvh_cache_rebuilds_when_corrupt() {
+    let path = std::env::temp_dir()
+        .join(format!("ray-tracer-{}-corrupt.bvh", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let options = BvhBuildOptions::default();
+    let reference = hit_distances(&Bvh::with_options(objects(), options));
+    Bvh::load_or_build(objects(), options, &path);
+    let written = std::fs::read(&path).unwrap();
+
+    // after the magic, key and counts: a root pointing back at itself, and a
+    // node count no file could hold
+    let header = 4 + 8 + 8;
+    let right = header + 8 + 6 * 8 + 1;
+    let corruptions: [(usize, u64); 2] = [(right, 0), (header, u64::MAX)];
+    for (offset, value) in corruptions {
+        let mut data = written.clone();
+        data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        std::fs::write(&path, data).unwrap();
+
+        let loaded = Bvh::load_or_build(objects(), options, &path);
+        assert_eq!(hit_distances(&loaded), reference);
+        assert_eq!(std::fs::read(&path).unwrap(), written);
+    }
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn traversals_count_only_for_heatmaps() {
+    let ray = Ray::new(Vector(0.0, 5.0, 0.0), Vector(0.0, -1.0, 0.0));
+    for kind in [
+        AcceleratorKind::default(),
+        AcceleratorKind::KdTree,
+        AcceleratorKind::Grid,
+    ] {
+        let accelerator = accelerator::build(kind, objects());
+        stats::take();
+        accelerator.ray_intersect(&ray, 1e-6, f64::INFINITY);
+        assert_eq!(stats::take().total(), 0);
+
+        stats::set_counting(true);
+        accelerator.ray_intersect(&ray, 1e-6, f64::INFINITY);
+        stats::set_counting(false);
+        let counted = stats::take();
+        assert!(counted.nodes > 0 && counted.primitives > 0);
+    }
+}
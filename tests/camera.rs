@@ -0,0 +1,40 @@
+// Cameras derived from the scene's, such as the eyes of a stereo pair, have
+// to keep everything about it but the position.
+use ray_tracer::camera::{Camera, PerspectiveCamera};
+use ray_tracer::utils;
+use ray_tracer::vector::Vector;
+
+#[test]
+fn translated_camera_keeps_lens_and_shutter() {
+    let mut camera = PerspectiveCamera::new(
+        Vector(0.0, 1.0, 5.0),
+        Vector(0.0, 1.0, 0.0),
+        Vector(0.0, 1.0, 0.0),
+        30.0,
+        1.5,
+        0.4,
+        5.0,
+    );
+    camera.set_shutter(0.2, 0.6);
+    let offset = Vector(0.03, 0.0, 0.0);
+    let eye = camera.translated(offset).unwrap();
+
+    assert!((eye.origin() - (camera.origin() + offset)).length() < 1e-12);
+    let (a, b) = (camera.center_ray(0.3, 0.7), eye.center_ray(0.3, 0.7));
+    assert!((a.direction - b.direction).length() < 1e-12);
+
+    utils::seed_rng(3);
+    let mut spread: f64 = 0.0;
+    for _ in 0..100 {
+        let ray = eye.generate_ray(0.5, 0.5);
+        let from_center = (ray.origin - eye.origin()).length();
+        assert!(from_center <= 0.2 + 1e-12);
+        spread = spread.max(from_center);
+        assert!((0.2..=0.6).contains(&ray.time));
+
+        // still focused 5 units ahead
+        let focus = ray.origin + (5.0 / -ray.direction.z()) * ray.direction;
+        assert!((focus - Vector(0.03, 1.0, 0.0)).length() < 1e-9);
+    }
+    assert!(spread > 0.1, "the lens was dropped");
+}
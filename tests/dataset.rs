@@ -0,0 +1,38 @@
+// Dataset frames are read by other tools, so what they write has to parse in
+// any JSON reader, whatever the scene's labels contain.
+use ray_tracer::camera::PerspectiveCamera;
+use ray_tracer::dataset::Frame;
+use ray_tracer::vector::Vector;
+
+#[test]
+fn class_legend_is_escaped_as_json() {
+    let mut frame = Frame::new(2, 1);
+    frame.rgb = vec![Vector(0.0, 0.0, 0.0).to_rgb(); 2];
+    frame.depth = vec![1.0; 2];
+    frame.normal = vec![Vector(0.0, 0.0, 1.0); 2];
+    frame.instance = vec![0; 2];
+    frame.class = vec![1, 2];
+    frame.legend = vec![
+        "chair \"arm\"\\left".to_string(),
+        "tab\there\u{1b}é".to_string(),
+    ];
+
+    let camera = PerspectiveCamera::new(
+        Vector(0.0, 0.0, 1.0),
+        Vector(0.0, 0.0, 0.0),
+        Vector(0.0, 1.0, 0.0),
+        40.0,
+        2.0,
+        0.0,
+        1.0,
+    );
+    let dir = std::env::temp_dir().join(format!("ray-tracer-{}-dataset", std::process::id()));
+    frame.write(&dir, 0, &camera).unwrap();
+    let legend = std::fs::read_to_string(dir.join("classes.json")).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(
+        legend,
+        "{\n  \"0\": \"background\",\n  \"1\": \"chair \\\"arm\\\"\\\\left\",\n  \"2\": \"tab\\there\\u001bé\"\n}\n"
+    );
+}
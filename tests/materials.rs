@@ -0,0 +1,127 @@
+// Wrapper materials have to pass every optional `Scatter` query on to the
+// material they wrap, or the scene silently treats the surface differently.
+use std::sync::Arc;
+
+use ray_tracer::camera::PerspectiveCamera;
+use ray_tracer::hittable::{Hit, Hittable};
+use ray_tracer::light::Light;
+use ray_tracer::material::Scatter;
+use ray_tracer::material_library::MaterialLibrary;
+use ray_tracer::materials::{
+    bump::Bump, coat::Coat, lambertian::Lambertian, metal::Metal, mix::Mix,
+    occlusion_map::OcclusionMap, one_sided::OneSided,
+};
+use ray_tracer::ray::Ray;
+use ray_tracer::scene::Scene;
+use ray_tracer::sphere::Sphere;
+use ray_tracer::texture::SolidColor;
+use ray_tracer::vector::Vector;
+
+const WHITE: Vector = Vector(1.0, 1.0, 1.0);
+
+fn hit_on(material: Arc<dyn Scatter>) -> Hit {
+    let sphere = Sphere::new(&Vector(0.0, 0.0, 0.0), 1.0, material);
+    let ray = Ray::new(Vector(0.0, 0.0, 5.0), Vector(0.0, 0.0, -1.0));
+    sphere.ray_intersect(&ray, 1e-6, f64::INFINITY).unwrap()
+}
+
+fn occluded(material: Arc<dyn Scatter>, baked: f64) -> Arc<dyn Scatter> {
+    Arc::new(OcclusionMap::new(
+        material,
+        Arc::new(SolidColor::scalar(baked)),
+    ))
+}
+
+#[test]
+fn occlusion_passes_through_wrappers() {
+    let gray = Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5)));
+    let baked = occluded(gray.clone(), 0.25);
+
+    let hit = hit_on(baked.clone());
+    assert_eq!(hit.material.occlusion(&hit), 0.25);
+    assert_eq!(gray.occlusion(&hit), 1.0);
+
+    let wrappers: Vec<Arc<dyn Scatter>> = vec![
+        Arc::new(OneSided::new(baked.clone())),
+        Arc::new(Bump::new(
+            baked.clone(),
+            Arc::new(SolidColor::scalar(0.0)),
+            1.0,
+        )),
+        Arc::new(Mix::new(baked.clone(), baked.clone(), 0.3)),
+    ];
+    for wrapper in wrappers {
+        let hit = hit_on(wrapper);
+        assert_eq!(hit.material.occlusion(&hit), 0.25);
+    }
+
+    // a mix blends the occlusion of its two sides
+    let half = Mix::new(baked, gray, 0.5);
+    assert_eq!(half.occlusion(&hit), 0.625);
+}
+
+#[test]
+fn diffuse_passes_through_wrappers() {
+    let gray: Arc<dyn Scatter> = Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5)));
+    let white: Arc<dyn Scatter> = Arc::new(Lambertian::new(Vector(1.0, 1.0, 1.0)));
+    let mirror: Arc<dyn Scatter> = Arc::new(Metal::new(Vector(1.0, 1.0, 1.0), 0.0));
+
+    let mut library = MaterialLibrary::new();
+    library.define("gray", gray.clone());
+    let slot = library.get("gray").unwrap();
+
+    let wrappers: Vec<(Arc<dyn Scatter>, Option<Vector>)> = vec![
+        (Arc::new(OneSided::new(gray.clone())), Some(0.5 * WHITE)),
+        (
+            Arc::new(Bump::new(
+                gray.clone(),
+                Arc::new(SolidColor::scalar(0.0)),
+                1.0,
+            )),
+            Some(0.5 * WHITE),
+        ),
+        (slot, Some(0.5 * WHITE)),
+        (
+            Arc::new(Mix::new(gray.clone(), white, 0.5)),
+            Some(0.75 * WHITE),
+        ),
+        // a mirror half isn't diffuse, nor is a glossy coat seen from outside
+        (Arc::new(Mix::new(gray.clone(), mirror, 0.5)), None),
+        (Arc::new(Coat::new(gray, 1.5, 0.1)), None),
+    ];
+    for (wrapper, expected) in wrappers {
+        let hit = hit_on(wrapper);
+        match (hit.material.diffuse(&hit), expected) {
+            (Some(a), Some(b)) => assert!((a - b).length() < 1e-9),
+            (a, b) => assert!(a.is_none() && b.is_none()),
+        }
+    }
+}
+
+#[test]
+fn mix_over_metal_sees_point_lights() {
+    let gray = Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5)));
+    let mirror = Arc::new(Metal::new(WHITE, 0.0));
+    let worn = Arc::new(Mix::new(gray, mirror, 0.5));
+
+    let camera = PerspectiveCamera::new(
+        Vector(0.0, 0.0, 5.0),
+        Vector(0.0, 0.0, 0.0),
+        Vector(0.0, 1.0, 0.0),
+        40.0,
+        ray_tracer::ASPECT_RATIO,
+        0.0,
+        5.0,
+    );
+    let objects: Vec<Box<dyn Hittable>> =
+        vec![Box::new(Sphere::new(&Vector(0.0, 0.0, 0.0), 1.0, worn))];
+    let mut scene = Scene::new(Box::new(camera), objects, String::new());
+    scene.add_light(Light::point(Vector(1.0, 1.0, 3.0), WHITE, 5.0, 0.0));
+
+    // the paint half still picks up the light the mirror half can't
+    let ray = Ray::new(Vector(0.0, 0.0, 5.0), Vector(0.0, 0.0, -1.0));
+    let hit = scene.check_hits(&ray).unwrap();
+    let lit = scene.direct_light(&ray, &hit);
+    assert!(lit.x() > 0.0 && lit.y() > 0.0 && lit.z() > 0.0);
+    assert!(hit.material.pdf(&ray, &hit, hit.normal).is_some());
+}
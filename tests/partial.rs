@@ -0,0 +1,137 @@
+// Partial renders are written by separate processes and merged afterwards, so
+// each one has to hold exactly what a normal pass would have rendered.
+use std::sync::Arc;
+
+use ray_tracer::bdpt::Bidirectional;
+use ray_tracer::camera::PerspectiveCamera;
+use ray_tracer::hittable::Hittable;
+use ray_tracer::light::{Falloff, Light};
+use ray_tracer::materials::{dielectric::Dielectric, lambertian::Lambertian};
+use ray_tracer::partial::Partial;
+use ray_tracer::scene::Scene;
+use ray_tracer::sphere::Sphere;
+use ray_tracer::utils;
+use ray_tracer::vector::Vector;
+
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("ray-tracer-{}-{}", std::process::id(), name))
+        .to_string_lossy()
+        .into_owned()
+}
+
+// a lamp over a glass ball on the ground, which focuses a caustic that only
+// light paths splatted onto the image pick up
+fn lit_scene() -> Scene {
+    let camera = PerspectiveCamera::new(
+        Vector(0.0, 1.0, 4.0),
+        Vector(0.0, 0.3, 0.0),
+        Vector(0.0, 1.0, 0.0),
+        40.0,
+        ray_tracer::ASPECT_RATIO,
+        0.0,
+        4.0,
+    );
+    let gray = Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5)));
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere::new(&Vector(0.0, -1000.0, 0.0), 1000.0, gray)),
+        Box::new(Sphere::new(
+            &Vector(0.0, 0.5, 0.0),
+            0.5,
+            Arc::new(Dielectric::new(1.5)),
+        )),
+    ];
+
+    let mut scene = Scene::new(Box::new(camera), objects, String::new());
+    scene.width = 16;
+    scene.height = 9;
+    scene.settings.samples = 2;
+    scene.add_light(Light::Point {
+        position: Vector(1.0, 2.0, 1.0),
+        color: Vector(1.0, 1.0, 1.0),
+        intensity: 5.0,
+        radius: 0.0,
+        falloff: Falloff::default(),
+    });
+    scene
+}
+
+#[test]
+fn partial_render_matches_a_pass() {
+    let mut scene = lit_scene();
+    // light paths land on pixels other than the one being sampled
+    scene.set_integrator(Box::new(Bidirectional));
+
+    let path = temp_path("pass.partial");
+    scene.render_partial(&path, 11).unwrap();
+    let partial = Partial::read(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    utils::seed_rng(11);
+    scene.render_pass();
+    let pass = scene.framebuffer().estimate();
+
+    assert_eq!(partial.samples, 2);
+    assert_eq!(partial.pixels.len(), pass.len());
+    for (a, b) in partial.pixels.iter().zip(&pass) {
+        assert!((*a - *b).length() < 1e-9);
+    }
+}
+
+fn flat(width: u32, height: u32, samples: u64, color: Vector) -> Partial {
+    Partial {
+        width,
+        height,
+        samples,
+        pixels: vec![color; (width * height) as usize],
+    }
+}
+
+#[test]
+fn partial_round_trips_through_a_file() {
+    let mut partial = flat(3, 2, 64, Vector(0.0, 0.0, 0.0));
+    // unclamped radiance, as written before tone mapping
+    partial.pixels[1] = Vector(0.25, 12.5, 1e-9);
+    partial.pixels[5] = Vector(1e6, 0.5, 0.125);
+
+    let path = temp_path("round-trip.partial");
+    partial.write(&path).unwrap();
+    let read = Partial::read(&path).unwrap();
+
+    assert_eq!((read.width, read.height, read.samples), (3, 2, 64));
+    for (a, b) in partial.pixels.iter().zip(&read.pixels) {
+        assert_eq!([a.x(), a.y(), a.z()], [b.x(), b.y(), b.z()]);
+    }
+
+    // anything cut short or not written by `write` is refused
+    let data = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &data[..data.len() - 8]).unwrap();
+    assert!(Partial::read(&path).is_err());
+    std::fs::write(&path, b"not a partial").unwrap();
+    assert!(Partial::read(&path).is_err());
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn merge_weights_parts_by_sample_count() {
+    let parts = [
+        flat(2, 2, 100, Vector(1.0, 0.0, 0.0)),
+        flat(2, 2, 300, Vector(0.0, 1.0, 0.0)),
+    ];
+    let merged = Partial::merge(&parts).unwrap();
+
+    assert_eq!(merged.samples, 400);
+    for p in &merged.pixels {
+        assert!((*p - Vector(0.25, 0.75, 0.0)).length() < 1e-12);
+    }
+}
+
+#[test]
+fn merge_rejects_mismatched_sizes() {
+    let parts = [
+        flat(4, 2, 10, Vector(1.0, 1.0, 1.0)),
+        flat(2, 4, 10, Vector(1.0, 1.0, 1.0)),
+    ];
+    assert!(Partial::merge(&parts).is_none());
+    assert!(Partial::merge(&[]).is_none());
+}
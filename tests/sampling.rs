@@ -0,0 +1,133 @@
+// Samplers hand out the random numbers of each pixel sample; these check the
+// sequences themselves and that successive passes keep drawing new samples.
+use std::sync::Arc;
+
+use ray_tracer::camera::PerspectiveCamera;
+use ray_tracer::hittable::Hittable;
+use ray_tracer::materials::lambertian::Lambertian;
+use ray_tracer::sampler::{BlueNoiseSampler, Sampler, SobolSampler, StratifiedSampler};
+use ray_tracer::scene::Scene;
+use ray_tracer::sphere::Sphere;
+use ray_tracer::vector::Vector;
+
+// a sphere against the sky, whose color changes across every pixel
+fn small_scene() -> Scene {
+    let camera = PerspectiveCamera::new(
+        Vector(0.0, 0.0, 4.0),
+        Vector(0.0, 0.0, 0.0),
+        Vector(0.0, 1.0, 0.0),
+        40.0,
+        ray_tracer::ASPECT_RATIO,
+        0.0,
+        4.0,
+    );
+    let objects: Vec<Box<dyn Hittable>> = vec![Box::new(Sphere::new(
+        &Vector(0.0, 0.0, 0.0),
+        0.5,
+        Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5))),
+    ))];
+
+    let mut scene = Scene::new(Box::new(camera), objects, String::new());
+    scene.width = 16;
+    scene.height = 9;
+    scene.settings.samples = 2;
+    scene
+}
+
+#[test]
+fn blue_noise_passes_take_new_samples() {
+    let mut scene = small_scene();
+    scene.set_sampler(Box::new(BlueNoiseSampler::new(3)));
+
+    scene.render_pass();
+    let first = scene.framebuffer().estimate();
+    scene.render_pass();
+    let both = scene.framebuffer().estimate();
+
+    // repeating the first pass's samples would leave the average unchanged
+    let changed = first
+        .iter()
+        .zip(&both)
+        .filter(|(a, b)| (**a - **b).length() > 0.0)
+        .count();
+    assert!(changed > first.len() / 2, "only {} pixels changed", changed);
+}
+
+// how many of `values` fall in each of `bins` equal intervals of [0, 1)
+fn histogram(values: impl Iterator<Item = f64>, bins: usize) -> Vec<usize> {
+    let mut counts = vec![0; bins];
+    for v in values {
+        assert!((0.0..1.0).contains(&v), "{} is outside [0, 1)", v);
+        counts[(v * bins as f64) as usize] += 1;
+    }
+    counts
+}
+
+#[test]
+fn stratified_samples_fill_every_stratum() {
+    let sampler = StratifiedSampler;
+    let pixel = (3, 4);
+
+    // the pixel position of 16 samples covers a 4 x 4 grid
+    let mut cells = vec![0; 16];
+    for index in 0..16 {
+        let x = sampler.get(pixel, index, 16, 0, 77);
+        let y = sampler.get(pixel, index, 16, 1, 77);
+        cells[(y * 4.0) as usize * 4 + (x * 4.0) as usize] += 1;
+    }
+    assert!(cells.iter().all(|&c| c == 1), "{:?}", cells);
+
+    // other dimensions are shuffled differently but stay one per stratum,
+    // in the next pass too
+    for dim in 2..6 {
+        for pass in 0..2 {
+            let values = (0..10).map(|i| sampler.get(pixel, pass * 10 + i, 10, dim, 77));
+            assert!(histogram(values, 10).iter().all(|&c| c == 1));
+        }
+    }
+}
+
+#[test]
+fn sobol_prefixes_are_stratified_in_two_dimensions() {
+    let sampler = SobolSampler::new();
+    for scramble in [0, 12345] {
+        for dims in [(0, 1), (2, 3), (4, 5)] {
+            let mut cells = vec![0; 16];
+            for index in 0..16 {
+                let x = sampler.get((0, 0), index, 16, dims.0, scramble);
+                let y = sampler.get((0, 0), index, 16, dims.1, scramble);
+                assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+                cells[(y * 4.0) as usize * 4 + (x * 4.0) as usize] += 1;
+            }
+            assert!(
+                cells.iter().all(|&c| c == 1),
+                "dims {:?}: {:?}",
+                dims,
+                cells
+            );
+        }
+    }
+}
+
+#[test]
+fn blue_noise_spreads_a_sample_evenly_over_a_tile() {
+    let sampler = BlueNoiseSampler::new(1);
+    // the texture holds every rank once, so one tile of pixels takes every
+    // value of a dimension equally often
+    for dim in 0..3 {
+        let values = (0..64 * 64).map(|i| sampler.get((i % 64, i / 64), 0, 1, dim, 0));
+        assert!(histogram(values, 16).iter().all(|&c| c == 256));
+    }
+
+    // deterministic for a seed, so renders split by seed can be merged
+    let again = BlueNoiseSampler::new(1);
+    let other = BlueNoiseSampler::new(2);
+    assert_eq!(
+        sampler.get((5, 9), 3, 4, 2, 0),
+        again.get((5, 9), 3, 4, 2, 0)
+    );
+    assert_ne!(
+        sampler.get((5, 9), 3, 4, 2, 0),
+        other.get((5, 9), 3, 4, 2, 0)
+    );
+}
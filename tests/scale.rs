@@ -0,0 +1,107 @@
+// The same scene rendered at very different scales should look the same: any
+// absolute distance in the intersection code (like the self-intersection
+// offset) shows up as acne or light leaks at one end of the range.
+use std::sync::Arc;
+
+use ray_tracer::camera::PerspectiveCamera;
+use ray_tracer::hittable::Hittable;
+use ray_tracer::materials::{lambertian::Lambertian, metal::Metal};
+use ray_tracer::scene::Scene;
+use ray_tracer::sphere::Sphere;
+use ray_tracer::utils;
+use ray_tracer::vector::Vector;
+
+const WIDTH: i32 = 48;
+const HEIGHT: i32 = 27;
+const SAMPLES: i32 = 8;
+
+fn render_at_scale(s: f64) -> Vec<Vector> {
+    let camera = PerspectiveCamera::new(
+        s * Vector(0.0, 1.2, 4.0),
+        s * Vector(0.0, 0.4, 0.0),
+        Vector(0.0, 1.0, 0.0),
+        40.0,
+        ray_tracer::ASPECT_RATIO,
+        0.0,
+        s * 4.0,
+    );
+
+    let ground = Arc::new(Lambertian::new(Vector(0.5, 0.5, 0.5)));
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere::new(
+            &(s * Vector(0.0, -1000.0, 0.0)),
+            s * 1000.0,
+            ground,
+        )),
+        // resting on the ground, the contact shadow is where offsets show first
+        Box::new(Sphere::new(
+            &(s * Vector(-0.6, 0.5, 0.0)),
+            s * 0.5,
+            Arc::new(Lambertian::new(Vector(0.7, 0.3, 0.3))),
+        )),
+        Box::new(Sphere::new(
+            &(s * Vector(0.6, 0.4, 0.3)),
+            s * 0.4,
+            Arc::new(Metal::new(Vector(0.8, 0.8, 0.8), 0.1)),
+        )),
+        // nearly touching, a 0.1% gap
+        Box::new(Sphere::new(
+            &(s * Vector(0.0, 0.101, 0.8)),
+            s * 0.1,
+            Arc::new(Lambertian::new(Vector(0.3, 0.3, 0.7))),
+        )),
+    ];
+
+    let mut scene = Scene::new(Box::new(camera), objects, String::new());
+    scene.width = WIDTH;
+    scene.height = HEIGHT;
+
+    utils::seed_rng(7);
+    let mut pixels = Vec::new();
+    for y in (0..HEIGHT).rev() {
+        for x in 0..WIDTH {
+            pixels.push(scene.antialias_color(SAMPLES, x, y));
+        }
+    }
+    pixels
+}
+
+fn mean_difference(a: &[Vector], b: &[Vector]) -> f64 {
+    let total: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(p, q)| {
+            let d = *p - *q;
+            (d.x().abs() + d.y().abs() + d.z().abs()) / 3.0
+        })
+        .sum();
+    total / a.len() as f64
+}
+
+fn assert_similar(scale: f64) {
+    let reference = render_at_scale(1.0);
+    let scaled = render_at_scale(scale);
+    let difference = mean_difference(&reference, &scaled);
+
+    assert!(
+        difference < 0.01,
+        "render at {}x differs from 1x by {:.4} on average",
+        scale,
+        difference
+    );
+}
+
+#[test]
+fn small_scene_matches_unit_scene() {
+    assert_similar(0.01);
+}
+
+#[test]
+fn large_scene_matches_unit_scene() {
+    assert_similar(1000.0);
+}
+
+#[test]
+fn unit_scene_is_deterministic() {
+    assert!(mean_difference(&render_at_scale(1.0), &render_at_scale(1.0)) == 0.0);
+}
@@ -0,0 +1,146 @@
+// Textures are looked up through `Texture::sample`, which carries the surface
+// coordinates, the world and object space points and the normal; wrappers
+// have to pass all of it on.
+use std::sync::Arc;
+
+use ray_tracer::hittable::Hittable;
+use ray_tracer::instance::Instance;
+use ray_tracer::materials::lambertian::Lambertian;
+use ray_tracer::ray::Ray;
+use ray_tracer::sphere::Sphere;
+use ray_tracer::texture::{
+    AddNode, Channel, Checker, ClampNode, Interpolation, InvertNode, MixNode, MultiplyNode, Ramp,
+    SolidColor, Texture, TexturePoint, TextureSpace, Triplanar, UvTransform,
+};
+use ray_tracer::transform::Transform;
+use ray_tracer::vector::Vector;
+
+const BLACK: Vector = Vector(0.0, 0.0, 0.0);
+const WHITE: Vector = Vector(1.0, 1.0, 1.0);
+
+fn close(a: Vector, b: Vector) -> bool {
+    (a - b).length() < 1e-9
+}
+
+fn checker(space: TextureSpace) -> Checker {
+    let mut checker = Checker::solid(BLACK.into(), WHITE.into(), 1.0);
+    checker.set_space(space);
+    checker
+}
+
+#[test]
+fn channels_pick_one_component_of_a_packed_map() {
+    // occlusion, roughness, metallic as glTF packs them
+    let packed = Vector(0.9, 0.4, 1.0);
+    assert_eq!(Channel::Red.pick(packed), 0.9);
+    assert_eq!(Channel::Green.pick(packed), 0.4);
+    assert_eq!(Channel::Blue.pick(packed), 1.0);
+    assert!((Channel::Luminance.pick(0.5 * WHITE) - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn triplanar_projects_along_the_facing_axis() {
+    let texture = Triplanar::new(Arc::new(checker(TextureSpace::Uv)), 1.0);
+    let on_floor = |x: f64, z: f64| TexturePoint {
+        normal: Vector(0.0, 1.0, 0.0),
+        ..TexturePoint::new(0.0, 0.0, Vector(x, 7.5, z))
+    };
+    // a floor sees the checker laid out over x and z, whatever its own (u, v)
+    assert!(close(texture.sample(&on_floor(0.5, 0.5)), BLACK));
+    assert!(close(texture.sample(&on_floor(1.5, 0.5)), WHITE));
+    assert!(close(texture.sample(&on_floor(1.5, 1.5)), BLACK));
+
+    // halfway between two faces both projections count equally
+    let edge = TexturePoint {
+        normal: Vector(1.0, 1.0, 0.0).to_unit_vector(),
+        ..TexturePoint::new(0.0, 0.0, Vector(0.5, 1.5, 0.5))
+    };
+    assert!(close(texture.sample(&edge), 0.5 * WHITE));
+}
+
+#[test]
+fn instances_keep_the_object_space_point() {
+    let sphere = Sphere::new(
+        &Vector(0.0, 0.0, 0.0),
+        0.5,
+        Arc::new(Lambertian::new(WHITE)),
+    );
+    let mut instance = Instance::new(Arc::new(sphere));
+    instance.set_transform(Transform::translate(Vector(11.25, 0.0, 0.0)));
+
+    let ray = Ray::new(Vector(11.25, 0.0, 5.0), Vector(0.0, 0.0, -1.0));
+    let hit = instance.ray_intersect(&ray, 1e-6, f64::INFINITY).unwrap();
+    assert!(close(hit.p, Vector(11.25, 0.0, 0.5)));
+    assert!(close(hit.local, Vector(0.0, 0.0, 0.5)));
+
+    // the world space cube is x in [11, 12), the object space one [0, 1)
+    let at = TexturePoint::from_hit(&hit);
+    assert!(close(checker(TextureSpace::World).sample(&at), WHITE));
+    assert!(close(checker(TextureSpace::Object).sample(&at), BLACK));
+}
+
+#[test]
+fn uv_space_ignores_the_point() {
+    let texture = checker(TextureSpace::Uv);
+    let far = Vector(123.5, -7.25, 3.0);
+    assert!(close(texture.value(0.5, 0.5, far), BLACK));
+    assert!(close(texture.value(1.5, 0.5, far), WHITE));
+}
+
+#[test]
+fn uv_transform_repeats_shifts_and_rotates() {
+    let mut placed = UvTransform::new(Arc::new(checker(TextureSpace::Uv)));
+    assert_eq!(placed.uv(0.25, 0.75), (0.25, 0.75));
+
+    placed.set_repeat(4.0, 2.0);
+    placed.set_offset(1.0, 0.0);
+    assert_eq!(placed.uv(0.25, 0.75), (2.0, 1.5));
+    assert!(close(placed.value(0.25, 0.75, BLACK), WHITE));
+
+    // a quarter turn about the middle takes the right edge to the top
+    let mut turned = UvTransform::new(Arc::new(checker(TextureSpace::Uv)));
+    turned.set_rotation(90.0);
+    let (u, v) = turned.uv(1.0, 0.5);
+    assert!((u - 0.5).abs() < 1e-9 && (v - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn ramp_interpolates_between_stops() {
+    let ramp = |interpolation| {
+        let mut ramp = Ramp::new(Arc::new(SolidColor::scalar(0.75)), interpolation);
+        ramp.add_stop(1.0, WHITE);
+        ramp.add_stop(0.5, BLACK);
+        ramp
+    };
+
+    let linear = ramp(Interpolation::Linear);
+    assert!(close(linear.value(0.0, 0.0, BLACK), 0.5 * WHITE));
+    assert!(close(linear.color(0.0), BLACK));
+    assert!(close(linear.color(2.0), WHITE));
+
+    assert!(close(ramp(Interpolation::Constant).color(0.99), BLACK));
+    assert!(close(
+        ramp(Interpolation::Smooth).color(0.625),
+        0.15625 * WHITE
+    ));
+}
+
+#[test]
+fn nodes_combine_their_inputs_at_the_lookup_point() {
+    let red: Arc<dyn Texture> = Vector(1.0, 0.0, 0.0).into();
+    let mask: Arc<dyn Texture> = Arc::new(checker(TextureSpace::Uv));
+
+    // red where the mask is white, plus a quarter gray lift, kept in range
+    let tinted = Arc::new(MultiplyNode::new(red.clone(), mask.clone()));
+    let lift = Arc::new(InvertNode::new(Arc::new(SolidColor::scalar(0.75))));
+    let graph = ClampNode::new(Arc::new(AddNode::new(tinted, lift)), 0.0, 1.0);
+    assert!(close(
+        graph.value(0.5, 0.5, BLACK),
+        Vector(0.25, 0.25, 0.25)
+    ));
+    assert!(close(graph.value(1.5, 0.5, BLACK), Vector(1.0, 0.25, 0.25)));
+
+    let mix = MixNode::new(red, WHITE.into(), mask);
+    assert!(close(mix.value(0.5, 0.5, BLACK), Vector(1.0, 0.0, 0.0)));
+    assert!(close(mix.value(1.5, 0.5, BLACK), WHITE));
+}